@@ -1,10 +1,8 @@
 """Helper utilities for normalizing measurement units.
 
-TODO: Migrate this module to Rust.
-
 This Python implementation is a fallback used when running the scoring
-pipeline outside of the WebAssembly build. The Rust version will live in
-``rust/src`` and expose equivalent functions via a Python FFI layer.
+pipeline outside of the WebAssembly build. The Rust engine performs the same
+conversions in ``rust/src/units.rs``; keep the two scale tables in sync.
 """
 
 import json
@@ -0,0 +1,186 @@
+//! Energy adjustment of intakes before scoring.
+//!
+//! Three methods are supported besides leaving intakes as reported:
+//!
+//! * nutrient density: energy-yielding nutrients as percent of energy;
+//! * density per 1,000 kcal: every intake divided by energy in thousands;
+//! * residual method (Willett): each intake regressed on energy across the
+//!   batch, keeping the residual plus the intake predicted at mean energy.
+//!
+//! A scorer may also declare intakes rescaled to the energy of a reference
+//! diet ([`EnergyAdjustment::PerReferenceKcal`]); runs do not choose it.
+//!
+//! Only intakes are adjusted. Energy itself and fields measured in years,
+//! codes, flags or counts (see `schema/default_units.json`) pass through.
+
+use crate::nutrition_vector::NutritionVector;
+use crate::units::canonical_unit;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EnergyAdjustment {
+    /// Intakes as reported.
+    #[default]
+    None,
+    /// Energy-yielding nutrients as percent of energy; other intakes as
+    /// reported.
+    NutrientDensity,
+    /// Every intake per 1,000 kcal.
+    PerThousandKcal,
+    /// Every intake rescaled to a diet of this many kcal, e.g. the 2,500 kcal
+    /// EAT-Lancet reference diet of PHDI_ENERGY_ADJUSTED. Declared by
+    /// scorers only; runs cannot choose it by name.
+    PerReferenceKcal(u32),
+    /// Residuals of a regression on energy across the batch, re-centred on the
+    /// intake predicted at the batch mean energy.
+    Residual,
+}
+
+impl EnergyAdjustment {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnergyAdjustment::None => "none",
+            EnergyAdjustment::NutrientDensity => "nutrient_density",
+            EnergyAdjustment::PerThousandKcal => "per_1000_kcal",
+            EnergyAdjustment::PerReferenceKcal(_) => "per_reference_kcal",
+            EnergyAdjustment::Residual => "residual",
+        }
+    }
+
+    /// Method a run can choose by name; `density` is short for
+    /// `nutrient_density`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name == "density" {
+            return Some(EnergyAdjustment::NutrientDensity);
+        }
+        [
+            EnergyAdjustment::None,
+            EnergyAdjustment::NutrientDensity,
+            EnergyAdjustment::PerThousandKcal,
+            EnergyAdjustment::Residual,
+        ]
+        .into_iter()
+        .find(|m| m.name() == name)
+    }
+}
+
+/// Energy per gram of the nutrients expressed as percent of energy by
+/// [`EnergyAdjustment::NutrientDensity`].
+pub const KCAL_PER_GRAM: &[(&str, f64)] = &[
+    ("added_fat_sat_trans", 9.0),
+    ("added_fat_unsat", 9.0),
+    ("added_sugar_g", 4.0),
+    ("alcohol", 7.0),
+    ("carbs", 4.0),
+    ("fat", 9.0),
+    ("mono_fat", 9.0),
+    ("protein", 4.0),
+    ("pufa", 9.0),
+    ("saturated_fat", 9.0),
+    ("sugar", 4.0),
+    ("trans_fat", 9.0),
+];
+
+const NON_INTAKE_UNITS: &[&str] = &["kcal", "years", "code", "flag", "count"];
+
+static INTAKE_FIELDS: Lazy<Vec<&'static str>> = Lazy::new(|| {
+    NutritionVector::all_field_names()
+        .iter()
+        .copied()
+        .filter(|f| canonical_unit(f).is_some_and(|u| !NON_INTAKE_UNITS.contains(&u)))
+        .collect()
+});
+
+/// Canonical fields that energy adjustment applies to.
+pub fn intake_fields() -> &'static [&'static str] {
+    INTAKE_FIELDS.as_slice()
+}
+
+/// Adjusts one record. The residual method needs a batch, so it leaves the
+/// record unchanged here; use [`adjust_batch`].
+pub fn adjust(nv: &NutritionVector, method: EnergyAdjustment) -> NutritionVector {
+    let mut out = nv.clone();
+    for field in intake_fields() {
+        if let Some(slot) = out.field_mut(field) {
+            adjust_values(field, std::slice::from_mut(slot), &[nv.energy], method);
+        }
+    }
+    out
+}
+
+/// Adjusts every record of `batch`. Records without a positive energy are
+/// left unchanged and, for the residual method, do not enter the regression.
+pub fn adjust_batch(batch: &[NutritionVector], method: EnergyAdjustment) -> Vec<NutritionVector> {
+    let mut out = batch.to_vec();
+    let energy: Vec<Option<f64>> = batch.iter().map(|nv| nv.energy).collect();
+    for field in intake_fields() {
+        let mut values: Vec<Option<f64>> = batch.iter().map(|nv| nv.get(field)).collect();
+        adjust_values(field, &mut values, &energy, method);
+        for (nv, value) in out.iter_mut().zip(values) {
+            if let Some(slot) = nv.field_mut(field) {
+                *slot = value;
+            }
+        }
+    }
+    out
+}
+
+/// Adjusts the values of `field` of a batch, one per record, given the
+/// `energy` of each record. Missing values stay missing; records without a
+/// positive energy are left unchanged and, for the residual method, do not
+/// enter the regression. Fields that are not intakes are left unchanged.
+pub fn adjust_values(
+    field: &str,
+    values: &mut [Option<f64>],
+    energy: &[Option<f64>],
+    method: EnergyAdjustment,
+) {
+    if !intake_fields().contains(&field) {
+        return;
+    }
+    let points: Vec<(usize, f64, f64)> = values
+        .iter()
+        .zip(energy)
+        .enumerate()
+        .filter_map(|(i, pair)| match pair {
+            (Some(value), Some(kcal)) if *kcal > 0.0 => Some((i, *kcal, *value)),
+            _ => None,
+        })
+        .collect();
+    let rescale = |values: &mut [Option<f64>], per_kcal: f64| {
+        for &(i, kcal, value) in &points {
+            values[i] = Some(value * per_kcal / kcal);
+        }
+    };
+    match method {
+        EnergyAdjustment::None => {}
+        EnergyAdjustment::NutrientDensity => {
+            if let Some((_, kcal_per_g)) = KCAL_PER_GRAM.iter().find(|(f, _)| *f == field) {
+                rescale(values, kcal_per_g * 100.0);
+            }
+        }
+        EnergyAdjustment::PerThousandKcal => rescale(values, 1000.0),
+        EnergyAdjustment::PerReferenceKcal(kcal) => rescale(values, f64::from(kcal)),
+        EnergyAdjustment::Residual => {
+            if points.is_empty() {
+                return;
+            }
+            let n = points.len() as f64;
+            let mean_kcal = points.iter().map(|p| p.1).sum::<f64>() / n;
+            let mean_value = points.iter().map(|p| p.2).sum::<f64>() / n;
+            let sxx: f64 = points.iter().map(|p| (p.1 - mean_kcal).powi(2)).sum();
+            let sxy: f64 = points
+                .iter()
+                .map(|p| (p.1 - mean_kcal) * (p.2 - mean_value))
+                .sum();
+            // Without spread in energy there is nothing to regress on.
+            let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+            for (i, kcal, value) in points {
+                // residual + prediction at mean energy
+                values[i] = Some(value - slope * (kcal - mean_kcal));
+            }
+        }
+    }
+}
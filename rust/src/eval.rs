@@ -1,6 +1,9 @@
 use crate::contracts;
+use crate::energy::EnergyAdjustment;
 use crate::nutrition_vector::{InputTrace, NutritionVector, SchemaError};
-use crate::scores::all_scorers;
+use crate::scores::batch::CohortInputs;
+use crate::scores::registry::{all_batch_scorers, cohort_fields};
+use crate::scores::{all_scorers, ComponentScore, ENERGY_NOT_POSITIVE};
 use serde::Serialize;
 use std::collections::HashMap;
 
@@ -9,6 +12,9 @@ pub struct ScoreInfo {
     pub value: Option<f64>,
     pub valid: bool,
     pub explanation: Option<String>,
+    /// Component table of the index, empty when it has none or no value.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ComponentScore>,
 }
 
 #[derive(Debug, Serialize, PartialEq, Clone)]
@@ -17,7 +23,7 @@ pub struct IndexError {
     pub missing_fields: Vec<&'static str>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct ScoreResult {
     pub scores: HashMap<String, ScoreInfo>,
     pub ordered_names: Vec<String>,
@@ -25,8 +31,60 @@ pub struct ScoreResult {
     pub errors: Vec<IndexError>,
 }
 
+impl ScoreResult {
+    /// Adds the indices of `other`, e.g. the cohort scores of
+    /// [`evaluate_cohort_scores`], after those already present. Its trace is
+    /// dropped.
+    pub fn merge(&mut self, other: ScoreResult) {
+        self.scores.extend(other.scores);
+        self.ordered_names.extend(other.ordered_names);
+        self.errors.extend(other.errors);
+    }
+}
+
+/// Checks a computed value against the contract range of `name`.
+fn checked_score(name: &str, value: f64, explanation: Option<String>) -> ScoreInfo {
+    let range = contracts::range(name);
+    let mut valid = true;
+    let mut explanation = explanation;
+    if value.is_nan() {
+        valid = false;
+        explanation = Some("logic bug: produced NaN".to_string());
+    } else if !range[0].is_nan() && (value < range[0] || value > range[1]) {
+        valid = false;
+        explanation = Some(format!("out of range [{}, {}]", range[0], range[1]));
+    }
+    ScoreInfo {
+        value: Some(value),
+        valid,
+        explanation,
+        components: Vec::new(),
+    }
+}
+
+fn missing_score(missing_fields: &[&str]) -> ScoreInfo {
+    ScoreInfo {
+        value: None,
+        valid: false,
+        explanation: Some(format!("missing fields: {}", missing_fields.join(", "))),
+        components: Vec::new(),
+    }
+}
+
+fn not_applicable_score(reason: &str) -> ScoreInfo {
+    ScoreInfo {
+        value: None,
+        valid: false,
+        explanation: Some(format!("not applicable: {}", reason)),
+        components: Vec::new(),
+    }
+}
+
+/// Scores `nv` on every per-record index, or fails with the fields that
+/// some index requires and `nv` is missing, see
+/// [`NutritionVector::missing_required_fields`].
 pub fn evaluate_all_scores(nv: &NutritionVector) -> Result<ScoreResult, SchemaError> {
-    let missing = nv.missing_fields();
+    let missing = nv.missing_required_fields();
     if !missing.is_empty() {
         return Err(SchemaError::new(missing, Vec::new(), Vec::new()));
     }
@@ -48,29 +106,20 @@ pub fn evaluate_allow_partial(nv: &NutritionVector) -> ScoreResult {
             .filter(|f| missing.contains(f))
             .collect();
         missing_fields.sort();
-        let info = if missing_fields.is_empty() {
-            let value = calc.evaluate(nv);
-            let range = contracts::range(&name);
-            let mut valid = true;
-            let mut explanation = None;
-            if value.is_nan() {
-                valid = false;
-                explanation = Some("logic bug: produced NaN".to_string());
-            } else if !range[0].is_nan() && (value < range[0] || value > range[1]) {
-                valid = false;
-                explanation = Some(format!("out of range [{}, {}]", range[0], range[1]));
-            }
-            ScoreInfo {
-                value: Some(value),
-                valid,
-                explanation,
-            }
+        let not_applicable = if missing_fields.is_empty() {
+            calc.not_applicable(nv)
         } else {
+            None
+        };
+        let info = if let Some(reason) = not_applicable {
+            not_applicable_score(&reason)
+        } else if missing_fields.is_empty() {
             ScoreInfo {
-                value: None,
-                valid: false,
-                explanation: Some(format!("missing fields: {}", missing_fields.join(", "))),
+                components: calc.components(nv),
+                ..checked_score(&name, calc.evaluate(nv), calc.explain(nv))
             }
+        } else {
+            missing_score(&missing_fields)
         };
         if !missing_fields.is_empty() {
             errors.push(IndexError {
@@ -89,6 +138,173 @@ pub fn evaluate_allow_partial(nv: &NutritionVector) -> ScoreResult {
     }
 }
 
+/// Scores every record of a dataset with the per-record scorers, as
+/// [`evaluate_allow_partial`] does, and with the population-based scorers of
+/// [`all_batch_scorers`]. Records missing a field a batch scorer requires are
+/// skipped for that index and left out of its distribution statistics.
+pub fn evaluate_batch_allow_partial(batch: &[NutritionVector]) -> Vec<ScoreResult> {
+    evaluate_batch_with_adjustment(batch, None)
+}
+
+/// Like [`evaluate_batch_allow_partial`], with the energy adjustment applied
+/// to the inputs of batch scorers chosen for the run. `None` keeps the
+/// adjustment each scorer declares. Under an adjustment, records without a
+/// positive energy are left out of the batch: missing `energy` when it is
+/// absent, not applicable otherwise. Per-record scorers always receive
+/// intakes as reported and apply the adjustment they declare themselves.
+pub fn evaluate_batch_with_adjustment(
+    batch: &[NutritionVector],
+    adjustment: Option<EnergyAdjustment>,
+) -> Vec<ScoreResult> {
+    batch
+        .iter()
+        .map(evaluate_allow_partial)
+        .zip(evaluate_cohort_scores(batch, adjustment))
+        .map(|(mut result, cohort)| {
+            result.merge(cohort);
+            result
+        })
+        .collect()
+}
+
+/// The population-based scores of [`evaluate_batch_with_adjustment`] alone:
+/// one result per record of `batch` holding only the indices of
+/// [`all_batch_scorers`], with an empty trace.
+pub fn evaluate_cohort_scores(
+    batch: &[NutritionVector],
+    adjustment: Option<EnergyAdjustment>,
+) -> Vec<ScoreResult> {
+    let cohort = evaluate_cohort(
+        &CohortInputs::from_records(batch, &cohort_fields()),
+        adjustment,
+    );
+    batch
+        .iter()
+        .enumerate()
+        .filter_map(|(i, nv)| cohort.result(i, &nv.missing_fields()))
+        .collect()
+}
+
+/// Scores the indices of [`all_batch_scorers`] on `inputs`, which must keep
+/// the [`cohort_fields`], with the energy adjustment of
+/// [`evaluate_batch_with_adjustment`].
+pub fn evaluate_cohort(
+    inputs: &CohortInputs,
+    adjustment: Option<EnergyAdjustment>,
+) -> CohortScores {
+    let indices = all_batch_scorers()
+        .iter()
+        .map(|calc| {
+            let required = calc.required_fields();
+            let method = adjustment.unwrap_or_else(|| calc.energy_adjustment());
+            let mut complete = Vec::new();
+            let mut not_applicable = HashMap::new();
+            for i in 0..inputs.len() {
+                if !required.iter().all(|f| inputs.get(i, f).is_some()) {
+                    continue;
+                }
+                let energy_positive = inputs.get(i, "energy").is_some_and(|kcal| kcal > 0.0);
+                let reason = calc.not_applicable(inputs.row(i)).or_else(|| {
+                    (method != EnergyAdjustment::None && !energy_positive)
+                        .then(|| ENERGY_NOT_POSITIVE.to_string())
+                });
+                match reason {
+                    Some(reason) => {
+                        not_applicable.insert(i, reason);
+                    }
+                    None => complete.push(i),
+                }
+            }
+            let mut subset = inputs.select(&complete);
+            subset.adjust(method);
+            let mut values = vec![f64::NAN; inputs.len()];
+            for (&i, value) in complete.iter().zip(calc.evaluate_inputs(&subset, method)) {
+                values[i] = value;
+            }
+            CohortIndex {
+                name: calc.name(),
+                required,
+                method,
+                values,
+                not_applicable,
+            }
+        })
+        .collect();
+    CohortScores { indices }
+}
+
+struct CohortIndex {
+    name: &'static str,
+    required: &'static [&'static str],
+    method: EnergyAdjustment,
+    /// Score of each record, NaN when it was left out of the batch.
+    values: Vec<f64>,
+    /// Records the index is not defined for, with the reason.
+    not_applicable: HashMap<usize, String>,
+}
+
+/// Scores of [`evaluate_cohort`]: one value per index and record.
+pub struct CohortScores {
+    indices: Vec<CohortIndex>,
+}
+
+impl CohortScores {
+    /// Number of records scored.
+    pub fn len(&self) -> usize {
+        self.indices.first().map_or(0, |index| index.values.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Result of record `row` holding only the cohort indices, given the
+    /// fields it is `missing`, or `None` past the last record. Records left
+    /// out of an index's batch are missing its fields, or `energy` when the
+    /// batch was energy-adjusted, or the index is not applicable to them.
+    pub fn result(&self, row: usize, missing: &[&'static str]) -> Option<ScoreResult> {
+        if row >= self.len() {
+            return None;
+        }
+        let mut result = ScoreResult::default();
+        for index in &self.indices {
+            let name = index.name.to_string();
+            let mut missing_fields: Vec<&'static str> = index
+                .required
+                .iter()
+                .copied()
+                .filter(|f| missing.contains(f))
+                .collect();
+            if index.method != EnergyAdjustment::None
+                && missing.contains(&"energy")
+                && !missing_fields.contains(&"energy")
+            {
+                missing_fields.push("energy");
+            }
+            missing_fields.sort();
+            let info = if !missing_fields.is_empty() {
+                let info = missing_score(&missing_fields);
+                result.errors.push(IndexError {
+                    index: name.clone(),
+                    missing_fields,
+                });
+                info
+            } else if let Some(reason) = index.not_applicable.get(&row) {
+                not_applicable_score(reason)
+            } else {
+                let explanation = match index.method {
+                    EnergyAdjustment::None => None,
+                    other => Some(format!("energy adjustment: {}", other.name())),
+                };
+                checked_score(&name, index.values[row], explanation)
+            };
+            result.ordered_names.push(name.clone());
+            result.scores.insert(name, info);
+        }
+        Some(result)
+    }
+}
+
 pub fn print_scores_as_json(nv: &NutritionVector) -> String {
     match evaluate_all_scores(nv) {
         Ok(result) => serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string()),
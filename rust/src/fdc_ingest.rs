@@ -0,0 +1,423 @@
+//! FoodData Central (FDC) foods and the intake summed from them.
+//!
+//! Nutrients are matched by FDC nutrient number, which is stable across the
+//! Foundation, SR Legacy, Survey (FNDDS) and Branded data types and across the
+//! full, abridged and search formats of the API. Amounts are per 100 g (per
+//! 100 ml for some branded beverages) and are converted from their `unitName`
+//! to the canonical units, see [`crate::units`]. A food's category adds its
+//! weight to a food group field such as `total_fruits` or `vegetables`.
+//!
+//! The input is a single food, scored as listed (100 g), or a list of foods
+//! with consumed amounts, summed into one day's intake:
+//!
+//! ```json
+//! {"foods": [
+//!   {"food": {"fdcId": 1750340, "foodNutrients": []}, "grams": 182},
+//!   {"food": {"fdcId": 2345678, "foodNutrients": []}, "portion": "cup", "quantity": 1.5},
+//!   {"fdcId": 1234567, "foodNutrients": [], "servings": 2}
+//! ]}
+//! ```
+//!
+//! A bare array of such items is accepted as well. `portion` is matched
+//! against the food's `foodPortions` (and a branded food's household serving)
+//! and `quantity` counts that portion as described. `servings` uses a branded
+//! food's `servingSize`, otherwise its first portion. A serving measured by
+//! volume (`servingSizeUnit` of `ml`) is rejected for `servings` and not
+//! offered as a portion, since its weight is unknown.
+
+use crate::units::{to_canonical, Converted, UnitConversion, UnitMismatch};
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// FDC nutrient numbers filling each field. Alternatives are tried in order
+/// and the first one reported by a food is used; the nutrients of an
+/// alternative are summed.
+const FDC_NUTRIENTS: &[(&str, &[&[&str]])] = &[
+    // Energy, then Atwater specific and general factors, then kilojoules.
+    ("energy", &[&["208"], &["958"], &["957"], &["268"]]),
+    ("fat", &[&["204"]]),
+    ("saturated_fat", &[&["606"]]),
+    ("mono_fat", &[&["645"]]),
+    ("pufa", &[&["646"]]),
+    ("trans_fat", &[&["605"]]),
+    // ALA (n-3 form when reported, else all 18:3), EPA, DPA and DHA.
+    (
+        "omega3",
+        &[&["851", "629", "631", "621"], &["619", "629", "631", "621"]],
+    ),
+    ("omega6", &[&["618", "620"]]),
+    ("epa_dha", &[&["629", "621"]]),
+    ("cholesterol", &[&["601"]]),
+    ("carbs", &[&["205"]]),
+    ("fiber", &[&["291"]]),
+    ("sugar", &[&["269"], &["269.3"]]),
+    ("protein", &[&["203"]]),
+    ("alcohol", &[&["221"]]),
+    ("caffeine", &[&["262"]]),
+    ("sodium", &[&["307"]]),
+    ("calcium", &[&["301"]]),
+    ("iron", &[&["303"]]),
+    ("magnesium", &[&["304"]]),
+    ("zinc", &[&["309"]]),
+    ("selenium", &[&["317"]]),
+    ("vitamin_a", &[&["320"]]),
+    ("beta_carotene", &[&["321"]]),
+    ("vitamin_e", &[&["323"]]),
+    ("vitamin_d", &[&["328"]]),
+    ("vitamin_c", &[&["401"]]),
+    ("thiamin", &[&["404"]]),
+    ("riboflavin", &[&["405"]]),
+    ("niacin", &[&["406"]]),
+    ("vitamin_b6", &[&["415"]]),
+    ("folic_acid", &[&["431"]]),
+    ("vitamin_b12", &[&["418"]]),
+];
+
+/// Numbers of nutrients given by name only, as in hand-written files.
+const FDC_NUTRIENT_NAMES: &[(&str, &str)] = &[
+    ("Energy", "208"),
+    ("Total lipid (fat)", "204"),
+    ("Fatty acids, total saturated", "606"),
+    ("Fatty acids, total monounsaturated", "645"),
+    ("Fatty acids, total polyunsaturated", "646"),
+    ("Fatty acids, total trans", "605"),
+    ("Carbohydrate, by difference", "205"),
+    ("Fiber, total dietary", "291"),
+    ("Sugars, total including NLEA", "269"),
+    ("Protein", "203"),
+    ("Sodium, Na", "307"),
+    ("Calcium, Ca", "301"),
+    ("Iron, Fe", "303"),
+    ("Magnesium, Mg", "304"),
+    ("Zinc, Zn", "309"),
+    ("Selenium, Se", "317"),
+    ("Vitamin A, RAE", "320"),
+    ("Vitamin E (alpha-tocopherol)", "323"),
+    ("Vitamin C, total ascorbic acid", "401"),
+    ("Alcohol, ethyl", "221"),
+];
+
+/// Food group fields filled with the weight of foods in an FDC food category
+/// (Foundation, SR Legacy) or branded food category, lower case.
+const FOOD_CATEGORY_GROUPS: &[(&str, &str)] = &[
+    ("fruits and fruit juices", "total_fruits"),
+    ("vegetables and vegetable products", "vegetables"),
+    ("legumes and legume products", "legumes"),
+    ("finfish and shellfish products", "fish"),
+    ("beef products", "red_meat"),
+    ("pork products", "red_meat"),
+    ("lamb, veal, and game products", "red_meat"),
+    ("poultry products", "poultry"),
+    ("nut and seed products", "nuts"),
+    ("fast foods", "fast_food"),
+    ("canned fruit", "total_fruits"),
+    ("canned vegetables", "vegetables"),
+    ("frozen vegetables", "vegetables"),
+    ("canned seafood", "fish"),
+    ("frozen fish & seafood", "fish"),
+    ("poultry, chicken & turkey", "poultry"),
+];
+
+/// Food group fields of WWEIA food category code ranges (Survey foods).
+/// Potatoes (68xx) and vegetable juice (7008) are not counted as vegetables.
+const WWEIA_GROUPS: &[(u64, u64, &str)] = &[
+    (2002, 2008, "red_meat"),
+    (2202, 2206, "poultry"),
+    (2402, 2404, "fish"),
+    (2802, 2802, "legumes"),
+    (2804, 2804, "nuts"),
+    (6002, 6099, "total_fruits"),
+    (6402, 6499, "vegetables"),
+    (7002, 7006, "total_fruits"),
+];
+
+/// Intake summed over one or more FDC foods.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FdcIntake {
+    /// Canonical fields reported by at least one food, in canonical units.
+    pub fields: BTreeMap<&'static str, f64>,
+    pub foods: usize,
+    /// Conversions applied, once per nutrient and field.
+    pub unit_conversions: Vec<UnitConversion>,
+    /// Nutrients left out because their unit cannot be converted.
+    pub unit_mismatches: Vec<UnitMismatch>,
+}
+
+impl FdcIntake {
+    /// Adds `grams` of `food`; nutrient amounts are per 100 g.
+    pub fn add_food(&mut self, food: &Value, grams: f64) {
+        let scale = grams / 100.0;
+        let entries = nutrient_entries(food);
+        for (field, alternatives) in FDC_NUTRIENTS {
+            let Some(parts) = alternatives
+                .iter()
+                .find(|numbers| entries.iter().any(|e| numbers.contains(&e.number)))
+            else {
+                continue;
+            };
+            let mut total = None;
+            for entry in entries.iter().filter(|e| parts.contains(&e.number)) {
+                match to_canonical(&entry.label, field, entry.amount, entry.unit) {
+                    Converted::Unchanged(value) => *total.get_or_insert(0.0) += value,
+                    Converted::Converted(value, conversion) => {
+                        *total.get_or_insert(0.0) += value;
+                        if !self.unit_conversions.contains(&conversion) {
+                            self.unit_conversions.push(conversion);
+                        }
+                    }
+                    Converted::Mismatch(mismatch) => {
+                        if !self.unit_mismatches.contains(&mismatch) {
+                            self.unit_mismatches.push(mismatch);
+                        }
+                    }
+                }
+            }
+            if let Some(total) = total {
+                *self.fields.entry(field).or_insert(0.0) += total * scale;
+            }
+        }
+        if let Some(group) = food_group(food) {
+            *self.fields.entry(group).or_insert(0.0) += grams;
+        }
+        self.foods += 1;
+    }
+}
+
+/// Reads a single FDC food, one consumed food (`{"food": ...}`), a list of
+/// consumed foods, or `{"foods": [...]}`.
+pub fn read_fdc_intake(input: &Value) -> anyhow::Result<FdcIntake> {
+    let mut intake = FdcIntake::default();
+    let items = match input {
+        Value::Array(items) => items.as_slice(),
+        Value::Object(obj) if obj.get("foodNutrients").is_some() => {
+            let grams = consumed_grams(input, input)?.unwrap_or(100.0);
+            intake.add_food(input, grams);
+            return Ok(intake);
+        }
+        Value::Object(obj) if obj.get("food").is_some() => std::slice::from_ref(input),
+        Value::Object(obj) => match obj.get("foods").and_then(Value::as_array) {
+            Some(items) => items.as_slice(),
+            None => bail!("expected an FDC food with foodNutrients or a list of foods"),
+        },
+        _ => bail!("expected an FDC food or a list of foods"),
+    };
+    for (i, item) in items.iter().enumerate() {
+        let food = item.get("food").unwrap_or(item);
+        if food.get("foodNutrients").is_none() {
+            bail!("food {}: no foodNutrients", i);
+        }
+        let grams = consumed_grams(food, item)?.ok_or_else(|| {
+            anyhow!(
+                "food {} ({}): no consumed amount, give grams, servings or portion",
+                i,
+                food_label(food)
+            )
+        })?;
+        intake.add_food(food, grams);
+    }
+    Ok(intake)
+}
+
+/// Grams of `food` consumed according to the amount keys of `item`, `None`
+/// when it has none.
+fn consumed_grams(food: &Value, item: &Value) -> anyhow::Result<Option<f64>> {
+    let number = |key: &str| item.get(key).and_then(Value::as_f64);
+    if let Some(grams) = number("grams") {
+        return Ok(Some(grams));
+    }
+    if let Some(servings) = number("servings") {
+        let size = serving_size(food)?
+            .ok_or_else(|| anyhow!("{}: no serving size or portion", food_label(food)))?;
+        return Ok(Some(servings * size));
+    }
+    if let Some(portion) = item.get("portion").and_then(Value::as_str) {
+        let weight = portion_weight(food, portion)
+            .ok_or_else(|| anyhow!("{}: no portion matching '{}'", food_label(food), portion))?;
+        return Ok(Some(number("quantity").unwrap_or(1.0) * weight));
+    }
+    Ok(None)
+}
+
+/// A branded food's serving size, or the weight of its first portion.
+fn serving_size(food: &Value) -> anyhow::Result<Option<f64>> {
+    Ok(branded_serving(food)?.or_else(|| portions(food).first().map(|(_, grams)| *grams)))
+}
+
+/// A branded food's serving size in grams, `None` when it gives none. Sizes
+/// in any other unit, such as `ml`, are an error.
+fn branded_serving(food: &Value) -> anyhow::Result<Option<f64>> {
+    let Some(size) = food.get("servingSize").and_then(Value::as_f64) else {
+        return Ok(None);
+    };
+    match food
+        .get("servingSizeUnit")
+        .and_then(Value::as_str)
+        .map(str::trim)
+    {
+        None => Ok(Some(size)),
+        Some(unit) if unit.eq_ignore_ascii_case("g") || unit.eq_ignore_ascii_case("grm") => {
+            Ok(Some(size))
+        }
+        Some(unit) => bail!(
+            "{}: serving size is {} {}, not grams; give grams",
+            food_label(food),
+            size,
+            unit
+        ),
+    }
+}
+
+/// Weight of the first portion whose description contains `portion`,
+/// ignoring case.
+fn portion_weight(food: &Value, portion: &str) -> Option<f64> {
+    let wanted = portion.trim().to_lowercase();
+    portions(food)
+        .into_iter()
+        .find(|(label, _)| label.to_lowercase().contains(&wanted))
+        .map(|(_, grams)| grams)
+}
+
+/// Portions of a food as (description, gram weight), in sequence order. A
+/// branded food's household serving comes first.
+fn portions(food: &Value) -> Vec<(String, f64)> {
+    let text = |v: &Value, key: &str| {
+        v.get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let mut out = Vec::new();
+    if let (Some(label), Some(size)) = (
+        text(food, "householdServingFullText"),
+        branded_serving(food).ok().flatten(),
+    ) {
+        out.push((label, size));
+    }
+    let mut listed: Vec<&Value> = food
+        .get("foodPortions")
+        .and_then(Value::as_array)
+        .map(|p| p.iter().collect())
+        .unwrap_or_default();
+    listed.sort_by_key(|p| {
+        p.get("sequenceNumber")
+            .and_then(Value::as_u64)
+            .unwrap_or(u64::MAX)
+    });
+    for p in listed {
+        let Some(grams) = p.get("gramWeight").and_then(Value::as_f64) else {
+            continue;
+        };
+        // Survey foods describe the whole portion; the other types give an
+        // amount, a measure unit and a modifier.
+        let label = text(p, "portionDescription")
+            .filter(|d| d != "Quantity not specified")
+            .unwrap_or_else(|| {
+                let amount = p
+                    .get("amount")
+                    .and_then(Value::as_f64)
+                    .map(|a| a.to_string());
+                let unit = p
+                    .get("measureUnit")
+                    .and_then(|u| text(u, "name"))
+                    .filter(|u| u != "undetermined");
+                [amount, unit, text(p, "modifier")]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            });
+        out.push((label, grams));
+    }
+    out
+}
+
+/// Food group field of a food's category, if it maps to one.
+pub fn food_group(food: &Value) -> Option<&'static str> {
+    if let Some(code) = food
+        .get("wweiaFoodCategory")
+        .and_then(|c| c.get("wweiaFoodCategoryCode"))
+        .and_then(Value::as_u64)
+    {
+        return WWEIA_GROUPS
+            .iter()
+            .find(|(lo, hi, _)| (*lo..=*hi).contains(&code))
+            .map(|(_, _, field)| *field);
+    }
+    let category = match food.get("foodCategory") {
+        Some(Value::String(s)) => Some(s.as_str()),
+        Some(c) => c.get("description").and_then(Value::as_str),
+        None => food.get("brandedFoodCategory").and_then(Value::as_str),
+    }?;
+    let category = category.trim().to_lowercase();
+    FOOD_CATEGORY_GROUPS
+        .iter()
+        .find(|(name, _)| *name == category)
+        .map(|(_, field)| *field)
+}
+
+struct NutrientEntry<'a> {
+    number: &'a str,
+    /// Name of the nutrient, or its number, for the unit trace.
+    label: String,
+    unit: Option<&'a str>,
+    amount: f64,
+}
+
+/// Nutrient amounts of a food in any of the FDC formats: full
+/// (`nutrient.number`, `amount`), abridged (`number`, `amount`) and search
+/// results (`nutrientNumber`, `value`). Entries without a number are matched
+/// by name.
+fn nutrient_entries(food: &Value) -> Vec<NutrientEntry<'_>> {
+    let Some(items) = food.get("foodNutrients").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<NutrientEntry> = Vec::new();
+    for item in items {
+        let nutrient = item.get("nutrient").unwrap_or(item);
+        let str_of = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|k| nutrient.get(*k).and_then(Value::as_str))
+        };
+        let Some(amount) = item
+            .get("amount")
+            .or_else(|| item.get("value"))
+            .and_then(Value::as_f64)
+        else {
+            continue;
+        };
+        let name = str_of(&["name", "nutrientName"]);
+        let unit = str_of(&["unitName"]);
+        let number = str_of(&["number", "nutrientNumber"]).or_else(|| {
+            let name = name?;
+            if name == "Energy" && unit.is_some_and(|u| u.eq_ignore_ascii_case("kj")) {
+                return Some("268");
+            }
+            FDC_NUTRIENT_NAMES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, number)| *number)
+        });
+        let Some(number) = number else { continue };
+        // The first amount of a nutrient is kept.
+        if entries.iter().any(|e| e.number == number) {
+            continue;
+        }
+        entries.push(NutrientEntry {
+            number,
+            label: name.unwrap_or(number).to_string(),
+            unit,
+            amount,
+        });
+    }
+    entries
+}
+
+fn food_label(food: &Value) -> String {
+    food.get("description")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| food.get("fdcId").map(Value::to_string))
+        .unwrap_or_else(|| "food".to_string())
+}
@@ -1,7 +1,41 @@
+//! Food items of `schema/food_components.json` and their expansion into
+//! canonical fields.
+//!
+//! An item lists its `components` per 100 g, or is a recipe of `ingredients`
+//! (other items, recipes or FPED food codes) in grams per batch:
+//!
+//! ```json
+//! "lasagna": {
+//!   "ingredients": [
+//!     {"item": "meat_sauce", "grams": 400},
+//!     {"item": "pasta", "grams": 250},
+//!     {"item": "cheese", "grams": 150}
+//!   ],
+//!   "yield_grams": 700,
+//!   "servings": 4,
+//!   "retention": {"vitamin_c": 0.5}
+//! }
+//! ```
+//!
+//! `yield_grams` is the cooked weight of the batch (the ingredient total when
+//! absent), `servings` the portions it makes, and `retention` the fraction of
+//! a component kept through cooking (1 when absent). Recipes are expanded
+//! recursively, each contribution keeping the path of ingredients it came
+//! through.
+//!
+//! Columns are matched to items by name, see [`crate::food_matching`]. An
+//! item's `synonyms` (`"ground beef"` for `beef`) match as its own name.
+
+use crate::food_matching::{
+    best_match, match_order, split_food_key, tokenize, AmountUnit, FoodSuggestion,
+    DEFAULT_MATCH_THRESHOLD, SUGGESTION_FLOOR,
+};
+use crate::food_patterns::FoodPatternTable;
+use log::warn;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use std::collections::HashMap;
-use log::info;
+use std::sync::RwLock;
 
 static FOOD_JSON: &str = include_str!("../../schema/food_components.json");
 
@@ -10,6 +44,16 @@ struct RawItem {
     #[serde(default)]
     components: HashMap<String, f64>,
     #[serde(default)]
+    ingredients: Vec<Ingredient>,
+    #[serde(default)]
+    yield_grams: Option<f64>,
+    #[serde(default)]
+    servings: Option<f64>,
+    #[serde(default)]
+    retention: HashMap<String, f64>,
+    #[serde(default)]
+    synonyms: Vec<String>,
+    #[serde(default)]
     #[allow(dead_code)]
     source: Option<String>,
     #[serde(default)]
@@ -20,47 +64,292 @@ struct RawItem {
     reviewed: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
-struct RawMap(HashMap<String, RawItem>);
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Ingredient {
+    pub item: String,
+    pub grams: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recipe {
+    pub ingredients: Vec<Ingredient>,
+    pub yield_grams: Option<f64>,
+    pub servings: Option<f64>,
+    pub retention: HashMap<&'static str, f64>,
+}
+
+impl Recipe {
+    /// Cooked weight of one batch.
+    pub fn batch_grams(&self) -> f64 {
+        self.yield_grams
+            .unwrap_or_else(|| self.ingredients.iter().map(|i| i.grams).sum())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FoodItem {
+    components: HashMap<&'static str, f64>,
+    recipe: Option<Recipe>,
+}
+
+/// Amount of one field reached from an input key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution {
+    pub field: &'static str,
+    pub value: f64,
+    /// The input key, then the ingredients expanded to reach the field.
+    pub path: Vec<String>,
+}
+
+/// Contributions of an input key, and the ingredients on the way that named
+/// no known item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resolution {
+    pub contributions: Vec<Contribution>,
+    /// Ingredient paths ending in an unknown item, starting like
+    /// [`Contribution::path`].
+    pub unresolved: Vec<Vec<String>>,
+}
 
 pub struct FoodItemResolver {
-    map: HashMap<&'static str, HashMap<&'static str, f64>>,
+    map: HashMap<&'static str, FoodItem>,
+    /// Tokens of item names and synonyms, with the item they name.
+    names: Vec<(Vec<String>, &'static str)>,
+    // joined name tokens -> best matching item, computed once per name
+    matches: RwLock<HashMap<String, Option<(String, f64)>>>,
 }
 
 impl FoodItemResolver {
     fn load() -> Self {
-        let raw: HashMap<String, RawItem> =
-            serde_json::from_str(FOOD_JSON).expect("invalid food_components.json");
+        Self::from_json(FOOD_JSON).expect("invalid food_components.json")
+    }
+
+    /// Resolver for items in the layout of `food_components.json`.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let leak = |s: String| -> &'static str { Box::leak(s.into_boxed_str()) };
+        let raw: HashMap<String, RawItem> = serde_json::from_str(json)?;
         let mut map = HashMap::new();
+        let mut names = Vec::new();
         for (item, entry) in raw {
-            let key: &'static str = Box::leak(item.to_ascii_lowercase().into_boxed_str());
-            let mut inner = HashMap::new();
-            for (nut, val) in entry.components {
-                let nut_key: &'static str = Box::leak(nut.to_string().into_boxed_str());
-                inner.insert(nut_key, val);
+            let key = leak(item.to_ascii_lowercase());
+            names.push((tokenize(key), key));
+            for synonym in &entry.synonyms {
+                names.push((tokenize(synonym), key));
             }
-            map.insert(key, inner);
+            let components = entry
+                .components
+                .into_iter()
+                .map(|(nut, val)| (leak(nut), val))
+                .collect();
+            let recipe = (!entry.ingredients.is_empty()).then(|| Recipe {
+                ingredients: entry.ingredients,
+                yield_grams: entry.yield_grams,
+                servings: entry.servings,
+                retention: entry
+                    .retention
+                    .into_iter()
+                    .map(|(nut, val)| (leak(nut), val))
+                    .collect(),
+            });
+            map.insert(key, FoodItem { components, recipe });
         }
-        FoodItemResolver { map }
+        Ok(FoodItemResolver {
+            map,
+            names,
+            matches: RwLock::new(HashMap::new()),
+        })
     }
 
+    /// Item, or food code of `patterns`, best matching the name `tokens`,
+    /// with its similarity. Exact names, synonyms, food codes and
+    /// descriptions score 1.
+    pub fn find_item(
+        &self,
+        tokens: &[String],
+        patterns: Option<&FoodPatternTable>,
+    ) -> Option<(String, f64)> {
+        if let Some((_, item)) = self.names.iter().find(|(name, _)| name == tokens) {
+            return Some((item.to_string(), 1.0));
+        }
+        let joined = tokens.join(" ");
+        if let Some(food) = patterns.and_then(|t| t.get(&joined)) {
+            return Some((food.code.clone(), 1.0));
+        }
+        let cached = self.matches.read().unwrap().get(&joined).cloned();
+        let item = cached.unwrap_or_else(|| {
+            let candidates = self
+                .names
+                .iter()
+                .map(|(name, item)| (name.as_slice(), *item));
+            let found =
+                best_match(tokens, candidates).map(|(item, score)| (item.to_string(), score));
+            self.matches.write().unwrap().insert(joined, found.clone());
+            found
+        });
+        let described = patterns.and_then(|t| t.best_description(tokens));
+        [item, described]
+            .into_iter()
+            .flatten()
+            .max_by(|a, b| match_order((&a.0, a.1), (&b.0, b.1)))
+    }
+
+    /// Best match of a food column that is too weak to apply at `threshold`,
+    /// if any is worth reporting.
+    pub fn suggest(
+        &self,
+        column: &str,
+        threshold: f64,
+        patterns: Option<&FoodPatternTable>,
+    ) -> Option<FoodSuggestion> {
+        let (tokens, _) = split_food_key(column)?;
+        let (item, score) = self.find_item(&tokens, patterns)?;
+        (score < threshold && score >= SUGGESTION_FLOOR).then(|| FoodSuggestion {
+            column: column.to_string(),
+            item,
+            score,
+        })
+    }
+
+    /// Recipe of an item, if it is one.
+    pub fn recipe(&self, item: &str) -> Option<&Recipe> {
+        self.map
+            .get(item.to_ascii_lowercase().as_str())
+            .and_then(|i| i.recipe.as_ref())
+    }
+
+    /// Fields reached from `amount` of the food column `field`, with names
+    /// matched at [`DEFAULT_MATCH_THRESHOLD`] and no food pattern table.
     pub fn resolve(&self, field: &str, amount: f64) -> Option<HashMap<&'static str, f64>> {
-        let lower = field.to_ascii_lowercase();
-        if lower.ends_with("_g") {
-            let base = lower.trim_end_matches("_g");
-            self.map.get(base).map(|comp| {
-                comp.iter()
-                    .map(|(k, v)| (*k, v * amount / 100.0))
-                    .collect()
-            })
-        } else if lower.ends_with("_servings") {
-            let base = lower.trim_end_matches("_servings");
-            self.map.get(base).map(|comp| {
-                comp.iter().map(|(k, v)| (*k, v * amount)).collect()
-            })
-        } else {
-            None
+        let mut totals = HashMap::new();
+        for c in self
+            .resolve_traced(field, amount, DEFAULT_MATCH_THRESHOLD, None)?
+            .contributions
+        {
+            *totals.entry(c.field).or_insert(0.0) += c.value;
+        }
+        Some(totals)
+    }
+
+    /// Like [`Self::resolve`], keeping each contribution with its ingredient
+    /// path, and applying a name match when its similarity reaches
+    /// `threshold` (see [`crate::food_matching::similarity`]). A column
+    /// matched to an item under another name has the item after the column
+    /// in the path. Keys such as `11111000_g` or `milk, whole_g`, and
+    /// ingredients that are not items, resolve through `patterns` by food
+    /// code or description.
+    pub fn resolve_traced(
+        &self,
+        field: &str,
+        amount: f64,
+        threshold: f64,
+        patterns: Option<&FoodPatternTable>,
+    ) -> Option<Resolution> {
+        let (tokens, unit) = split_food_key(field)?;
+        let (base, score) = self.find_item(&tokens, patterns)?;
+        if score < threshold {
+            return None;
+        }
+        let renamed = tokenize(&base) != tokens;
+        let grams = match unit {
+            AmountUnit::Grams => amount,
+            AmountUnit::Servings => match &self.map.get(base.as_str())?.recipe {
+                // A serving of a recipe is its batch split evenly.
+                Some(recipe) => amount * recipe.batch_grams() / recipe.servings?,
+                None => {
+                    let item = &self.map[base.as_str()];
+                    let contributions = item
+                        .components
+                        .iter()
+                        .map(|(k, v)| Contribution {
+                            field: k,
+                            value: v * amount,
+                            path: if renamed {
+                                vec![field.to_string(), base.clone()]
+                            } else {
+                                vec![field.to_string()]
+                            },
+                        })
+                        .collect();
+                    return Some(Resolution {
+                        contributions,
+                        unresolved: Vec::new(),
+                    });
+                }
+            },
+        };
+        let mut out = Resolution::default();
+        if !self.expand(&base, grams, &mut vec![base.clone()], &mut out, patterns) {
+            return None;
+        }
+        let paths = out.contributions.iter_mut().map(|c| &mut c.path);
+        for path in paths.chain(out.unresolved.iter_mut()) {
+            if renamed {
+                path.insert(0, field.to_string());
+            } else {
+                path[0] = field.to_string();
+            }
+        }
+        Some(out)
+    }
+
+    /// Adds the contributions of `grams` of `item`, the last entry of `path`,
+    /// to `out`, and the paths of unknown ingredients. Returns false when the
+    /// item itself is unknown.
+    fn expand(
+        &self,
+        item: &str,
+        grams: f64,
+        path: &mut Vec<String>,
+        out: &mut Resolution,
+        patterns: Option<&FoodPatternTable>,
+    ) -> bool {
+        let Some(food) = self.map.get(item) else {
+            let Some(pattern) = patterns.and_then(|t| t.get(item)) else {
+                return false;
+            };
+            let amounts = pattern.amounts(grams).into_iter();
+            out.contributions
+                .extend(amounts.map(|(field, value)| Contribution {
+                    field,
+                    value,
+                    path: path.clone(),
+                }));
+            return true;
+        };
+        out.contributions.extend(
+            food.components
+                .iter()
+                .map(|(field, per_100g)| Contribution {
+                    field,
+                    value: per_100g * grams / 100.0,
+                    path: path.clone(),
+                }),
+        );
+        let Some(recipe) = &food.recipe else {
+            return true;
+        };
+        let batch = recipe.batch_grams();
+        if batch <= 0.0 {
+            return true;
+        }
+        for ingredient in &recipe.ingredients {
+            let name = ingredient.item.to_ascii_lowercase();
+            if path.contains(&name) {
+                warn!("recipe cycle through {} in {}", name, path.join(" > "));
+                continue;
+            }
+            path.push(name.clone());
+            let start = out.contributions.len();
+            if !self.expand(&name, ingredient.grams * grams / batch, path, out, patterns) {
+                warn!("unknown ingredient {} of {}", name, item);
+                out.unresolved.push(path.clone());
+            }
+            for c in &mut out.contributions[start..] {
+                c.value *= recipe.retention.get(c.field).copied().unwrap_or(1.0);
+            }
+            path.pop();
         }
+        true
     }
 }
 
@@ -69,5 +358,7 @@ pub static FOOD_RESOLVER: Lazy<FoodItemResolver> = Lazy::new(FoodItemResolver::l
 #[derive(Debug, Default, Clone, serde::Serialize, PartialEq)]
 pub struct TranslationEntry {
     pub value: f64,
+    /// Input keys that contributed, with the ingredient path of recipes
+    /// joined by ` > `.
     pub source: Vec<String>,
 }
@@ -0,0 +1,114 @@
+//! Matching of food columns to food items by name.
+//!
+//! A food column names an item and the unit of its amount: `beef_g`,
+//! `beef_grams`, `Beef (g)` and `beef servings` all read as beef. The item
+//! name is reduced to lower-case word tokens and compared with item names and
+//! their synonyms, then ranked by fuzzy similarity, the better of token
+//! overlap and edit distance. Matches scoring below the threshold given with
+//! each lookup are not applied; the best one is reported as a
+//! [`FoodSuggestion`].
+
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// Similarity a match needs to be applied unless configured otherwise.
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.85;
+
+/// Similarity below which a match is not worth suggesting.
+pub const SUGGESTION_FLOOR: f64 = 0.4;
+
+const GRAM_TOKENS: &[&str] = &["g", "gm", "gr", "gram", "grams"];
+const SERVING_TOKENS: &[&str] = &["serv", "serving", "servings"];
+
+/// Unit of a food column's amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountUnit {
+    Grams,
+    Servings,
+}
+
+/// Lower-case runs of letters and digits.
+pub fn tokenize(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Item name tokens and amount unit of a food column; `None` unless the last
+/// token is a unit.
+pub fn split_food_key(key: &str) -> Option<(Vec<String>, AmountUnit)> {
+    let mut tokens = tokenize(key);
+    let last = tokens.pop()?;
+    let unit = if GRAM_TOKENS.contains(&last.as_str()) {
+        AmountUnit::Grams
+    } else if SERVING_TOKENS.contains(&last.as_str()) {
+        AmountUnit::Servings
+    } else {
+        return None;
+    };
+    (!tokens.is_empty()).then_some((tokens, unit))
+}
+
+/// Similarity of two token lists from 0 to 1: the better of their token
+/// overlap, where near-identical tokens count 0.8, and the normalised edit
+/// distance of the joined names.
+pub fn similarity(a: &[String], b: &[String]) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    let mut matched = 0;
+    let mut weight = 0.0;
+    for t in a {
+        if b.contains(t) {
+            matched += 1;
+            weight += 1.0;
+        } else if b.iter().any(|u| near(t, u)) {
+            matched += 1;
+            weight += 0.8;
+        }
+    }
+    let union = a.len() + b.len() - matched;
+    let overlap = if union == 0 {
+        0.0
+    } else {
+        weight / union as f64
+    };
+    let chars = strsim::normalized_levenshtein(&a.join(" "), &b.join(" "));
+    overlap.max(chars)
+}
+
+// One edit apart, for words long enough that this is a typo.
+fn near(a: &str, b: &str) -> bool {
+    a.len().min(b.len()) >= 4 && strsim::levenshtein(a, b) <= 1
+}
+
+/// Best of `candidates` (tokens, target) for `tokens`, ties broken by the
+/// shorter then alphabetically first target.
+pub fn best_match<'a>(
+    tokens: &[String],
+    candidates: impl IntoIterator<Item = (&'a [String], &'a str)>,
+) -> Option<(&'a str, f64)> {
+    candidates
+        .into_iter()
+        .map(|(cand, target)| (target, similarity(tokens, cand)))
+        .max_by(|&a, &b| match_order(a, b))
+}
+
+/// Order of (target, similarity) matches in which [`best_match`] picks the
+/// greatest.
+pub fn match_order((ta, a): (&str, f64), (tb, b): (&str, f64)) -> Ordering {
+    a.total_cmp(&b)
+        .then_with(|| tb.len().cmp(&ta.len()))
+        .then_with(|| tb.cmp(ta))
+}
+
+/// A food column left unresolved whose best match scored below the
+/// threshold.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FoodSuggestion {
+    pub column: String,
+    /// Food item, or FPED food code, it may refer to.
+    pub item: String,
+    pub score: f64,
+}
@@ -0,0 +1,296 @@
+//! Food pattern equivalents of foods, from an FPED-style table.
+//!
+//! The USDA Food Pattern Equivalents Database gives, per 100 g of each FNDDS
+//! food code, the cup, ounce and teaspoon equivalents of the food pattern
+//! components (`F_TOTAL`, `V_DRKGR`, `G_WHOLE`, `PF_MEAT`, ...). A table in
+//! that layout, saved as CSV or TSV, is loaded from a local file and turns a
+//! day's foods into the food group fields of a [`NutritionVector`]. Headers
+//! are matched ignoring case, spacing and a parenthesised unit, so both
+//! `F_TOTAL` and `F_TOTAL (cup eq.)` are read, as are `Food code` and `Main
+//! food description`. Columns named after canonical fields are taken as
+//! amounts per 100 g in that field's unit.
+//!
+//! Components fill the equivalent fields, which share their cup, ounce and
+//! teaspoon units (`f_total`, `v_greens_beans`, `pf_seaplant`, ...), as they
+//! are. They also fill the gram fields the scorers read (`total_fruits`,
+//! `vegetables`, `legumes`, `whole_grains`, `red_meat`) through the
+//! conversions below: a cup equivalent of fruit or vegetables is two 80 g
+//! portions, one of legumes a cup of cooked beans, an ounce equivalent of
+//! whole grains 16 g of whole grain and one of meat an ounce. Legumes count
+//! both as vegetables and as protein foods in the equivalent fields, and only
+//! once, from `V_LEGUMES`, in grams.
+//!
+//! A table is given to the runs that use it, through
+//! [`crate::stream::StreamOptions::food_patterns`] or
+//! [`NutritionVector::from_partial_map_with_units`], so food columns of
+//! other runs in the same process are not resolved through it.
+
+use crate::food_matching::{best_match, tokenize};
+use crate::nutrition_vector::NutritionVector;
+use crate::tabular::delimiter_for;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Grams of fruit or vegetables in one cup equivalent.
+pub const GRAMS_PER_CUP_EQ: f64 = 160.0;
+/// Grams of cooked legumes in one cup equivalent.
+pub const LEGUME_GRAMS_PER_CUP_EQ: f64 = 172.0;
+/// Grams of whole grain in one ounce equivalent of whole grains.
+pub const WHOLE_GRAIN_GRAMS_PER_OZ_EQ: f64 = 16.0;
+/// Grams of meat in one ounce equivalent.
+pub const MEAT_GRAMS_PER_OZ_EQ: f64 = 28.35;
+
+/// Fields filled by each FPED component, with the amount of the field in one
+/// unit of the component. Components listed for one field are summed.
+pub const FPED_COMPONENTS: &[(&str, &[(&str, f64)])] = &[
+    (
+        "F_TOTAL",
+        &[("f_total", 1.0), ("total_fruits", GRAMS_PER_CUP_EQ)],
+    ),
+    ("F_CITMLB", &[("f_whole", 1.0)]),
+    ("F_OTHER", &[("f_whole", 1.0)]),
+    (
+        "V_TOTAL",
+        &[("v_total", 1.0), ("vegetables", GRAMS_PER_CUP_EQ)],
+    ),
+    ("V_DRKGR", &[("v_greens_beans", 1.0)]),
+    (
+        "V_LEGUMES",
+        &[
+            ("v_total", 1.0),
+            ("v_greens_beans", 1.0),
+            ("legumes", LEGUME_GRAMS_PER_CUP_EQ),
+        ],
+    ),
+    (
+        "G_WHOLE",
+        &[
+            ("g_whole", 1.0),
+            ("whole_grains", WHOLE_GRAIN_GRAMS_PER_OZ_EQ),
+        ],
+    ),
+    ("G_REFINED", &[("g_refined", 1.0)]),
+    ("PF_TOTAL", &[("pf_total", 1.0)]),
+    ("PF_MEAT", &[("red_meat", MEAT_GRAMS_PER_OZ_EQ)]),
+    ("PF_CUREDMEAT", &[("red_meat", MEAT_GRAMS_PER_OZ_EQ)]),
+    ("PF_SEAFD_HI", &[("pf_seaplant", 1.0)]),
+    ("PF_SEAFD_LOW", &[("pf_seaplant", 1.0)]),
+    ("PF_SOY", &[("pf_seaplant", 1.0)]),
+    ("PF_NUTSDS", &[("pf_seaplant", 1.0)]),
+    ("PF_LEGUMES", &[("pf_total", 1.0), ("pf_seaplant", 1.0)]),
+    ("D_TOTAL", &[("d_total", 1.0)]),
+    ("ADD_SUGARS", &[("add_sugars", 1.0)]),
+];
+
+const CODE_HEADERS: &[&str] = &["FOODCODE", "FOOD_CODE", "CODE"];
+const DESCRIPTION_HEADERS: &[&str] = &["DESCRIPTION", "MAIN_FOOD_DESCRIPTION", "FOOD_DESCRIPTION"];
+
+/// Field amounts per 100 g of one food.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FoodPattern {
+    pub code: String,
+    pub description: Option<String>,
+    pub per_100g: BTreeMap<&'static str, f64>,
+}
+
+impl FoodPattern {
+    /// Field amounts in `grams` of the food.
+    pub fn amounts(&self, grams: f64) -> HashMap<&'static str, f64> {
+        self.per_100g
+            .iter()
+            .map(|(field, value)| (*field, value * grams / 100.0))
+            .collect()
+    }
+}
+
+/// Food pattern equivalents keyed by food code, also found by description.
+#[derive(Debug, Default)]
+pub struct FoodPatternTable {
+    foods: HashMap<String, FoodPattern>,
+    // lower-case description -> code
+    descriptions: HashMap<String, String>,
+    // joined name tokens -> best matching description, computed once per name
+    matches: RwLock<HashMap<String, Option<(String, f64)>>>,
+}
+
+impl Clone for FoodPatternTable {
+    fn clone(&self) -> Self {
+        FoodPatternTable {
+            foods: self.foods.clone(),
+            descriptions: self.descriptions.clone(),
+            matches: RwLock::default(),
+        }
+    }
+}
+
+impl PartialEq for FoodPatternTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.foods == other.foods && self.descriptions == other.descriptions
+    }
+}
+
+impl FoodPatternTable {
+    /// Loads a CSV or TSV table, tab-delimited for `.tsv` and `.tab`.
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            std::fs::File::open(path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        Ok(Self::from_reader(file, delimiter_for(path))?)
+    }
+
+    pub fn from_reader<R: Read>(input: R, delimiter: u8) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(input);
+        let headers: Vec<String> = reader
+            .headers()?
+            .iter()
+            .map(|h| {
+                let name = h.trim_start_matches('\u{feff}');
+                let name = name.split('(').next().unwrap_or("");
+                name.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join("_")
+                    .to_ascii_uppercase()
+            })
+            .collect();
+        let find = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+        let code_col = find(CODE_HEADERS).unwrap_or(0);
+        let description_col = find(DESCRIPTION_HEADERS);
+        // column -> fields it fills, with the amount per unit
+        let columns: Vec<(usize, Vec<(&'static str, f64)>)> = headers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, h)| {
+                let fields = match FPED_COMPONENTS.iter().find(|(c, _)| *c == h) {
+                    Some((_, fields)) => fields.to_vec(),
+                    None => NutritionVector::all_field_names()
+                        .iter()
+                        .find(|f| f.eq_ignore_ascii_case(h))
+                        .map(|f| vec![(*f, 1.0)])?,
+                };
+                Some((i, fields))
+            })
+            .collect();
+
+        let mut table = FoodPatternTable::default();
+        for record in reader.records() {
+            let record = record?;
+            let Some(code) = record.get(code_col).filter(|c| !c.is_empty()) else {
+                continue;
+            };
+            let mut per_100g = BTreeMap::new();
+            for (i, fields) in &columns {
+                let Some(value) = record.get(*i).and_then(|v| v.parse::<f64>().ok()) else {
+                    continue;
+                };
+                for (field, per_unit) in fields {
+                    *per_100g.entry(*field).or_insert(0.0) += value * per_unit;
+                }
+            }
+            let description = description_col
+                .and_then(|i| record.get(i))
+                .filter(|d| !d.is_empty())
+                .map(str::to_string);
+            table.insert(FoodPattern {
+                code: code.to_string(),
+                description,
+                per_100g,
+            });
+        }
+        Ok(table)
+    }
+
+    pub fn insert(&mut self, food: FoodPattern) {
+        self.matches.get_mut().unwrap().clear();
+        if let Some(description) = &food.description {
+            self.descriptions
+                .insert(description.to_lowercase(), food.code.clone());
+        }
+        self.foods.insert(food.code.clone(), food);
+    }
+
+    /// Food with the given code, or else the given description ignoring case.
+    pub fn get(&self, key: &str) -> Option<&FoodPattern> {
+        let key = key.trim();
+        self.foods.get(key).or_else(|| {
+            self.descriptions
+                .get(&key.to_lowercase())
+                .and_then(|code| self.foods.get(code))
+        })
+    }
+
+    /// Code of the food whose description best matches the name `tokens`,
+    /// with its similarity, see [`crate::food_matching::best_match`].
+    pub fn best_description(&self, tokens: &[String]) -> Option<(String, f64)> {
+        let joined = tokens.join(" ");
+        if let Some(found) = self.matches.read().unwrap().get(&joined) {
+            return found.clone();
+        }
+        let described: Vec<(Vec<String>, &str)> = self
+            .foods()
+            .filter_map(|f| Some((tokenize(f.description.as_deref()?), f.code.as_str())))
+            .collect();
+        let candidates = described
+            .iter()
+            .map(|(name, code)| (name.as_slice(), *code));
+        let found = best_match(tokens, candidates).map(|(code, score)| (code.to_string(), score));
+        self.matches.write().unwrap().insert(joined, found.clone());
+        found
+    }
+
+    pub fn foods(&self) -> impl Iterator<Item = &FoodPattern> {
+        self.foods.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.foods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.foods.is_empty()
+    }
+
+    /// Sums the field amounts of a day's foods, given as (food code or
+    /// description, grams eaten).
+    pub fn aggregate<'a>(
+        &self,
+        foods: impl IntoIterator<Item = (&'a str, f64)>,
+    ) -> FoodPatternIntake {
+        let mut intake = FoodPatternIntake::default();
+        for (key, grams) in foods {
+            match self.get(key) {
+                Some(food) => {
+                    for (field, value) in food.amounts(grams) {
+                        *intake.fields.entry(field).or_insert(0.0) += value;
+                    }
+                }
+                None => intake.unmatched.push(key.to_string()),
+            }
+        }
+        intake
+    }
+}
+
+/// Food group amounts of a day's foods.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FoodPatternIntake {
+    pub fields: BTreeMap<&'static str, f64>,
+    /// Foods not in the table.
+    pub unmatched: Vec<String>,
+}
+
+impl FoodPatternIntake {
+    /// Adds the amounts to the fields of `nv`.
+    pub fn apply(&self, nv: &mut NutritionVector) {
+        for (field, value) in &self.fields {
+            if let Some(slot) = nv.field_mut(field) {
+                *slot = Some(slot.unwrap_or(0.0) + value);
+            }
+        }
+    }
+}
@@ -18,6 +18,23 @@ static HCSN_MAP: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
 
 pub type CanonicalField = &'static str;
 
+/// Fields read by the MIND scorer, filled with the sum of the weekly HCNS
+/// components beside them.
+const MIND_FIELDS: &[(&str, &[&str])] = &[
+    ("vegetables", &["leafy_greens_wk", "other_veg_wk"]),
+    ("berries", &["berries_wk"]),
+    ("nuts", &["nuts_wk"]),
+    ("whole_grains", &["whole_grains_wk"]),
+    ("fish", &["fish_wk"]),
+    ("poultry", &["poultry_wk"]),
+    ("mono_fat", &["olive_oil_primary"]),
+    ("red_meat", &["red_meat_wk"]),
+    ("butter", &["butter_wk"]),
+    ("cheese", &["cheese_wk"]),
+    ("sugar", &["pastries_sweets_wk"]),
+    ("fast_food", &["fried_food_wk"]),
+];
+
 pub fn is_hcsn_sheet(headers: &[String]) -> bool {
     headers
         .iter()
@@ -35,3 +52,52 @@ pub fn resolve_hcsn_headers(raw_headers: &[String]) -> HashMap<String, Canonical
     }
     map
 }
+
+/// Rename a row with `map` from [`resolve_hcsn_headers`]. HCNS food frequency
+/// items that make up one MIND component (e.g. `C5A_FF_13` to `C5D_FF_13` for
+/// nuts) are summed, and `olive_oil_primary` is reduced to a 0/1 flag.
+/// The summed components also fill the fields the MIND scorer reads, added
+/// to any given directly (`whole_grain_g`). Unmapped columns are kept as
+/// they are.
+pub fn aggregate_hcsn_row(
+    row: &HashMap<String, serde_json::Value>,
+    map: &HashMap<String, CanonicalField>,
+) -> HashMap<String, serde_json::Value> {
+    let mut out: HashMap<String, serde_json::Value> = HashMap::new();
+    for (k, v) in row {
+        match (map.get(k), v.as_f64()) {
+            (Some(&canon), Some(num)) => {
+                let total = out
+                    .get(canon)
+                    .and_then(|existing| existing.as_f64())
+                    .unwrap_or(0.0)
+                    + num;
+                out.insert(canon.to_string(), serde_json::json!(total));
+            }
+            (Some(&canon), None) => {
+                out.insert(canon.to_string(), v.clone());
+            }
+            (None, _) => {
+                out.insert(k.clone(), v.clone());
+            }
+        }
+    }
+    for (field, components) in MIND_FIELDS {
+        let values: Vec<f64> = components
+            .iter()
+            .filter_map(|c| out.get(*c).and_then(serde_json::Value::as_f64))
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+        let given = out.get(*field).and_then(serde_json::Value::as_f64);
+        let total = given.unwrap_or(0.0) + values.iter().sum::<f64>();
+        out.insert(field.to_string(), serde_json::json!(total));
+    }
+    if let Some(flag) = out.get_mut("olive_oil_primary") {
+        if let Some(num) = flag.as_f64() {
+            *flag = serde_json::json!(if num > 0.0 { 1.0 } else { 0.0 });
+        }
+    }
+    out
+}
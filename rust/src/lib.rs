@@ -1,10 +1,20 @@
 pub mod contracts;
+pub mod energy;
 pub mod eval;
 pub mod nutrition_vector;
+pub mod output;
 pub mod scores;
+pub mod stream;
 pub mod nhanes_ingest;
+pub mod fdc_ingest;
 pub mod acs2020_ingest;
 pub mod hcsn_ingest;
+pub mod record_ids;
+pub mod survey;
+pub mod tabular;
 pub mod wasm;
 pub mod food_item_resolver;
+pub mod food_matching;
+pub mod food_patterns;
+pub mod units;
 pub mod unmapped_monitor;
@@ -1,22 +1,32 @@
+use dietarycodex::energy::EnergyAdjustment;
 use dietarycodex::eval::{
-    evaluate_allow_partial, format_skipped_scores, print_scores_as_json,
-    print_scores_as_json_allow_partial,
+    evaluate_all_scores, evaluate_allow_partial, format_skipped_scores, ScoreResult,
 };
-use dietarycodex::nutrition_vector::NutritionVector;
+use dietarycodex::food_matching::DEFAULT_MATCH_THRESHOLD;
+use dietarycodex::food_patterns::FoodPatternTable;
+use dietarycodex::nutrition_vector::{NutritionVector, SchemaError};
+use dietarycodex::output::{wide_table, write_table, OutputFormat, TableLayout};
 use dietarycodex::scores::registry::all_score_metadata;
+use dietarycodex::stream::{write_json_stream, write_table_stream, ScoreStream, StreamOptions};
+use dietarycodex::tabular::{delimiter_for, ScoredRow};
 use serde_json::to_string_pretty;
 use std::env;
 use std::fs;
-use std::io::Write;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
 use tabwriter::TabWriter;
 
+const USAGE: &str =
+    "<fdc_json|csv|tsv> [--allow-partial] [--verbose-partial] [--list-scores] [--json] \
+     [--format json|csv|tsv|parquet] [--output <path>] [--components] [--coverage <path>] \
+     [--id-column <name>]... [--fped <path>] [--match-threshold <0-1>] \
+     [--energy-adjustment none|density|per_1000_kcal|residual]";
+
 fn main() -> anyhow::Result<()> {
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!(
-            "Usage: {} <fdc_json> [--allow-partial] [--verbose-partial] [--list-scores] [--json]",
-            args[0]
-        );
+        eprintln!("Usage: {} {}", args[0], USAGE);
         std::process::exit(1);
     }
     let mut allow_partial = false;
@@ -24,8 +34,44 @@ fn main() -> anyhow::Result<()> {
     let mut file = String::new();
     let mut list_scores = false;
     let mut json_output = false;
-    for arg in args.iter().skip(1) {
-        if arg == "--allow-partial" {
+    let mut format: Option<OutputFormat> = None;
+    let mut output: Option<String> = None;
+    let mut components = false;
+    let mut coverage: Option<String> = None;
+    let mut id_columns: Vec<String> = Vec::new();
+    let mut fped: Option<String> = None;
+    let mut match_threshold = DEFAULT_MATCH_THRESHOLD;
+    let mut energy_adjustment: Option<EnergyAdjustment> = None;
+    let mut rest = args.iter().skip(1);
+    while let Some(arg) = rest.next() {
+        if arg == "--format" {
+            let name = rest.next().map(String::as_str).unwrap_or_default();
+            format = Some(
+                OutputFormat::from_name(name)
+                    .ok_or_else(|| anyhow::anyhow!("unknown output format '{}'", name))?,
+            );
+        } else if arg == "--output" {
+            output = rest.next().cloned();
+        } else if arg == "--coverage" {
+            coverage = rest.next().cloned();
+        } else if arg == "--match-threshold" {
+            let value = rest.next().map(String::as_str).unwrap_or_default();
+            match_threshold = value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid match threshold '{}'", value))?;
+        } else if arg == "--energy-adjustment" {
+            let name = rest.next().map(String::as_str).unwrap_or_default();
+            energy_adjustment = Some(
+                EnergyAdjustment::from_name(name)
+                    .ok_or_else(|| anyhow::anyhow!("unknown energy adjustment '{}'", name))?,
+            );
+        } else if arg == "--fped" {
+            fped = rest.next().cloned();
+        } else if arg == "--id-column" {
+            id_columns.extend(rest.next().cloned());
+        } else if arg == "--components" {
+            components = true;
+        } else if arg == "--allow-partial" {
             allow_partial = true;
         } else if arg == "--verbose-partial" {
             verbose_partial = true;
@@ -55,30 +101,134 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
     if file.is_empty() {
-        eprintln!(
-            "Usage: {} <fdc_json> [--allow-partial] [--verbose-partial] [--list-scores] [--json]",
-            args[0]
-        );
+        eprintln!("Usage: {} {}", args[0], USAGE);
         std::process::exit(1);
     }
-    let data = fs::read_to_string(&file)?;
-    let nv = NutritionVector::from_fdc_json(&data)?;
-    if allow_partial {
-        if verbose_partial {
-            let result = evaluate_allow_partial(&nv);
-            if let Some(report) = format_skipped_scores(&result) {
-                eprint!("{}", report);
-                eprintln!();
+    let food_patterns = match &fped {
+        Some(p) => Some(Arc::new(FoodPatternTable::from_path(Path::new(p))?)),
+        None => None,
+    };
+    let path = Path::new(&file);
+    let format = format
+        .or_else(|| {
+            output
+                .as_deref()
+                .and_then(|p| OutputFormat::from_path(Path::new(p)))
+        })
+        .unwrap_or(OutputFormat::Json);
+    if is_table(path) {
+        // Tables are streamed and scored row by row; rows rarely carry every
+        // field, so they are always scored partially. The cohort indices need
+        // a first read of the file that keeps the fields they use for every
+        // row, so memory grows with the number of rows.
+        if allow_partial {
+            anyhow::bail!(
+                "--allow-partial applies to FDC JSON input; table rows are always scored partially"
+            );
+        }
+        let options = StreamOptions {
+            id_columns,
+            match_threshold,
+            food_patterns,
+            ..StreamOptions::new(delimiter_for(path))
+        };
+        let cohort =
+            ScoreStream::new(fs::File::open(path)?, &options)?.cohort_scores(energy_adjustment)?;
+        let mut stream =
+            ScoreStream::new(fs::File::open(path)?, &options)?.with_cohort_scores(cohort);
+        let mut out: Box<dyn Write + Send> = match &output {
+            Some(p) => Box::new(BufWriter::new(fs::File::create(p)?)),
+            None => Box::new(BufWriter::new(std::io::stdout())),
+        };
+        let layout =
+            TableLayout::from_registry(stream.id_columns(), stream.design_columns(), components);
+        let rows = stream.by_ref().inspect(|row| {
+            if let (true, Ok(row)) = (verbose_partial, row) {
+                if let Some(report) = format_skipped_scores(&row.result) {
+                    eprintln!("row {}:", row.row);
+                    eprint!("{}", report);
+                    eprintln!();
+                }
             }
-            let json = serde_json::to_string_pretty(&result)?;
-            println!("{}", json);
+        });
+        if format == OutputFormat::Json {
+            write_json_stream(rows, &mut out)?;
         } else {
-            let json = print_scores_as_json_allow_partial(&nv);
-            println!("{}", json);
+            let mut sink = format
+                .table_sink(&mut out)
+                .ok_or_else(|| anyhow::anyhow!("no table writer for {} output", format.name()))?;
+            write_table_stream(rows, &layout, sink.as_mut())?;
+        }
+        if let Some(p) = &coverage {
+            fs::write(p, format!("{}\n", to_string_pretty(&stream.coverage())?))?;
+        }
+        return Ok(());
+    }
+    let data = fs::read_to_string(path)?;
+    let result = match score_fdc(&data, allow_partial)? {
+        Ok(result) => result,
+        Err(err) if format == OutputFormat::Json => {
+            write_json(&to_string_pretty(&err)?, output.as_deref())?;
+            return Ok(());
+        }
+        Err(err) => anyhow::bail!(
+            "missing fields: {}; pass --allow-partial to score what is available",
+            err.missing_canonical_fields.join(", ")
+        ),
+    };
+    if verbose_partial {
+        if let Some(report) = format_skipped_scores(&result) {
+            eprint!("{}", report);
+            eprintln!();
+        }
+    }
+    if format != OutputFormat::Json {
+        let rows = vec![ScoredRow {
+            row: 0,
+            id: Default::default(),
+            design: Default::default(),
+            result,
+        }];
+        let table = wide_table(&rows, components);
+        match &output {
+            Some(p) => write_table(format, &table, &mut fs::File::create(p)?)?,
+            None => write_table(format, &table, &mut std::io::stdout())?,
         }
+        return Ok(());
+    }
+    write_json(&to_string_pretty(&result)?, output.as_deref())?;
+    Ok(())
+}
+
+/// Scores an FDC food, keeping its unit conversions in the trace. Unless
+/// `allow_partial`, fails with the fields some index requires and the food
+/// lacks, as [`evaluate_all_scores`] does.
+fn score_fdc(data: &str, allow_partial: bool) -> anyhow::Result<Result<ScoreResult, SchemaError>> {
+    let (nv, trace) = NutritionVector::from_fdc_json_traced(data)?;
+    let result = if allow_partial {
+        Ok(evaluate_allow_partial(&nv))
     } else {
-        let json = print_scores_as_json(&nv);
-        println!("{}", json);
+        evaluate_all_scores(&nv)
+    };
+    Ok(result.map(|mut result| {
+        result.trace.unit_conversions = trace.unit_conversions;
+        result.trace.unit_mismatches = trace.unit_mismatches;
+        result
+    }))
+}
+
+fn is_table(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+        ["csv", "tsv", "tab"]
+            .iter()
+            .any(|t| e.eq_ignore_ascii_case(t))
+    })
+}
+
+fn write_json(json: &str, output: Option<&str>) -> anyhow::Result<()> {
+    match output {
+        Some(path) => fs::write(path, format!("{}\n", json))?,
+        None => println!("{}", json),
     }
     Ok(())
 }
@@ -1,7 +1,9 @@
 use once_cell::sync::Lazy;
-use serde_json;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{self, Value};
+use std::collections::{BTreeMap, HashMap};
 
+use crate::food_patterns::FPED_COMPONENTS;
 use crate::nutrition_vector::{NutritionVector, SchemaError};
 
 static HEADER_ALIASES_JSON: &str = include_str!("../../schema/header_aliases.json");
@@ -38,25 +40,178 @@ pub fn resolve_nhanes_headers(raw_headers: &[String]) -> HashMap<String, Canonic
     map
 }
 
-pub fn build_nutrition_vector_from_nhanes(
-    row: &HashMap<String, f32>,
-) -> Result<NutritionVector, SchemaError> {
-    let mut temp: HashMap<&str, Vec<f64>> = HashMap::new();
-    for (k, v) in row {
-        if let Some(canon) = HEADER_MAP.get(k.to_ascii_lowercase().as_str()) {
-            temp.entry(*canon).or_default().push(*v as f64);
-        } else if crate::nutrition_vector::NutritionVector::all_field_names()
-            .contains(&k.as_str())
-        {
-            let key: &'static str = Box::leak(k.as_str().to_string().into_boxed_str());
-            temp.entry(key).or_default().push(*v as f64);
+/// How the 24-hour recalls of the two NHANES interview days are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecallDays {
+    /// Mean over the days that reported each field.
+    #[default]
+    MeanOfDays,
+    /// The in-person day 1 recall only.
+    Day1Only,
+    /// Mean of both days; fields missing on either day are left out.
+    RequireBoth,
+}
+
+impl RecallDays {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecallDays::MeanOfDays => "mean_of_days",
+            RecallDays::Day1Only => "day1_only",
+            RecallDays::RequireBoth => "require_both",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            RecallDays::MeanOfDays,
+            RecallDays::Day1Only,
+            RecallDays::RequireBoth,
+        ]
+        .into_iter()
+        .find(|m| m.name() == name)
+    }
+}
+
+/// Recall day of a raw NHANES header: `DR1*`/`DR2*` variables and labels
+/// ending in `Day 1`/`Day 2`.
+pub fn recall_day(header: &str) -> Option<u8> {
+    let upper = header.trim().to_ascii_uppercase();
+    for day in [1u8, 2] {
+        if upper.starts_with(&format!("DR{}", day)) || upper.ends_with(&format!("DAY {}", day)) {
+            return Some(day);
+        }
+    }
+    None
+}
+
+/// Recall days combined for one NHANES row.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecallSummary {
+    /// Days that contributed, in order.
+    pub days: Vec<u8>,
+    /// Columns of a contributing day that were left out because another
+    /// column of that day already gave their field, with the field.
+    pub conflicting_aliases: Vec<(String, CanonicalField)>,
+}
+
+// Fields of an FPED day total such as `DR1T_PF_SEAFD_HI`, with the amount
+// per unit.
+fn fped_fields(header: &str) -> Option<&'static [(&'static str, f64)]> {
+    let upper = header.trim().to_ascii_uppercase();
+    let component = upper
+        .strip_prefix("DR1T_")
+        .or_else(|| upper.strip_prefix("DR2T_"))?;
+    FPED_COMPONENTS
+        .iter()
+        .find(|(c, _)| *c == component)
+        .map(|(_, fields)| *fields)
+}
+
+/// Combine the recall days of a raw NHANES row according to `mode`.
+///
+/// FPED day totals are summed into the fields [`FPED_COMPONENTS`] lists,
+/// converted to grams for the gram fields, so the high and low n-3 seafood
+/// groups add up to one day's seafood. Any
+/// other columns of one day that name the same field, such as `DR1TKCAL` and
+/// `Total Calories - Day 1`, are synonyms: the first in `headers`, the
+/// column order of the file, is kept and the rest are reported as
+/// conflicting. Columns of `row` not in `headers` come after, by name. Day
+/// cells that are not numbers are left out as missing. The day totals are
+/// then combined; columns without a recall day are kept as they are.
+pub fn aggregate_nhanes_row(
+    row: &HashMap<String, Value>,
+    headers: &[String],
+    mode: RecallDays,
+) -> (HashMap<String, Value>, RecallSummary) {
+    let mut out: HashMap<String, Value> = HashMap::new();
+    let mut days: BTreeMap<u8, HashMap<&'static str, f64>> = BTreeMap::new();
+    let mut conflicts: Vec<(u8, String, CanonicalField)> = Vec::new();
+    let mut unlisted: Vec<&String> = row.keys().filter(|k| !headers.contains(k)).collect();
+    unlisted.sort();
+    let ordered = headers.iter().filter(|h| row.contains_key(*h));
+    for k in ordered.chain(unlisted) {
+        let v = &row[k];
+        let canon = HEADER_MAP.get(k.to_ascii_lowercase().as_str());
+        match (canon, recall_day(k), v.as_f64()) {
+            (Some(_), Some(_), None) => {}
+            (Some(&canon), Some(day), Some(num)) => {
+                let totals = days.entry(day).or_default();
+                if let Some(fields) = fped_fields(k) {
+                    for (field, per_unit) in fields {
+                        *totals.entry(field).or_insert(0.0) += num * per_unit;
+                    }
+                } else if totals.contains_key(canon) {
+                    conflicts.push((day, k.clone(), canon));
+                } else {
+                    totals.insert(canon, num);
+                }
+            }
+            (Some(&canon), _, _) => {
+                out.insert(canon.to_string(), v.clone());
+            }
+            (None, _, _) => {
+                out.insert(k.clone(), v.clone());
+            }
         }
     }
 
-    let mut flat: HashMap<String, f64> = HashMap::new();
-    for (canon, vals) in temp {
-        let avg = vals.iter().sum::<f64>() / vals.len() as f64;
-        flat.insert(canon.to_string(), avg);
+    let used: Vec<u8> = match mode {
+        RecallDays::Day1Only => days.keys().copied().filter(|&d| d == 1).collect(),
+        RecallDays::MeanOfDays => days.keys().copied().collect(),
+        RecallDays::RequireBoth if days.len() == 2 => days.keys().copied().collect(),
+        RecallDays::RequireBoth => Vec::new(),
+    };
+    let mut totals: BTreeMap<&'static str, Vec<f64>> = BTreeMap::new();
+    for day in &used {
+        for (&canon, &value) in &days[day] {
+            totals.entry(canon).or_default().push(value);
+        }
+    }
+    for (canon, values) in totals {
+        if mode == RecallDays::RequireBoth && values.len() < used.len() {
+            continue;
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        out.insert(canon.to_string(), serde_json::json!(mean));
     }
-    NutritionVector::from_map(&flat)
+    let conflicting_aliases = conflicts
+        .into_iter()
+        .filter(|(day, _, _)| used.contains(day))
+        .map(|(_, header, canon)| (header, canon))
+        .collect();
+    (
+        out,
+        RecallSummary {
+            days: used,
+            conflicting_aliases,
+        },
+    )
+}
+
+/// Build a vector from a raw NHANES row, averaging the recall days.
+pub fn build_nutrition_vector_from_nhanes(
+    row: &HashMap<String, f32>,
+) -> Result<NutritionVector, SchemaError> {
+    build_nutrition_vector_from_nhanes_days(row, RecallDays::MeanOfDays).map(|(nv, _)| nv)
+}
+
+/// Build a vector from a raw NHANES row with the recall days combined as
+/// `mode` says. Also returns the days that contributed. The map has no
+/// column order, so synonyms of one day are taken by header name.
+pub fn build_nutrition_vector_from_nhanes_days(
+    row: &HashMap<String, f32>,
+    mode: RecallDays,
+) -> Result<(NutritionVector, Vec<u8>), SchemaError> {
+    let raw: HashMap<String, Value> = row
+        .iter()
+        .map(|(k, v)| (k.clone(), serde_json::json!(*v as f64)))
+        .collect();
+    let (combined, recall) = aggregate_nhanes_row(&raw, &[], mode);
+    let flat: HashMap<String, f64> = combined
+        .into_iter()
+        .filter(|(k, _)| NutritionVector::all_field_names().contains(&k.as_str()))
+        .filter_map(|(k, v)| v.as_f64().map(|num| (k, num)))
+        .collect();
+    NutritionVector::from_map(&flat).map(|nv| (nv, recall.days))
 }
@@ -2,7 +2,13 @@ use once_cell::sync::Lazy;
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
+use crate::fdc_ingest::read_fdc_intake;
 use crate::food_item_resolver::{FOOD_RESOLVER, TranslationEntry};
+use crate::food_matching::{FoodSuggestion, DEFAULT_MATCH_THRESHOLD};
+use crate::food_patterns::FoodPatternTable;
+use crate::units::{
+    split_unit_suffix, to_canonical, ColumnUnits, Converted, UnitConversion, UnitMismatch,
+};
 use crate::unmapped_monitor::UNMAPPED_MONITOR;
 use log::info;
 #[cfg(feature = "hot_reload_aliases")]
@@ -45,43 +51,209 @@ pub struct NutritionVector {
     pub trans_fat: Option<f64>,
     #[serde(alias = "ALCOHOL", alias = "alcohol_intake")]
     pub alcohol: Option<f64>,
+    // Remaining parameters of the DII global standard database
+    pub anthocyanidins: Option<f64>,
+    pub beta_carotene: Option<f64>,
+    pub caffeine: Option<f64>,
+    pub cholesterol: Option<f64>,
+    pub eugenol: Option<f64>,
+    pub flavan3ols: Option<f64>,
+    pub flavanones: Option<f64>,
+    pub flavones: Option<f64>,
+    pub flavonols: Option<f64>,
+    pub folic_acid: Option<f64>,
+    pub garlic: Option<f64>,
+    pub ginger: Option<f64>,
+    pub isoflavones: Option<f64>,
+    pub niacin: Option<f64>,
+    pub omega6: Option<f64>,
+    pub onion: Option<f64>,
+    pub pepper: Option<f64>,
+    pub pufa: Option<f64>,
+    pub riboflavin: Option<f64>,
+    pub rosemary: Option<f64>,
+    pub saffron: Option<f64>,
+    pub tea: Option<f64>,
+    pub thiamin: Option<f64>,
+    pub thyme_oregano: Option<f64>,
+    pub turmeric: Option<f64>,
+    pub vitamin_b12: Option<f64>,
+    pub vitamin_b6: Option<f64>,
+    pub vitamin_d: Option<f64>,
+    // USDA food pattern equivalents (cup, ounce and teaspoon equivalents)
+    pub f_total: Option<f64>,
+    pub f_whole: Option<f64>,
+    pub v_total: Option<f64>,
+    pub v_greens_beans: Option<f64>,
+    pub g_whole: Option<f64>,
+    pub g_refined: Option<f64>,
+    pub d_total: Option<f64>,
+    pub pf_total: Option<f64>,
+    pub pf_seaplant: Option<f64>,
+    pub add_sugars: Option<f64>,
+    // Demographics used by age- and sex-specific standards
+    pub age: Option<f64>,
+    /// 1 = male, 2 = female (NHANES `RIAGENDR` coding).
+    pub sex: Option<f64>,
+    // AHEI-2010 servings per day and long-chain n-3 fats
+    pub veg_serv: Option<f64>,
+    pub fruit_serv: Option<f64>,
+    pub nuts_legumes_serv: Option<f64>,
+    pub ssb_serv: Option<f64>,
+    pub red_processed_meat_serv: Option<f64>,
+    pub epa_dha: Option<f64>,
+    // Mediterranean diet servings per day
+    pub olive_oil_serv: Option<f64>,
+    pub legumes_serv: Option<f64>,
+    pub nuts_serv: Option<f64>,
+    pub fish_serv: Option<f64>,
+    pub sweets_serv: Option<f64>,
+    pub discret_fat_serv: Option<f64>,
+    // ACS 2020 guideline score inputs
+    pub veg_variety: Option<f64>,
+    pub fruit_variety: Option<f64>,
+    pub hpf_rg_serv: Option<f64>,
+    // MIND servings per week, as reported by the HCNS 2013 food frequency questionnaire
+    pub leafy_greens_wk: Option<f64>,
+    pub other_veg_wk: Option<f64>,
+    pub berries_wk: Option<f64>,
+    pub nuts_wk: Option<f64>,
+    pub beans_wk: Option<f64>,
+    pub whole_grains_wk: Option<f64>,
+    pub fish_wk: Option<f64>,
+    pub poultry_wk: Option<f64>,
+    pub olive_oil_primary: Option<f64>,
+    pub wine_wk: Option<f64>,
+    pub red_meat_wk: Option<f64>,
+    pub butter_wk: Option<f64>,
+    pub cheese_wk: Option<f64>,
+    pub pastries_sweets_wk: Option<f64>,
+    pub fried_food_wk: Option<f64>,
+    // EAT-Lancet food groups and added fats (grams per day)
+    pub starchy_veg: Option<f64>,
+    pub dairy: Option<f64>,
+    pub eggs: Option<f64>,
+    pub soy: Option<f64>,
+    pub added_fat_unsat: Option<f64>,
+    pub added_fat_sat_trans: Option<f64>,
+    pub added_sugar_g: Option<f64>,
+    // DASH servings per day
+    pub low_fat_dairy_serv: Option<f64>,
 }
 
 static TEMPLATE_CSV: &str = include_str!("../../data/template.csv");
 /// Canonical field set derived from scoring contracts
 static CANONICAL_SET: &[&str] = &[
+    "add_sugars",
+    "added_fat_sat_trans",
+    "added_fat_unsat",
+    "added_sugar_g",
+    "age",
     "alcohol",
+    "anthocyanidins",
+    "beans_wk",
     "berries",
+    "berries_wk",
+    "beta_carotene",
     "butter",
+    "butter_wk",
+    "caffeine",
     "calcium",
     "carbs",
     "cheese",
+    "cheese_wk",
+    "cholesterol",
+    "d_total",
+    "dairy",
+    "discret_fat_serv",
+    "eggs",
     "energy",
+    "epa_dha",
+    "eugenol",
+    "f_total",
+    "f_whole",
     "fast_food",
     "fat",
     "fiber",
     "fish",
+    "fish_serv",
+    "fish_wk",
+    "flavan3ols",
+    "flavanones",
+    "flavones",
+    "flavonols",
+    "folic_acid",
+    "fried_food_wk",
+    "fruit_serv",
+    "fruit_variety",
+    "g_refined",
+    "g_whole",
+    "garlic",
+    "ginger",
+    "hpf_rg_serv",
     "iron",
+    "isoflavones",
+    "leafy_greens_wk",
     "legumes",
+    "legumes_serv",
+    "low_fat_dairy_serv",
     "magnesium",
     "mono_fat",
+    "niacin",
     "nuts",
+    "nuts_legumes_serv",
+    "nuts_serv",
+    "nuts_wk",
+    "olive_oil_primary",
+    "olive_oil_serv",
     "omega3",
+    "omega6",
+    "onion",
+    "other_veg_wk",
+    "pastries_sweets_wk",
+    "pepper",
+    "pf_seaplant",
+    "pf_total",
     "poultry",
+    "poultry_wk",
     "protein",
+    "pufa",
     "red_meat",
+    "red_meat_wk",
+    "red_processed_meat_serv",
     "refined_grains",
+    "riboflavin",
+    "rosemary",
+    "saffron",
     "saturated_fat",
     "selenium",
+    "sex",
     "sodium",
+    "soy",
+    "ssb_serv",
+    "starchy_veg",
     "sugar",
+    "sweets_serv",
+    "tea",
+    "thiamin",
+    "thyme_oregano",
     "total_fruits",
     "trans_fat",
+    "turmeric",
+    "v_greens_beans",
+    "v_total",
+    "veg_serv",
+    "veg_variety",
     "vegetables",
     "vitamin_a",
+    "vitamin_b12",
+    "vitamin_b6",
     "vitamin_c",
+    "vitamin_d",
     "vitamin_e",
     "whole_grains",
+    "whole_grains_wk",
+    "wine_wk",
     "zinc",
 ];
 
@@ -183,12 +355,16 @@ fn canonical_field(name: &str) -> Option<&'static str> {
         .copied()
 }
 
-fn guess_canonical(name: &str) -> Option<&'static str> {
-    let lower = name.to_ascii_lowercase();
-    ALL_FIELD_NAMES
-        .iter()
-        .copied()
-        .find(|f| lower.contains(f.trim_end_matches("_g")))
+/// Canonical field of a column and the unit its name declares. Columns that
+/// are not aliases themselves may still name a field with a unit suffix, as
+/// in `sodium_g`.
+fn resolve_column(name: &str) -> Option<(&'static str, Option<&'static str>)> {
+    let suffix_unit = split_unit_suffix(name);
+    match canonical_field(name) {
+        Some(canon) => Some((canon, suffix_unit.map(|(_, unit)| unit))),
+        None => suffix_unit
+            .and_then(|(base, unit)| canonical_field(base).map(|canon| (canon, Some(unit)))),
+    }
 }
 
 use serde::Serialize;
@@ -208,6 +384,23 @@ pub struct InputTrace {
     pub aliases_applied: Vec<(String, &'static str)>,
     pub conflicting_aliases: Vec<(String, &'static str)>,
     pub translation_log: HashMap<&'static str, TranslationEntry>,
+    /// NHANES recall days combined into this record, see
+    /// [`crate::nhanes_ingest::RecallDays`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recall_days: Vec<u8>,
+    /// Values converted to the canonical unit of their field.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unit_conversions: Vec<UnitConversion>,
+    /// Values left out because their unit cannot be converted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unit_mismatches: Vec<UnitMismatch>,
+    /// Food columns left unresolved that may name a known food.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub food_suggestions: Vec<FoodSuggestion>,
+    /// Recipe ingredients naming no known item, as the path from the input
+    /// key joined by ` > `. The rest of the recipe is still counted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unresolved_ingredients: Vec<String>,
 }
 
 impl InputTrace {
@@ -224,6 +417,11 @@ impl InputTrace {
             aliases_applied: Vec::new(),
             conflicting_aliases: Vec::new(),
             translation_log: HashMap::new(),
+            recall_days: Vec::new(),
+            unit_conversions: Vec::new(),
+            unit_mismatches: Vec::new(),
+            food_suggestions: Vec::new(),
+            unresolved_ingredients: Vec::new(),
         }
     }
 }
@@ -261,113 +459,44 @@ impl SchemaError {
 
 impl NutritionVector {
     pub fn from_fdc_json(data: &str) -> anyhow::Result<Self> {
+        Ok(Self::from_fdc_json_traced(data)?.0)
+    }
+
+    /// Parses a FoodData Central food or a list of consumed foods, see
+    /// [`crate::fdc_ingest`]. Returns the trace of unit conversions and of
+    /// nutrients whose unit could not be converted.
+    pub fn from_fdc_json_traced(data: &str) -> anyhow::Result<(Self, InputTrace)> {
         let v: Value = serde_json::from_str(data)?;
+        let intake = read_fdc_intake(&v)?;
         let mut nv = NutritionVector::default();
-        if let Some(items) = v.get("foodNutrients").and_then(|v| v.as_array()) {
-            let mut map: HashMap<&str, f64> = HashMap::new();
-            for item in items {
-                if let (Some(nutrient), Some(amount)) = (item.get("nutrient"), item.get("amount")) {
-                    let name = nutrient.get("name").and_then(|n| n.as_str()).unwrap_or("");
-                    let unit = nutrient
-                        .get("unitName")
-                        .and_then(|n| n.as_str())
-                        .unwrap_or("");
-                    let amount = amount.as_f64().unwrap_or(0.0);
-                    map.insert(
-                        name,
-                        match unit.to_ascii_lowercase().as_str() {
-                            "mg" => amount / 1000.0,
-                            _ => amount,
-                        },
-                    );
-                }
+        for (field, value) in &intake.fields {
+            if let Some(slot) = nv.field_mut(field) {
+                *slot = Some(*value);
             }
-            nv.energy = map.get("Energy").copied();
-            nv.fat = map.get("Total lipid (fat)").copied();
-            nv.saturated_fat = map.get("Fatty acids, total saturated").copied();
-            nv.carbs = map.get("Carbohydrate, by difference").copied();
-            nv.fiber = map.get("Fiber, total dietary").copied();
-            nv.sugar = map.get("Sugars, total including NLEA").copied();
-            nv.protein = map.get("Protein").copied();
-            nv.sodium = map.get("Sodium, Na").map(|v| v * 1000.0);
-            nv.calcium = map.get("Calcium, Ca").map(|v| v * 1000.0);
-            nv.iron = map.get("Iron, Fe").map(|v| v * 1000.0);
-            nv.vitamin_c = map
-                .get("Vitamin C, total ascorbic acid")
-                .map(|v| v * 1000.0);
-            nv.omega3 = map.get("Fatty acids, total polyunsaturated").copied();
-            nv.vitamin_a = map.get("Vitamin A, RAE").copied();
-            nv.vitamin_e = map.get("Vitamin E (alpha-tocopherol)").map(|v| v * 1000.0);
-            nv.zinc = map.get("Zinc, Zn").map(|v| v * 1000.0);
-            nv.selenium = map.get("Selenium, Se").copied();
-            nv.magnesium = map.get("Magnesium, Mg").map(|v| v * 1000.0);
-            nv.trans_fat = map.get("Fatty acids, total trans").copied();
-            nv.alcohol = map.get("Alcohol, ethyl").copied();
         }
-        Ok(nv)
+        let mut trace = InputTrace::from_nv(&nv);
+        trace.unit_conversions = intake.unit_conversions;
+        trace.unit_mismatches = intake.unit_mismatches;
+        Ok((nv, trace))
     }
 
+    /// Maps columns as [`Self::from_partial_map`] does, unit suffixes
+    /// included, and fails unless every field some index requires is
+    /// present and every column was matched without conflict.
     pub fn from_map(data: &HashMap<String, f64>) -> Result<Self, SchemaError> {
-        let mut obj = serde_json::Map::new();
-        let mut unmapped = Vec::new();
-        let mut conflicts = Vec::new();
-        let mut chosen: HashMap<&str, String> = HashMap::new();
-        let mut food_contrib: HashMap<&'static str, f64> = HashMap::new();
-
-        let mut items: Vec<(&String, &f64)> = data.iter().collect();
-        items.sort_by_key(|(k, _)| {
-            canonical_field(k)
-                .and_then(|c| FIELD_ORDER_MAP.get(c))
-                .copied()
-                .unwrap_or(usize::MAX)
-        });
-
-        for (k, v) in items {
-            match canonical_field(k) {
-                Some(canon) => {
-                    if obj.contains_key(canon) {
-                        let current = chosen.get(canon).cloned().unwrap_or_else(|| canon.to_string());
-                        if k != canon {
-                            conflicts.push((k.clone(), canon));
-                        } else if current != canon {
-                            conflicts.push((current.clone(), canon));
-                        }
-                        let prefer_new = k == canon && current != canon;
-                        if prefer_new {
-                            obj.insert(canon.to_string(), serde_json::json!(v));
-                            chosen.insert(canon, k.clone());
-                        }
-                    } else {
-                        obj.insert(canon.to_string(), serde_json::json!(v));
-                        chosen.insert(canon, k.clone());
-                    }
-                }
-                None => {
-                    if let Some(map) = FOOD_RESOLVER.resolve(k, *v) {
-                        for (field, val) in map {
-                            *food_contrib.entry(field).or_insert(0.0) += val;
-                        }
-                    } else {
-                        info!("TODO: map food field {}", k);
-                        UNMAPPED_MONITOR.log(k, Some(*v));
-                        unmapped.push(k.clone());
-                    }
-                }
-            }
-        }
-
-        for (field, val) in food_contrib {
-            if let Some(existing) = obj.get_mut(field) {
-                if let Some(num) = existing.as_f64() {
-                    *existing = serde_json::json!(num + val);
-                }
-            } else {
-                obj.insert(field.to_string(), serde_json::json!(val));
-            }
-        }
-
-        let nv: NutritionVector = serde_json::from_value(Value::Object(obj)).unwrap_or_default();
-        let missing = nv.missing_fields();
+        let values: HashMap<String, Value> = data
+            .iter()
+            .map(|(k, v)| (k.clone(), serde_json::json!(v)))
+            .collect();
+        let (nv, trace, unmapped) = Self::map_partial(
+            &values,
+            &HashMap::new(),
+            DEFAULT_MATCH_THRESHOLD,
+            None,
+            true,
+        );
+        let missing = nv.missing_required_fields();
+        let conflicts = trace.conflicting_aliases;
         if !missing.is_empty() || !unmapped.is_empty() || !conflicts.is_empty() {
             return Err(SchemaError::new(missing, unmapped, conflicts));
         }
@@ -375,23 +504,80 @@ impl NutritionVector {
     }
 
     pub fn from_partial_map(data: &HashMap<String, Value>) -> (Self, InputTrace) {
+        Self::from_partial_map_with_units(data, &HashMap::new(), DEFAULT_MATCH_THRESHOLD, None)
+    }
+
+    /// Like [`Self::from_partial_map`], with the units of some columns given
+    /// explicitly, e.g. from a units header row. Values are converted to the
+    /// canonical units, see [`crate::units`]. Food columns are matched to
+    /// food items when their similarity reaches `match_threshold`, and to the
+    /// foods of `food_patterns` by code or description when one is given.
+    pub fn from_partial_map_with_units(
+        data: &HashMap<String, Value>,
+        units: &ColumnUnits,
+        match_threshold: f64,
+        food_patterns: Option<&FoodPatternTable>,
+    ) -> (Self, InputTrace) {
+        let (nv, trace, _) = Self::map_partial(data, units, match_threshold, food_patterns, true);
+        (nv, trace)
+    }
+
+    /// Like [`Self::from_partial_map_with_units`] without the trace, and
+    /// without logging unmatched columns to [`UNMAPPED_MONITOR`] or looking
+    /// up food suggestions for them: for passes over rows that are mapped
+    /// again with their trace later.
+    pub fn from_partial_map_quiet(
+        data: &HashMap<String, Value>,
+        units: &ColumnUnits,
+        match_threshold: f64,
+        food_patterns: Option<&FoodPatternTable>,
+    ) -> Self {
+        Self::map_partial(data, units, match_threshold, food_patterns, false).0
+    }
+
+    /// Also returns the columns that named neither a field nor a food item.
+    fn map_partial(
+        data: &HashMap<String, Value>,
+        units: &ColumnUnits,
+        match_threshold: f64,
+        food_patterns: Option<&FoodPatternTable>,
+        report_unmatched: bool,
+    ) -> (Self, InputTrace, Vec<String>) {
         let mut obj = serde_json::Map::new();
+        let mut conversions: Vec<UnitConversion> = Vec::new();
+        let mut mismatches: Vec<UnitMismatch> = Vec::new();
         let mut aliases = Vec::new();
         let mut conflicts = Vec::new();
         let mut chosen: HashMap<&str, String> = HashMap::new();
         let mut translation: HashMap<&'static str, TranslationEntry> = HashMap::new();
         let mut food_contrib: HashMap<&'static str, f64> = HashMap::new();
+        let mut suggestions = Vec::new();
+        let mut unresolved = Vec::new();
+        let mut unmatched = Vec::new();
 
         let mut items: Vec<(&String, &Value)> = data.iter().collect();
         items.sort_by_key(|(k, _)| {
-            canonical_field(k)
-                .and_then(|c| FIELD_ORDER_MAP.get(c))
+            resolve_column(k)
+                .and_then(|(c, _)| FIELD_ORDER_MAP.get(c))
                 .copied()
                 .unwrap_or(usize::MAX)
         });
 
         for (k, v) in items {
-            if let Some(canon) = canonical_field(k) {
+            if let Some((canon, suffix_unit)) = resolve_column(k) {
+                let unit = units.get(k).map(String::as_str).or(suffix_unit);
+                let converted = v.as_f64().map(|num| to_canonical(k, canon, num, unit));
+                let (v, conversion) = match converted {
+                    Some(Converted::Converted(num, conversion)) => {
+                        (serde_json::json!(num), Some(conversion))
+                    }
+                    Some(Converted::Mismatch(mismatch)) => {
+                        mismatches.push(mismatch);
+                        continue;
+                    }
+                    Some(Converted::Unchanged(_)) | None => (v.clone(), None),
+                };
+                let v = &v;
                 if obj.contains_key(canon) {
                     let current = chosen.get(canon).cloned().unwrap_or_else(|| canon.to_string());
                     if k != canon {
@@ -403,6 +589,8 @@ impl NutritionVector {
                     if prefer_new {
                         obj.insert(canon.to_string(), v.clone());
                         chosen.insert(canon, k.clone());
+                        conversions.retain(|c| c.field != canon);
+                        conversions.extend(conversion);
                     }
                 } else {
                     if canon != k.as_str() {
@@ -410,18 +598,33 @@ impl NutritionVector {
                     }
                     obj.insert(canon.to_string(), v.clone());
                     chosen.insert(canon, k.clone());
+                    conversions.extend(conversion);
                 }
             } else if let Some(num) = v.as_f64() {
-                if let Some(map) = FOOD_RESOLVER.resolve(k, num) {
-                    for (field, val) in map {
-                        *food_contrib.entry(field).or_insert(0.0) += val;
-                        let entry = translation.entry(field).or_default();
-                        entry.value += val;
-                        entry.source.push(k.clone());
+                if let Some(resolution) =
+                    FOOD_RESOLVER.resolve_traced(k, num, match_threshold, food_patterns)
+                {
+                    unresolved.extend(resolution.unresolved.iter().map(|p| p.join(" > ")));
+                    for c in resolution.contributions {
+                        *food_contrib.entry(c.field).or_insert(0.0) += c.value;
+                        let entry = translation.entry(c.field).or_default();
+                        entry.value += c.value;
+                        let source = c.path.join(" > ");
+                        if !entry.source.contains(&source) {
+                            entry.source.push(source);
+                        }
                     }
                 } else {
-                    info!("TODO: map food field {}", k);
-                    UNMAPPED_MONITOR.log(k, Some(num));
+                    if report_unmatched {
+                        info!("TODO: map food field {}", k);
+                        UNMAPPED_MONITOR.log(k, Some(num));
+                        suggestions.extend(FOOD_RESOLVER.suggest(
+                            k,
+                            match_threshold,
+                            food_patterns,
+                        ));
+                    }
+                    unmatched.push(k.clone());
                 }
             }
         }
@@ -449,10 +652,27 @@ impl NutritionVector {
                 aliases_applied: aliases,
                 conflicting_aliases: conflicts,
                 translation_log: translation,
+                recall_days: Vec::new(),
+                unit_conversions: conversions,
+                unit_mismatches: mismatches,
+                food_suggestions: suggestions,
+                unresolved_ingredients: unresolved,
             },
+            unmatched,
         )
     }
 
+    /// The [`Self::missing_fields`] that some per-record scorer requires.
+    /// A record missing none of them is scored on every index of
+    /// [`all_scorers`](crate::scores::all_scorers).
+    pub fn missing_required_fields(&self) -> Vec<&'static str> {
+        let scorers = crate::scores::all_scorers();
+        self.missing_fields()
+            .into_iter()
+            .filter(|f| scorers.iter().any(|s| s.required_fields().contains(f)))
+            .collect()
+    }
+
     pub fn missing_fields(&self) -> Vec<&'static str> {
         let mut missing = Vec::new();
         if self.energy.is_none() {
@@ -554,10 +774,480 @@ impl NutritionVector {
         if self.alcohol.is_none() {
             missing.push("alcohol");
         }
+        if self.anthocyanidins.is_none() {
+            missing.push("anthocyanidins");
+        }
+        if self.beta_carotene.is_none() {
+            missing.push("beta_carotene");
+        }
+        if self.caffeine.is_none() {
+            missing.push("caffeine");
+        }
+        if self.cholesterol.is_none() {
+            missing.push("cholesterol");
+        }
+        if self.eugenol.is_none() {
+            missing.push("eugenol");
+        }
+        if self.flavan3ols.is_none() {
+            missing.push("flavan3ols");
+        }
+        if self.flavanones.is_none() {
+            missing.push("flavanones");
+        }
+        if self.flavones.is_none() {
+            missing.push("flavones");
+        }
+        if self.flavonols.is_none() {
+            missing.push("flavonols");
+        }
+        if self.folic_acid.is_none() {
+            missing.push("folic_acid");
+        }
+        if self.garlic.is_none() {
+            missing.push("garlic");
+        }
+        if self.ginger.is_none() {
+            missing.push("ginger");
+        }
+        if self.isoflavones.is_none() {
+            missing.push("isoflavones");
+        }
+        if self.niacin.is_none() {
+            missing.push("niacin");
+        }
+        if self.omega6.is_none() {
+            missing.push("omega6");
+        }
+        if self.onion.is_none() {
+            missing.push("onion");
+        }
+        if self.pepper.is_none() {
+            missing.push("pepper");
+        }
+        if self.pufa.is_none() {
+            missing.push("pufa");
+        }
+        if self.riboflavin.is_none() {
+            missing.push("riboflavin");
+        }
+        if self.rosemary.is_none() {
+            missing.push("rosemary");
+        }
+        if self.saffron.is_none() {
+            missing.push("saffron");
+        }
+        if self.tea.is_none() {
+            missing.push("tea");
+        }
+        if self.thiamin.is_none() {
+            missing.push("thiamin");
+        }
+        if self.thyme_oregano.is_none() {
+            missing.push("thyme_oregano");
+        }
+        if self.turmeric.is_none() {
+            missing.push("turmeric");
+        }
+        if self.vitamin_b12.is_none() {
+            missing.push("vitamin_b12");
+        }
+        if self.vitamin_b6.is_none() {
+            missing.push("vitamin_b6");
+        }
+        if self.vitamin_d.is_none() {
+            missing.push("vitamin_d");
+        }
+        if self.f_total.is_none() {
+            missing.push("f_total");
+        }
+        if self.f_whole.is_none() {
+            missing.push("f_whole");
+        }
+        if self.v_total.is_none() {
+            missing.push("v_total");
+        }
+        if self.v_greens_beans.is_none() {
+            missing.push("v_greens_beans");
+        }
+        if self.g_whole.is_none() {
+            missing.push("g_whole");
+        }
+        if self.g_refined.is_none() {
+            missing.push("g_refined");
+        }
+        if self.d_total.is_none() {
+            missing.push("d_total");
+        }
+        if self.pf_total.is_none() {
+            missing.push("pf_total");
+        }
+        if self.pf_seaplant.is_none() {
+            missing.push("pf_seaplant");
+        }
+        if self.add_sugars.is_none() {
+            missing.push("add_sugars");
+        }
+        if self.age.is_none() {
+            missing.push("age");
+        }
+        if self.sex.is_none() {
+            missing.push("sex");
+        }
+        if self.veg_serv.is_none() {
+            missing.push("veg_serv");
+        }
+        if self.fruit_serv.is_none() {
+            missing.push("fruit_serv");
+        }
+        if self.nuts_legumes_serv.is_none() {
+            missing.push("nuts_legumes_serv");
+        }
+        if self.ssb_serv.is_none() {
+            missing.push("ssb_serv");
+        }
+        if self.red_processed_meat_serv.is_none() {
+            missing.push("red_processed_meat_serv");
+        }
+        if self.epa_dha.is_none() {
+            missing.push("epa_dha");
+        }
+        if self.olive_oil_serv.is_none() {
+            missing.push("olive_oil_serv");
+        }
+        if self.legumes_serv.is_none() {
+            missing.push("legumes_serv");
+        }
+        if self.nuts_serv.is_none() {
+            missing.push("nuts_serv");
+        }
+        if self.fish_serv.is_none() {
+            missing.push("fish_serv");
+        }
+        if self.sweets_serv.is_none() {
+            missing.push("sweets_serv");
+        }
+        if self.discret_fat_serv.is_none() {
+            missing.push("discret_fat_serv");
+        }
+        if self.veg_variety.is_none() {
+            missing.push("veg_variety");
+        }
+        if self.fruit_variety.is_none() {
+            missing.push("fruit_variety");
+        }
+        if self.hpf_rg_serv.is_none() {
+            missing.push("hpf_rg_serv");
+        }
+        if self.leafy_greens_wk.is_none() {
+            missing.push("leafy_greens_wk");
+        }
+        if self.other_veg_wk.is_none() {
+            missing.push("other_veg_wk");
+        }
+        if self.berries_wk.is_none() {
+            missing.push("berries_wk");
+        }
+        if self.nuts_wk.is_none() {
+            missing.push("nuts_wk");
+        }
+        if self.beans_wk.is_none() {
+            missing.push("beans_wk");
+        }
+        if self.whole_grains_wk.is_none() {
+            missing.push("whole_grains_wk");
+        }
+        if self.fish_wk.is_none() {
+            missing.push("fish_wk");
+        }
+        if self.poultry_wk.is_none() {
+            missing.push("poultry_wk");
+        }
+        if self.olive_oil_primary.is_none() {
+            missing.push("olive_oil_primary");
+        }
+        if self.wine_wk.is_none() {
+            missing.push("wine_wk");
+        }
+        if self.red_meat_wk.is_none() {
+            missing.push("red_meat_wk");
+        }
+        if self.butter_wk.is_none() {
+            missing.push("butter_wk");
+        }
+        if self.cheese_wk.is_none() {
+            missing.push("cheese_wk");
+        }
+        if self.pastries_sweets_wk.is_none() {
+            missing.push("pastries_sweets_wk");
+        }
+        if self.fried_food_wk.is_none() {
+            missing.push("fried_food_wk");
+        }
+        if self.starchy_veg.is_none() {
+            missing.push("starchy_veg");
+        }
+        if self.dairy.is_none() {
+            missing.push("dairy");
+        }
+        if self.eggs.is_none() {
+            missing.push("eggs");
+        }
+        if self.soy.is_none() {
+            missing.push("soy");
+        }
+        if self.added_fat_unsat.is_none() {
+            missing.push("added_fat_unsat");
+        }
+        if self.added_fat_sat_trans.is_none() {
+            missing.push("added_fat_sat_trans");
+        }
+        if self.added_sugar_g.is_none() {
+            missing.push("added_sugar_g");
+        }
+        if self.low_fat_dairy_serv.is_none() {
+            missing.push("low_fat_dairy_serv");
+        }
         missing
     }
 
     pub fn all_field_names() -> &'static [&'static str] {
         ALL_FIELD_NAMES.as_slice()
     }
+
+    /// Look up a canonical field by name.
+    pub fn get(&self, field: &str) -> Option<f64> {
+        match field {
+            "energy" => self.energy,
+            "fat" => self.fat,
+            "saturated_fat" => self.saturated_fat,
+            "carbs" => self.carbs,
+            "fiber" => self.fiber,
+            "sugar" => self.sugar,
+            "protein" => self.protein,
+            "sodium" => self.sodium,
+            "calcium" => self.calcium,
+            "iron" => self.iron,
+            "vitamin_c" => self.vitamin_c,
+            "total_fruits" => self.total_fruits,
+            "vegetables" => self.vegetables,
+            "whole_grains" => self.whole_grains,
+            "refined_grains" => self.refined_grains,
+            "legumes" => self.legumes,
+            "fish" => self.fish,
+            "red_meat" => self.red_meat,
+            "mono_fat" => self.mono_fat,
+            "berries" => self.berries,
+            "cheese" => self.cheese,
+            "butter" => self.butter,
+            "poultry" => self.poultry,
+            "fast_food" => self.fast_food,
+            "nuts" => self.nuts,
+            "omega3" => self.omega3,
+            "vitamin_a" => self.vitamin_a,
+            "vitamin_e" => self.vitamin_e,
+            "zinc" => self.zinc,
+            "selenium" => self.selenium,
+            "magnesium" => self.magnesium,
+            "trans_fat" => self.trans_fat,
+            "alcohol" => self.alcohol,
+            "anthocyanidins" => self.anthocyanidins,
+            "beta_carotene" => self.beta_carotene,
+            "caffeine" => self.caffeine,
+            "cholesterol" => self.cholesterol,
+            "eugenol" => self.eugenol,
+            "flavan3ols" => self.flavan3ols,
+            "flavanones" => self.flavanones,
+            "flavones" => self.flavones,
+            "flavonols" => self.flavonols,
+            "folic_acid" => self.folic_acid,
+            "garlic" => self.garlic,
+            "ginger" => self.ginger,
+            "isoflavones" => self.isoflavones,
+            "niacin" => self.niacin,
+            "omega6" => self.omega6,
+            "onion" => self.onion,
+            "pepper" => self.pepper,
+            "pufa" => self.pufa,
+            "riboflavin" => self.riboflavin,
+            "rosemary" => self.rosemary,
+            "saffron" => self.saffron,
+            "tea" => self.tea,
+            "thiamin" => self.thiamin,
+            "thyme_oregano" => self.thyme_oregano,
+            "turmeric" => self.turmeric,
+            "vitamin_b12" => self.vitamin_b12,
+            "vitamin_b6" => self.vitamin_b6,
+            "vitamin_d" => self.vitamin_d,
+            "f_total" => self.f_total,
+            "f_whole" => self.f_whole,
+            "v_total" => self.v_total,
+            "v_greens_beans" => self.v_greens_beans,
+            "g_whole" => self.g_whole,
+            "g_refined" => self.g_refined,
+            "d_total" => self.d_total,
+            "pf_total" => self.pf_total,
+            "pf_seaplant" => self.pf_seaplant,
+            "add_sugars" => self.add_sugars,
+            "age" => self.age,
+            "sex" => self.sex,
+            "veg_serv" => self.veg_serv,
+            "fruit_serv" => self.fruit_serv,
+            "nuts_legumes_serv" => self.nuts_legumes_serv,
+            "ssb_serv" => self.ssb_serv,
+            "red_processed_meat_serv" => self.red_processed_meat_serv,
+            "epa_dha" => self.epa_dha,
+            "olive_oil_serv" => self.olive_oil_serv,
+            "legumes_serv" => self.legumes_serv,
+            "nuts_serv" => self.nuts_serv,
+            "fish_serv" => self.fish_serv,
+            "sweets_serv" => self.sweets_serv,
+            "discret_fat_serv" => self.discret_fat_serv,
+            "veg_variety" => self.veg_variety,
+            "fruit_variety" => self.fruit_variety,
+            "hpf_rg_serv" => self.hpf_rg_serv,
+            "leafy_greens_wk" => self.leafy_greens_wk,
+            "other_veg_wk" => self.other_veg_wk,
+            "berries_wk" => self.berries_wk,
+            "nuts_wk" => self.nuts_wk,
+            "beans_wk" => self.beans_wk,
+            "whole_grains_wk" => self.whole_grains_wk,
+            "fish_wk" => self.fish_wk,
+            "poultry_wk" => self.poultry_wk,
+            "olive_oil_primary" => self.olive_oil_primary,
+            "wine_wk" => self.wine_wk,
+            "red_meat_wk" => self.red_meat_wk,
+            "butter_wk" => self.butter_wk,
+            "cheese_wk" => self.cheese_wk,
+            "pastries_sweets_wk" => self.pastries_sweets_wk,
+            "fried_food_wk" => self.fried_food_wk,
+            "starchy_veg" => self.starchy_veg,
+            "dairy" => self.dairy,
+            "eggs" => self.eggs,
+            "soy" => self.soy,
+            "added_fat_unsat" => self.added_fat_unsat,
+            "added_fat_sat_trans" => self.added_fat_sat_trans,
+            "added_sugar_g" => self.added_sugar_g,
+            "low_fat_dairy_serv" => self.low_fat_dairy_serv,
+            _ => None,
+        }
+    }
+
+    /// Mutable access to a canonical field by name.
+    pub fn field_mut(&mut self, field: &str) -> Option<&mut Option<f64>> {
+        match field {
+            "energy" => Some(&mut self.energy),
+            "fat" => Some(&mut self.fat),
+            "saturated_fat" => Some(&mut self.saturated_fat),
+            "carbs" => Some(&mut self.carbs),
+            "fiber" => Some(&mut self.fiber),
+            "sugar" => Some(&mut self.sugar),
+            "protein" => Some(&mut self.protein),
+            "sodium" => Some(&mut self.sodium),
+            "calcium" => Some(&mut self.calcium),
+            "iron" => Some(&mut self.iron),
+            "vitamin_c" => Some(&mut self.vitamin_c),
+            "total_fruits" => Some(&mut self.total_fruits),
+            "vegetables" => Some(&mut self.vegetables),
+            "whole_grains" => Some(&mut self.whole_grains),
+            "refined_grains" => Some(&mut self.refined_grains),
+            "legumes" => Some(&mut self.legumes),
+            "fish" => Some(&mut self.fish),
+            "red_meat" => Some(&mut self.red_meat),
+            "mono_fat" => Some(&mut self.mono_fat),
+            "berries" => Some(&mut self.berries),
+            "cheese" => Some(&mut self.cheese),
+            "butter" => Some(&mut self.butter),
+            "poultry" => Some(&mut self.poultry),
+            "fast_food" => Some(&mut self.fast_food),
+            "nuts" => Some(&mut self.nuts),
+            "omega3" => Some(&mut self.omega3),
+            "vitamin_a" => Some(&mut self.vitamin_a),
+            "vitamin_e" => Some(&mut self.vitamin_e),
+            "zinc" => Some(&mut self.zinc),
+            "selenium" => Some(&mut self.selenium),
+            "magnesium" => Some(&mut self.magnesium),
+            "trans_fat" => Some(&mut self.trans_fat),
+            "alcohol" => Some(&mut self.alcohol),
+            "anthocyanidins" => Some(&mut self.anthocyanidins),
+            "beta_carotene" => Some(&mut self.beta_carotene),
+            "caffeine" => Some(&mut self.caffeine),
+            "cholesterol" => Some(&mut self.cholesterol),
+            "eugenol" => Some(&mut self.eugenol),
+            "flavan3ols" => Some(&mut self.flavan3ols),
+            "flavanones" => Some(&mut self.flavanones),
+            "flavones" => Some(&mut self.flavones),
+            "flavonols" => Some(&mut self.flavonols),
+            "folic_acid" => Some(&mut self.folic_acid),
+            "garlic" => Some(&mut self.garlic),
+            "ginger" => Some(&mut self.ginger),
+            "isoflavones" => Some(&mut self.isoflavones),
+            "niacin" => Some(&mut self.niacin),
+            "omega6" => Some(&mut self.omega6),
+            "onion" => Some(&mut self.onion),
+            "pepper" => Some(&mut self.pepper),
+            "pufa" => Some(&mut self.pufa),
+            "riboflavin" => Some(&mut self.riboflavin),
+            "rosemary" => Some(&mut self.rosemary),
+            "saffron" => Some(&mut self.saffron),
+            "tea" => Some(&mut self.tea),
+            "thiamin" => Some(&mut self.thiamin),
+            "thyme_oregano" => Some(&mut self.thyme_oregano),
+            "turmeric" => Some(&mut self.turmeric),
+            "vitamin_b12" => Some(&mut self.vitamin_b12),
+            "vitamin_b6" => Some(&mut self.vitamin_b6),
+            "vitamin_d" => Some(&mut self.vitamin_d),
+            "f_total" => Some(&mut self.f_total),
+            "f_whole" => Some(&mut self.f_whole),
+            "v_total" => Some(&mut self.v_total),
+            "v_greens_beans" => Some(&mut self.v_greens_beans),
+            "g_whole" => Some(&mut self.g_whole),
+            "g_refined" => Some(&mut self.g_refined),
+            "d_total" => Some(&mut self.d_total),
+            "pf_total" => Some(&mut self.pf_total),
+            "pf_seaplant" => Some(&mut self.pf_seaplant),
+            "add_sugars" => Some(&mut self.add_sugars),
+            "age" => Some(&mut self.age),
+            "sex" => Some(&mut self.sex),
+            "veg_serv" => Some(&mut self.veg_serv),
+            "fruit_serv" => Some(&mut self.fruit_serv),
+            "nuts_legumes_serv" => Some(&mut self.nuts_legumes_serv),
+            "ssb_serv" => Some(&mut self.ssb_serv),
+            "red_processed_meat_serv" => Some(&mut self.red_processed_meat_serv),
+            "epa_dha" => Some(&mut self.epa_dha),
+            "olive_oil_serv" => Some(&mut self.olive_oil_serv),
+            "legumes_serv" => Some(&mut self.legumes_serv),
+            "nuts_serv" => Some(&mut self.nuts_serv),
+            "fish_serv" => Some(&mut self.fish_serv),
+            "sweets_serv" => Some(&mut self.sweets_serv),
+            "discret_fat_serv" => Some(&mut self.discret_fat_serv),
+            "veg_variety" => Some(&mut self.veg_variety),
+            "fruit_variety" => Some(&mut self.fruit_variety),
+            "hpf_rg_serv" => Some(&mut self.hpf_rg_serv),
+            "leafy_greens_wk" => Some(&mut self.leafy_greens_wk),
+            "other_veg_wk" => Some(&mut self.other_veg_wk),
+            "berries_wk" => Some(&mut self.berries_wk),
+            "nuts_wk" => Some(&mut self.nuts_wk),
+            "beans_wk" => Some(&mut self.beans_wk),
+            "whole_grains_wk" => Some(&mut self.whole_grains_wk),
+            "fish_wk" => Some(&mut self.fish_wk),
+            "poultry_wk" => Some(&mut self.poultry_wk),
+            "olive_oil_primary" => Some(&mut self.olive_oil_primary),
+            "wine_wk" => Some(&mut self.wine_wk),
+            "red_meat_wk" => Some(&mut self.red_meat_wk),
+            "butter_wk" => Some(&mut self.butter_wk),
+            "cheese_wk" => Some(&mut self.cheese_wk),
+            "pastries_sweets_wk" => Some(&mut self.pastries_sweets_wk),
+            "fried_food_wk" => Some(&mut self.fried_food_wk),
+            "starchy_veg" => Some(&mut self.starchy_veg),
+            "dairy" => Some(&mut self.dairy),
+            "eggs" => Some(&mut self.eggs),
+            "soy" => Some(&mut self.soy),
+            "added_fat_unsat" => Some(&mut self.added_fat_unsat),
+            "added_fat_sat_trans" => Some(&mut self.added_fat_sat_trans),
+            "added_sugar_g" => Some(&mut self.added_sugar_g),
+            "low_fat_dairy_serv" => Some(&mut self.low_fat_dairy_serv),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,386 @@
+//! Wide result tables and the writers that serialise them.
+//!
+//! A [`WideTable`] has one row per scored record: the row position,
+//! identifier and survey design columns, then per index its value and a `_valid` flag,
+//! optionally one column of points per component, and finally the fields
+//! its indices required and did not get, joined by `;`. Writers implement [`TableSink`] and accept
+//! the table in chunks, so streamed results never need to be held at once.
+//! CSV and TSV are always available, Parquet with the `parquet` feature.
+
+use crate::scores::registry::{all_batch_scorers, all_scorers};
+use crate::tabular::ScoredRow;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::io::Write;
+
+/// Values of one column, typed so binary formats keep the types.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Integer(Vec<Option<i64>>),
+    Number(Vec<Option<f64>>),
+    Flag(Vec<Option<bool>>),
+    Text(Vec<Option<String>>),
+}
+
+impl Column {
+    pub fn len(&self) -> usize {
+        match self {
+            Column::Integer(v) => v.len(),
+            Column::Number(v) => v.len(),
+            Column::Flag(v) => v.len(),
+            Column::Text(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cell `row` as delimited text; missing values are empty.
+    pub fn cell_text(&self, row: usize) -> String {
+        match self {
+            Column::Integer(v) => v[row].map(|x| x.to_string()),
+            Column::Number(v) => v[row].map(|x| x.to_string()),
+            Column::Flag(v) => v[row].map(|x| x.to_string()),
+            Column::Text(v) => v[row].clone(),
+        }
+        .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WideTable {
+    pub columns: Vec<(String, Column)>,
+}
+
+impl WideTable {
+    pub fn row_count(&self) -> usize {
+        self.columns.first().map_or(0, |(_, c)| c.len())
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|(n, _)| n == name).map(|(_, c)| c)
+    }
+}
+
+/// Columns of a wide table, fixed so that chunks of a stream line up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableLayout {
+    pub id_columns: Vec<String>,
+    /// Survey design columns, see [`crate::survey`].
+    pub design_columns: Vec<String>,
+    /// Indices in output order, each with the components given columns.
+    pub indices: Vec<(String, Vec<&'static str>)>,
+}
+
+impl TableLayout {
+    /// Layout of every registered index, in the order records are scored,
+    /// so the columns do not depend on the rows. With `components`, each
+    /// per-record index is followed by the points of all its components.
+    pub fn from_registry(
+        id_columns: &[String],
+        design_columns: &[String],
+        components: bool,
+    ) -> Self {
+        let mut indices: Vec<(String, Vec<&'static str>)> = all_scorers()
+            .iter()
+            .map(|s| {
+                let parts = if components {
+                    s.component_names()
+                } else {
+                    Vec::new()
+                };
+                (s.name().to_string(), parts)
+            })
+            .collect();
+        indices.extend(
+            all_batch_scorers()
+                .iter()
+                .map(|s| (s.name().to_string(), Vec::new())),
+        );
+        TableLayout {
+            id_columns: id_columns.to_vec(),
+            design_columns: design_columns.to_vec(),
+            indices,
+        }
+    }
+
+    /// Layout of `rows`. Indices appear in the order they were first scored;
+    /// with `components`, each index is followed by the points of the
+    /// components seen in `rows` as `{index}_{component}`.
+    pub fn from_rows(rows: &[ScoredRow], components: bool) -> Self {
+        let mut layout = TableLayout::default();
+        for row in rows {
+            for col in row.id.keys() {
+                if !layout.id_columns.contains(col) {
+                    layout.id_columns.push(col.clone());
+                }
+            }
+            for col in row.design.keys() {
+                if !layout.design_columns.contains(col) {
+                    layout.design_columns.push(col.clone());
+                }
+            }
+            for name in &row.result.ordered_names {
+                let pos = match layout.indices.iter().position(|(n, _)| n == name) {
+                    Some(pos) => pos,
+                    None => {
+                        layout.indices.push((name.clone(), Vec::new()));
+                        layout.indices.len() - 1
+                    }
+                };
+                if let (true, Some(info)) = (components, row.result.scores.get(name)) {
+                    let parts = &mut layout.indices[pos].1;
+                    for c in &info.components {
+                        if !parts.contains(&c.name) {
+                            parts.push(c.name);
+                        }
+                    }
+                }
+            }
+        }
+        layout
+    }
+
+    /// Wide table of `rows` in this layout; values outside it are dropped.
+    pub fn table(&self, rows: &[ScoredRow]) -> WideTable {
+        let mut table = WideTable::default();
+        table.columns.push((
+            "row".to_string(),
+            Column::Integer(rows.iter().map(|r| Some(r.row as i64)).collect()),
+        ));
+        for col in &self.id_columns {
+            let values = rows.iter().map(|r| r.id.get(col).map(id_text)).collect();
+            table.columns.push((col.clone(), Column::Text(values)));
+        }
+        for col in &self.design_columns {
+            let values = rows
+                .iter()
+                .map(|r| r.design.get(col).and_then(Value::as_f64))
+                .collect();
+            table.columns.push((col.clone(), Column::Number(values)));
+        }
+        for (name, parts) in &self.indices {
+            let infos: Vec<_> = rows.iter().map(|r| r.result.scores.get(name)).collect();
+            table.columns.push((
+                name.clone(),
+                Column::Number(infos.iter().map(|i| i.and_then(|i| i.value)).collect()),
+            ));
+            table.columns.push((
+                format!("{}_valid", name),
+                Column::Flag(infos.iter().map(|i| i.map(|i| i.valid)).collect()),
+            ));
+            for part in parts {
+                let points = infos
+                    .iter()
+                    .map(|i| {
+                        i.and_then(|i| i.components.iter().find(|c| c.name == *part))
+                            .map(|c| c.points)
+                    })
+                    .collect();
+                table
+                    .columns
+                    .push((format!("{}_{}", name, part), Column::Number(points)));
+            }
+        }
+        table.columns.push((
+            "missing_fields".to_string(),
+            Column::Text(rows.iter().map(|r| Some(required_missing(r))).collect()),
+        ));
+        table
+    }
+}
+
+/// Builds the wide table of `rows`, see [`TableLayout::from_rows`].
+pub fn wide_table(rows: &[ScoredRow], components: bool) -> WideTable {
+    TableLayout::from_rows(rows, components).table(rows)
+}
+
+/// Fields that some index of `row` required and did not get, by name.
+fn required_missing(row: &ScoredRow) -> String {
+    let fields: BTreeSet<&str> = row
+        .result
+        .errors
+        .iter()
+        .flat_map(|e| e.missing_fields.iter().copied())
+        .collect();
+    fields.into_iter().collect::<Vec<_>>().join(";")
+}
+
+fn id_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Writes a wide table in one or more chunks of rows sharing a layout.
+pub trait TableSink {
+    fn write_chunk(&mut self, table: &WideTable) -> anyhow::Result<()>;
+    /// Flushes buffered rows and writes any footer.
+    fn finish(&mut self) -> anyhow::Result<()>;
+}
+
+/// Comma- or tab-separated text with a header line.
+pub struct DelimitedSink<W: Write> {
+    writer: csv::Writer<W>,
+    header_written: bool,
+}
+
+impl<W: Write> DelimitedSink<W> {
+    pub fn new(out: W, delimiter: u8) -> Self {
+        DelimitedSink {
+            writer: csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(out),
+            header_written: false,
+        }
+    }
+}
+
+impl<W: Write> TableSink for DelimitedSink<W> {
+    fn write_chunk(&mut self, table: &WideTable) -> anyhow::Result<()> {
+        if !self.header_written {
+            self.writer
+                .write_record(table.columns.iter().map(|(name, _)| name))?;
+            self.header_written = true;
+        }
+        for row in 0..table.row_count() {
+            self.writer
+                .write_record(table.columns.iter().map(|(_, c)| c.cell_text(row)))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Apache Parquet, one row group per chunk.
+#[cfg(feature = "parquet")]
+pub struct ParquetSink<W: Write + Send> {
+    out: Option<W>,
+    writer: Option<parquet::arrow::ArrowWriter<W>>,
+}
+
+#[cfg(feature = "parquet")]
+impl<W: Write + Send> ParquetSink<W> {
+    pub fn new(out: W) -> Self {
+        ParquetSink {
+            out: Some(out),
+            writer: None,
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl<W: Write + Send> TableSink for ParquetSink<W> {
+    fn write_chunk(&mut self, table: &WideTable) -> anyhow::Result<()> {
+        use arrow_array::{
+            ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+        };
+        use arrow_schema::{DataType, Field, Schema};
+        use std::sync::Arc;
+
+        let mut fields = Vec::new();
+        let mut arrays: Vec<ArrayRef> = Vec::new();
+        for (name, column) in &table.columns {
+            let (data_type, array): (DataType, ArrayRef) = match column {
+                Column::Integer(v) => (DataType::Int64, Arc::new(Int64Array::from(v.clone()))),
+                Column::Number(v) => (DataType::Float64, Arc::new(Float64Array::from(v.clone()))),
+                Column::Flag(v) => (DataType::Boolean, Arc::new(BooleanArray::from(v.clone()))),
+                Column::Text(v) => (DataType::Utf8, Arc::new(StringArray::from(v.clone()))),
+            };
+            fields.push(Field::new(name, data_type, true));
+            arrays.push(array);
+        }
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?;
+        if self.writer.is_none() {
+            let out = self.out.take().expect("parquet sink already finished");
+            self.writer = Some(parquet::arrow::ArrowWriter::try_new(
+                out,
+                batch.schema(),
+                None,
+            )?);
+        }
+        let writer = self.writer.as_mut().expect("writer created above");
+        writer.write(&batch)?;
+        // Each chunk becomes its own row group so memory stays bounded.
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `table` in one chunk to `out` in `format`.
+pub fn write_table(
+    format: OutputFormat,
+    table: &WideTable,
+    out: &mut (dyn Write + Send),
+) -> anyhow::Result<()> {
+    let mut sink = format
+        .table_sink(out)
+        .ok_or_else(|| anyhow::anyhow!("no table writer for {} output", format.name()))?;
+    sink.write_chunk(table)?;
+    sink.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    Tsv,
+    Parquet,
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            OutputFormat::Json,
+            OutputFormat::Csv,
+            OutputFormat::Tsv,
+            OutputFormat::Parquet,
+        ]
+        .into_iter()
+        .find(|f| f.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Format named by the extension of `path`, if any.
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_name)
+    }
+
+    /// Sink for the wide table formats; `None` for JSON, which is written
+    /// from the scored rows directly, and for Parquet without the `parquet`
+    /// feature.
+    pub fn table_sink<'a, W: Write + Send + 'a>(&self, out: W) -> Option<Box<dyn TableSink + 'a>> {
+        match self {
+            OutputFormat::Json => None,
+            OutputFormat::Csv => Some(Box::new(DelimitedSink::new(out, b','))),
+            OutputFormat::Tsv => Some(Box::new(DelimitedSink::new(out, b'\t'))),
+            #[cfg(feature = "parquet")]
+            OutputFormat::Parquet => Some(Box::new(ParquetSink::new(out))),
+            #[cfg(not(feature = "parquet"))]
+            OutputFormat::Parquet => None,
+        }
+    }
+}
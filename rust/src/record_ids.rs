@@ -0,0 +1,95 @@
+//! Participant identifiers carried from input rows to output rows.
+//!
+//! Callers name the identifier columns of an upload, or let the first header
+//! matching [`DEFAULT_ID_COLUMNS`] be used. The values are copied verbatim;
+//! rows without an identifier and identifiers used by several rows are
+//! reported so results can be joined back safely.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Headers recognised as the identifier when none is designated, in order of
+/// preference. Matching ignores case.
+pub const DEFAULT_ID_COLUMNS: &[&str] = &["id", "participant_id", "seqn"];
+
+/// Headers to read identifiers from. Designated columns are matched against
+/// `headers` ignoring case and kept in the order given, a name matching no
+/// header is kept as is so every row reports it missing. Without designated
+/// columns, the first default identifier column present is used.
+pub fn resolve_id_columns(headers: &[String], designated: &[String]) -> Vec<String> {
+    let find = |name: &str| {
+        headers
+            .iter()
+            .find(|h| h.eq_ignore_ascii_case(name))
+            .cloned()
+    };
+    if designated.is_empty() {
+        DEFAULT_ID_COLUMNS
+            .iter()
+            .find_map(|c| find(c))
+            .into_iter()
+            .collect()
+    } else {
+        designated
+            .iter()
+            .map(|c| find(c).unwrap_or_else(|| c.clone()))
+            .collect()
+    }
+}
+
+/// Identifier values of `row`, keyed by column. Null or empty values are left
+/// out.
+pub fn record_id(row: &HashMap<String, Value>, columns: &[String]) -> BTreeMap<String, Value> {
+    columns
+        .iter()
+        .filter_map(|c| match row.get(c) {
+            None | Some(Value::Null) => None,
+            Some(Value::String(s)) if s.trim().is_empty() => None,
+            Some(v) => Some((c.clone(), v.clone())),
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
+pub struct IdReport {
+    pub columns: Vec<String>,
+    /// Positions of rows lacking at least one identifier column.
+    pub missing: Vec<usize>,
+    /// Identifiers shared by several rows, with the positions of those rows.
+    pub duplicates: BTreeMap<String, Vec<usize>>,
+}
+
+/// Check the identifiers extracted by [`record_id`], one per row in order.
+pub fn check_ids(columns: &[String], ids: &[BTreeMap<String, Value>]) -> IdReport {
+    let mut report = IdReport {
+        columns: columns.to_vec(),
+        ..Default::default()
+    };
+    let mut seen: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        if id.len() < columns.len() {
+            report.missing.push(i);
+            continue;
+        }
+        seen.entry(id_key(columns, id)).or_default().push(i);
+    }
+    report.duplicates = seen
+        .into_iter()
+        .filter(|(_, rows)| rows.len() > 1)
+        .collect();
+    report
+}
+
+/// Identifier as one string, columns joined by `|`; strings are used without
+/// their JSON quotes. `id` must hold every column.
+pub fn id_key(columns: &[String], id: &BTreeMap<String, Value>) -> String {
+    columns
+        .iter()
+        .map(|c| match &id[c] {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
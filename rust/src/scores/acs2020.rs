@@ -1,7 +1,12 @@
+use super::batch::{stratum_quantile_groups, BatchScore, CohortInputs, CohortRow};
 use super::{capped_score, DietScore, FieldDeps};
 use crate::contracts;
+use crate::energy::EnergyAdjustment;
 use crate::nutrition_vector::NutritionVector;
 
+/// Single-record ACS 2020 score on fixed intake targets. The editions of
+/// [`Acs2020CohortScorer`] score the published definition on cohort
+/// quartiles.
 pub struct Acs2020Scorer;
 
 impl FieldDeps for Acs2020Scorer {
@@ -34,3 +39,92 @@ impl DietScore for Acs2020Scorer {
         <Self as FieldDeps>::required_fields()
     }
 }
+
+/// Editions of the American Cancer Society 2020 diet score. V1 ranks highly
+/// processed foods and refined grains as reported; V2 ranks them per 1,000
+/// kcal. V3 currently follows V2, as in the reference implementation, until
+/// its own cut points are published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acs2020Version {
+    V1,
+    V2,
+    V3,
+}
+
+/// ACS 2020 diet score: sex-specific quartiles of the batch for every food
+/// group, and fixed cut points for sugar-sweetened beverages.
+#[derive(Debug, Clone, Copy)]
+pub struct Acs2020CohortScorer(pub Acs2020Version);
+
+const VARIETY_POINTS: [f64; 4] = [0.0, 0.25, 0.5, 0.75];
+const WHOLE_GRAIN_POINTS: [f64; 4] = [0.0, 1.0, 2.0, 3.0];
+const RED_MEAT_POINTS: [f64; 4] = [3.0, 2.0, 1.0, 0.0];
+const PROCESSED_POINTS: [f64; 4] = [1.5, 1.0, 0.5, 0.0];
+
+/// Daily servings of sugar-sweetened beverages: none earns 1.5 points, under
+/// 3 a week 1 point, under one a day 0.5 points.
+pub fn ssb_points(servings: f64) -> f64 {
+    if servings <= 0.0 {
+        1.5
+    } else if servings < 3.0 / 7.0 {
+        1.0
+    } else if servings < 1.0 {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+fn hpf_rg_per_1000_kcal(r: CohortRow) -> Option<f64> {
+    match (r.get("hpf_rg_serv"), r.get("energy")) {
+        (Some(servings), Some(energy)) if energy > 0.0 => Some(servings / energy * 1000.0),
+        _ => None,
+    }
+}
+
+impl BatchScore for Acs2020CohortScorer {
+    fn name(&self) -> &'static str {
+        match self.0 {
+            Acs2020Version::V1 => "ACS2020_V1",
+            Acs2020Version::V2 => "ACS2020_V2",
+            Acs2020Version::V3 => "ACS2020_V3",
+        }
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        contracts::required_fields(self.name())
+    }
+
+    /// V2 and V3 take highly processed foods and refined grains as they are
+    /// from a batch already per 1,000 kcal.
+    fn evaluate_inputs(&self, batch: &CohortInputs, applied: EnergyAdjustment) -> Vec<f64> {
+        type Intake = fn(CohortRow) -> Option<f64>;
+        let processed: Intake = match (self.0, applied) {
+            (Acs2020Version::V1, _) | (_, EnergyAdjustment::PerThousandKcal) => {
+                |r| r.get("hpf_rg_serv")
+            }
+            (Acs2020Version::V2 | Acs2020Version::V3, _) => hpf_rg_per_1000_kcal,
+        };
+        let components: [(Intake, [f64; 4]); 7] = [
+            (|r| r.get("veg_serv"), VARIETY_POINTS),
+            (|r| r.get("veg_variety"), VARIETY_POINTS),
+            (|r| r.get("fruit_serv"), VARIETY_POINTS),
+            (|r| r.get("fruit_variety"), VARIETY_POINTS),
+            (|r| r.get("whole_grains"), WHOLE_GRAIN_POINTS),
+            (|r| r.get("red_processed_meat_serv"), RED_MEAT_POINTS),
+            (processed, PROCESSED_POINTS),
+        ];
+        let mut scores: Vec<f64> = (0..batch.len())
+            .map(|i| ssb_points(batch.get(i, "ssb_serv").unwrap_or(0.0)))
+            .collect();
+        for (intake, points) in components {
+            let quartiles = stratum_quantile_groups(batch, true, 4, intake);
+            for (score, quartile) in scores.iter_mut().zip(quartiles) {
+                if let Some(q) = quartile {
+                    *score += points[q];
+                }
+            }
+        }
+        scores
+    }
+}
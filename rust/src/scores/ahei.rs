@@ -1,7 +1,152 @@
-use super::{DietScore, FieldDeps};
+//! Alternative Healthy Eating Index 2010 (Chiuve et al., 2012).
+//!
+//! Eleven components worth up to 10 points each. Food groups are servings per
+//! day, whole grains grams per day, long-chain n-3 fats (EPA + DHA) mg per
+//! day, PUFA and trans fat percent of energy. Whole grain and alcohol
+//! standards depend on sex.
+//!
+//! The original index scores sodium on deciles of energy-adjusted intake in
+//! the cohort. [`AheiCohortScorer`] does so as a [`BatchScore`]; [`Ahei`]
+//! scores a single record with fixed sodium bands in their place.
+
+use super::batch::{quantile_groups, BatchScore, CohortInputs, CohortRow};
+use super::{
+    energy_not_positive, total_points, ComponentScore, DietScore, FieldDeps, ENERGY_NOT_POSITIVE,
+};
 use crate::contracts;
+use crate::energy::{adjust_values, EnergyAdjustment};
 use crate::nutrition_vector::NutritionVector;
 
+const KCAL_PER_G_FAT: f64 = 9.0;
+/// Grams of ethanol in one standard drink.
+pub const GRAMS_PER_DRINK: f64 = 14.0;
+pub(super) const MALE: f64 = 1.0;
+pub(super) const FEMALE: f64 = 2.0;
+
+/// Zero points at `min`, full points at `max`.
+pub(super) fn adequacy(value: f64, min: f64, max: f64) -> f64 {
+    ((value - min) / (max - min) * 10.0).clamp(0.0, 10.0)
+}
+
+/// Full points at `best`, zero points at `worst`.
+pub(super) fn moderation(value: f64, best: f64, worst: f64) -> f64 {
+    ((worst - value) / (worst - best) * 10.0).clamp(0.0, 10.0)
+}
+
+pub(super) fn percent_energy(grams: f64, energy: f64) -> f64 {
+    grams * KCAL_PER_G_FAT / energy * 100.0
+}
+
+/// Per-record fallback for sodium: sodium per 2,000 kcal in 400 mg bands,
+/// 10 points up to 400 mg and one point less for each further band. The
+/// bands stand in for the cohort deciles of the original index, which
+/// [`AheiCohortScorer`] scores, so a single record can be scored.
+fn sodium_score(sodium: f64, energy: f64) -> f64 {
+    let per_2000_kcal = sodium / energy * 2000.0;
+    (11.0 - (per_2000_kcal / 400.0).ceil()).clamp(0.0, 10.0)
+}
+
+/// Moderate drinking scores best; heavy drinking scores zero and abstainers
+/// 2.5 points.
+fn alcohol_score(drinks: f64, sex: f64) -> f64 {
+    let (ideal_max, zero_at) = if sex == FEMALE {
+        (1.5, 2.5)
+    } else {
+        (2.0, 3.5)
+    };
+    if drinks <= 0.125 {
+        2.5
+    } else if drinks < 0.5 {
+        drinks / 0.5 * 10.0
+    } else if drinks <= ideal_max {
+        10.0
+    } else {
+        moderation(drinks, ideal_max, zero_at)
+    }
+}
+
+/// Reason a record cannot get sex-specific standards, if any.
+pub(super) fn sex_not_coded(nv: &NutritionVector) -> Option<String> {
+    uncoded_sex(nv.sex)
+}
+
+fn uncoded_sex(sex: Option<f64>) -> Option<String> {
+    match sex {
+        Some(sex) if sex == MALE || sex == FEMALE => None,
+        other => Some(format!(
+            "sex {} is not coded 1 (male) or 2 (female)",
+            other.unwrap_or(f64::NAN)
+        )),
+    }
+}
+
+/// Ten-point component; `standard` is the intake earning full points.
+pub(super) fn component(
+    name: &'static str,
+    intake: f64,
+    standard: f64,
+    points: f64,
+) -> ComponentScore {
+    ComponentScore {
+        name,
+        intake,
+        standard,
+        points,
+        max_points: 10.0,
+    }
+}
+
+/// Points for each component, in the order of the original publication.
+pub fn component_scores(nv: &NutritionVector) -> Vec<ComponentScore> {
+    let energy = nv.energy.unwrap_or(0.0);
+    let sex = nv.sex.unwrap_or(MALE);
+    let whole_grain_max = if sex == FEMALE { 75.0 } else { 90.0 };
+    let veg = nv.veg_serv.unwrap_or(0.0);
+    let fruit = nv.fruit_serv.unwrap_or(0.0);
+    let whole_grains = nv.whole_grains.unwrap_or(0.0);
+    let ssb = nv.ssb_serv.unwrap_or(0.0);
+    let nuts_legumes = nv.nuts_legumes_serv.unwrap_or(0.0);
+    let red_meat = nv.red_processed_meat_serv.unwrap_or(0.0);
+    let trans = percent_energy(nv.trans_fat.unwrap_or(0.0), energy);
+    let n3 = nv.epa_dha.unwrap_or(0.0);
+    let pufa = percent_energy(nv.pufa.unwrap_or(0.0), energy);
+    let sodium = nv.sodium.unwrap_or(0.0);
+    let drinks = nv.alcohol.unwrap_or(0.0) / GRAMS_PER_DRINK;
+    vec![
+        component("vegetables", veg, 5.0, adequacy(veg, 0.0, 5.0)),
+        component("fruit", fruit, 4.0, adequacy(fruit, 0.0, 4.0)),
+        component(
+            "whole_grains",
+            whole_grains,
+            whole_grain_max,
+            adequacy(whole_grains, 0.0, whole_grain_max),
+        ),
+        component("ssb_fruit_juice", ssb, 0.0, moderation(ssb, 0.0, 1.0)),
+        component(
+            "nuts_legumes",
+            nuts_legumes,
+            1.0,
+            adequacy(nuts_legumes, 0.0, 1.0),
+        ),
+        component(
+            "red_processed_meat",
+            red_meat,
+            0.0,
+            moderation(red_meat, 0.0, 1.5),
+        ),
+        component("trans_fat", trans, 0.5, moderation(trans, 0.5, 4.0)),
+        component("long_chain_n3", n3, 250.0, adequacy(n3, 0.0, 250.0)),
+        component("pufa", pufa, 10.0, adequacy(pufa, 2.0, 10.0)),
+        component(
+            "sodium",
+            sodium / energy * 2000.0,
+            400.0,
+            sodium_score(sodium, energy),
+        ),
+        component("alcohol", drinks, 0.5, alcohol_score(drinks, sex)),
+    ]
+}
+
 pub struct Ahei;
 
 impl FieldDeps for Ahei {
@@ -16,19 +161,7 @@ impl FieldDeps for Ahei {
 
 impl DietScore for Ahei {
     fn evaluate(&self, nv: &NutritionVector) -> f64 {
-        // Simplified scoring: ratio of healthy components
-        let mut score = 0.0;
-        if nv.fiber.unwrap_or(0.0) >= 25.0 {
-            score += 10.0;
-        } else {
-            score += nv.fiber.unwrap_or(0.0) / 2.5;
-        }
-        if nv.fat.unwrap_or(0.0) > 0.0 {
-            let puf_ratio = (nv.fat.unwrap_or(0.0) - nv.saturated_fat.unwrap_or(0.0))
-                / nv.fat.unwrap_or(0.0);
-            score += (puf_ratio * 10.0).clamp(0.0, 10.0);
-        }
-        score
+        total_points(&component_scores(nv))
     }
 
     fn name(&self) -> &'static str {
@@ -38,4 +171,65 @@ impl DietScore for Ahei {
     fn required_fields(&self) -> &'static [&'static str] {
         <Self as FieldDeps>::required_fields()
     }
+
+    fn not_applicable(&self, nv: &NutritionVector) -> Option<String> {
+        sex_not_coded(nv).or_else(|| energy_not_positive(nv))
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        component_scores(nv)
+    }
+}
+
+/// Sodium on deciles of energy-adjusted intake across the batch: 10 points
+/// for the lowest decile down to 0 for the highest.
+fn sodium_decile_points(decile: usize) -> f64 {
+    (9 - decile.min(9)) as f64 * 10.0 / 9.0
+}
+
+/// AHEI-2010 as published: the components of [`Ahei`], with sodium scored on
+/// deciles of the batch instead of fixed bands. Sodium is ranked on
+/// residuals from a regression on energy unless the batch was already put
+/// per unit of energy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AheiCohortScorer;
+
+impl BatchScore for AheiCohortScorer {
+    fn name(&self) -> &'static str {
+        "AHEI_COHORT"
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        contracts::required_fields("AHEI_COHORT")
+    }
+
+    fn not_applicable(&self, row: CohortRow) -> Option<String> {
+        uncoded_sex(row.get("sex")).or_else(|| match row.get("energy") {
+            Some(kcal) if kcal > 0.0 => None,
+            _ => Some(ENERGY_NOT_POSITIVE.to_string()),
+        })
+    }
+
+    fn evaluate_inputs(&self, batch: &CohortInputs, applied: EnergyAdjustment) -> Vec<f64> {
+        let mut sodium: Vec<Option<f64>> =
+            (0..batch.len()).map(|i| batch.get(i, "sodium")).collect();
+        // Density adjustment leaves sodium as reported.
+        if let EnergyAdjustment::None | EnergyAdjustment::NutrientDensity = applied {
+            let energy: Vec<Option<f64>> =
+                (0..batch.len()).map(|i| batch.get(i, "energy")).collect();
+            adjust_values("sodium", &mut sodium, &energy, EnergyAdjustment::Residual);
+        }
+        quantile_groups(&sodium, 10)
+            .into_iter()
+            .enumerate()
+            .map(|(i, decile)| {
+                let others: f64 = component_scores(&batch.row(i).nutrition_vector())
+                    .iter()
+                    .filter(|c| c.name != "sodium")
+                    .map(|c| c.points)
+                    .sum();
+                others + decile.map_or(0.0, sodium_decile_points)
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,93 @@
+//! Alternative Healthy Eating Index for Pregnancy (Rifas-Shiman et al., 2009).
+//!
+//! Nine components worth up to 10 points each. AHEI-P keeps the vegetable,
+//! fruit, fat and meat components of the AHEI, drops alcohol and nuts, and
+//! adds the pregnancy targets for calcium, folate and iron.
+
+use super::ahei::{adequacy, component, moderation, percent_energy};
+use super::{energy_not_positive, total_points, ComponentScore, DietScore, FieldDeps};
+use crate::contracts;
+use crate::nutrition_vector::NutritionVector;
+
+/// Zero points at `min`, full points at `max`. A zero denominator earns full
+/// points when the numerator is positive.
+fn ratio_score(numerator: f64, denominator: f64, min: f64, max: f64) -> f64 {
+    if denominator > 0.0 {
+        adequacy(numerator / denominator, min, max)
+    } else if numerator > 0.0 {
+        10.0
+    } else {
+        0.0
+    }
+}
+
+/// Points for each component, in the order of the original publication.
+pub fn component_scores(nv: &NutritionVector) -> Vec<ComponentScore> {
+    let energy = nv.energy.unwrap_or(0.0);
+    let veg = nv.veg_serv.unwrap_or(0.0);
+    let fruit = nv.fruit_serv.unwrap_or(0.0);
+    let white_meat = nv.poultry.unwrap_or(0.0) + nv.fish.unwrap_or(0.0);
+    let red_meat = nv.red_meat.unwrap_or(0.0);
+    let fiber = nv.fiber.unwrap_or(0.0);
+    let trans = percent_energy(nv.trans_fat.unwrap_or(0.0), energy);
+    let pufa = nv.pufa.unwrap_or(0.0);
+    let sfa = nv.saturated_fat.unwrap_or(0.0);
+    let calcium = nv.calcium.unwrap_or(0.0);
+    let folate = nv.folic_acid.unwrap_or(0.0);
+    let iron = nv.iron.unwrap_or(0.0);
+    vec![
+        component("vegetables", veg, 5.0, adequacy(veg, 0.0, 5.0)),
+        component("whole_fruit", fruit, 4.0, adequacy(fruit, 0.0, 4.0)),
+        component(
+            "white_red_meat_ratio",
+            white_meat / red_meat,
+            4.0,
+            ratio_score(white_meat, red_meat, 0.0, 4.0),
+        ),
+        component("fiber", fiber, 25.0, adequacy(fiber, 0.0, 25.0)),
+        component("trans_fat", trans, 0.5, moderation(trans, 0.5, 4.0)),
+        component(
+            "pufa_sfa_ratio",
+            pufa / sfa,
+            1.0,
+            ratio_score(pufa, sfa, 0.1, 1.0),
+        ),
+        component("calcium", calcium, 1200.0, adequacy(calcium, 0.0, 1200.0)),
+        component("folate", folate, 600.0, adequacy(folate, 0.0, 600.0)),
+        component("iron", iron, 27.0, adequacy(iron, 0.0, 27.0)),
+    ]
+}
+
+pub struct AheipScorer;
+
+impl FieldDeps for AheipScorer {
+    fn name() -> &'static str {
+        "AHEIP"
+    }
+
+    fn required_fields() -> &'static [&'static str] {
+        contracts::required_fields("AHEIP")
+    }
+}
+
+impl DietScore for AheipScorer {
+    fn evaluate(&self, nv: &NutritionVector) -> f64 {
+        total_points(&component_scores(nv))
+    }
+
+    fn name(&self) -> &'static str {
+        <Self as FieldDeps>::name()
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        <Self as FieldDeps>::required_fields()
+    }
+
+    fn not_applicable(&self, nv: &NutritionVector) -> Option<String> {
+        energy_not_positive(nv)
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        component_scores(nv)
+    }
+}
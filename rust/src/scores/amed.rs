@@ -1,7 +1,11 @@
+use super::batch::{stratum_medians, BatchScore, CohortInputs, CohortRow};
 use super::{capped_score, DietScore, FieldDeps};
 use crate::contracts;
+use crate::energy::EnergyAdjustment;
 use crate::nutrition_vector::NutritionVector;
 
+/// Single-record aMED on fixed intake targets. [`AMedCohortScorer`] scores
+/// the published definition against cohort medians.
 pub struct AMedScorer;
 
 impl FieldDeps for AMedScorer {
@@ -35,3 +39,80 @@ impl DietScore for AMedScorer {
         <Self as FieldDeps>::required_fields()
     }
 }
+
+/// MUFA:SFA ratio, the fat component of the Mediterranean scores.
+pub fn mufa_sfa_ratio(mono_fat: Option<f64>, saturated_fat: Option<f64>) -> Option<f64> {
+    match (mono_fat, saturated_fat) {
+        (Some(mono), Some(sat)) if sat > 0.0 => Some(mono / sat),
+        _ => None,
+    }
+}
+
+/// Alternate Mediterranean Diet score as defined by Fung et al. (2005): one
+/// point per component for intakes at or above the cohort median (below it
+/// for red and processed meat) and one point for alcohol within
+/// `alcohol_window` grams per day.
+#[derive(Debug, Clone, Copy)]
+pub struct AMedCohortScorer {
+    /// Compare each record with the medians of its own sex only.
+    pub stratify_by_sex: bool,
+    /// Inclusive range of daily alcohol grams that earns the point.
+    pub alcohol_window: (f64, f64),
+}
+
+impl Default for AMedCohortScorer {
+    fn default() -> Self {
+        AMedCohortScorer {
+            stratify_by_sex: true,
+            alcohol_window: (10.0, 25.0),
+        }
+    }
+}
+
+type Intake = fn(CohortRow) -> Option<f64>;
+
+const AMED_HEALTHY: [Intake; 7] = [
+    |r| r.get("total_fruits"),
+    |r| r.get("vegetables"),
+    |r| r.get("whole_grains"),
+    |r| r.get("legumes"),
+    |r| r.get("nuts"),
+    |r| r.get("fish"),
+    |r| mufa_sfa_ratio(r.get("mono_fat"), r.get("saturated_fat")),
+];
+
+impl BatchScore for AMedCohortScorer {
+    fn name(&self) -> &'static str {
+        "aMED_COHORT"
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        contracts::required_fields("aMED_COHORT")
+    }
+
+    fn evaluate_inputs(&self, batch: &CohortInputs, _applied: EnergyAdjustment) -> Vec<f64> {
+        let mut scores = vec![0.0; batch.len()];
+        for intake in AMED_HEALTHY {
+            let medians = stratum_medians(batch, self.stratify_by_sex, intake);
+            for (i, score) in scores.iter_mut().enumerate() {
+                let value = intake(batch.row(i)).unwrap_or(0.0);
+                if value > 0.0 && value >= medians[i] {
+                    *score += 1.0;
+                }
+            }
+        }
+        let medians = stratum_medians(batch, self.stratify_by_sex, |r| r.get("red_meat"));
+        let (low, high) = self.alcohol_window;
+        for (i, score) in scores.iter_mut().enumerate() {
+            let red_meat = batch.get(i, "red_meat").unwrap_or(0.0);
+            if red_meat == 0.0 || red_meat < medians[i] {
+                *score += 1.0;
+            }
+            let alcohol = batch.get(i, "alcohol").unwrap_or(0.0);
+            if (low..=high).contains(&alcohol) {
+                *score += 1.0;
+            }
+        }
+        scores
+    }
+}
@@ -0,0 +1,270 @@
+//! Scoring relative to a population.
+//!
+//! Some indices award points by comparing each record with the distribution
+//! of intakes in the whole dataset (medians, quantiles), usually within
+//! sex-specific strata. They cannot be expressed through [`DietScore`], which
+//! sees one record at a time, so they implement [`BatchScore`] and are
+//! evaluated in two passes: statistics over the batch, then one score per
+//! record.
+//!
+//! A batch is held as [`CohortInputs`], the few fields the scorers read for
+//! each record rather than whole nutrition vectors.
+//!
+//! [`DietScore`]: super::DietScore
+
+use crate::energy::{adjust_values, EnergyAdjustment};
+use crate::nutrition_vector::NutritionVector;
+use std::collections::BTreeMap;
+
+pub trait BatchScore {
+    fn name(&self) -> &'static str;
+    fn required_fields(&self) -> &'static [&'static str];
+    /// Fields read for each record: the required ones, plus `energy` for
+    /// the adjustment and `sex` for the strata.
+    fn input_fields(&self) -> Vec<&'static str> {
+        let mut fields = self.required_fields().to_vec();
+        for extra in ["energy", "sex"] {
+            if !fields.contains(&extra) {
+                fields.push(extra);
+            }
+        }
+        fields
+    }
+    /// Scores every record of `inputs`, in order, against statistics of the
+    /// batch itself. `applied` is the energy adjustment the inputs already
+    /// went through; scorers that put some intakes on that basis themselves
+    /// use it so those intakes are not adjusted twice.
+    fn evaluate_inputs(&self, inputs: &CohortInputs, applied: EnergyAdjustment) -> Vec<f64>;
+    /// Scores every record of `batch`, as reported, see
+    /// [`Self::evaluate_inputs`].
+    fn evaluate_batch(&self, batch: &[NutritionVector]) -> Vec<f64> {
+        let inputs = CohortInputs::from_records(batch, &self.input_fields());
+        self.evaluate_inputs(&inputs, EnergyAdjustment::None)
+    }
+    /// Energy adjustment applied to the batch before ranking, unless the run
+    /// asks for another one.
+    fn energy_adjustment(&self) -> EnergyAdjustment {
+        EnergyAdjustment::None
+    }
+    /// Reason the index is not defined for a record, which is then left out
+    /// of the batch, e.g. a sex code the index has no standards for.
+    fn not_applicable(&self, _row: CohortRow) -> Option<String> {
+        None
+    }
+}
+
+/// Some fields of every record of a batch, stored by column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CohortInputs {
+    fields: Vec<&'static str>,
+    columns: Vec<Vec<Option<f64>>>,
+}
+
+impl CohortInputs {
+    /// An empty batch keeping `fields`; repeated names are kept once.
+    pub fn new(fields: &[&'static str]) -> Self {
+        let mut kept: Vec<&'static str> = Vec::new();
+        for field in fields {
+            if !kept.contains(field) {
+                kept.push(field);
+            }
+        }
+        CohortInputs {
+            columns: vec![Vec::new(); kept.len()],
+            fields: kept,
+        }
+    }
+
+    /// `fields` of every record of `batch`.
+    pub fn from_records(batch: &[NutritionVector], fields: &[&'static str]) -> Self {
+        let mut inputs = Self::new(fields);
+        for nv in batch {
+            inputs.push(nv);
+        }
+        inputs
+    }
+
+    /// Appends the kept fields of `nv`.
+    pub fn push(&mut self, nv: &NutritionVector) {
+        for (field, column) in self.fields.iter().zip(&mut self.columns) {
+            column.push(nv.get(field));
+        }
+    }
+
+    pub fn fields(&self) -> &[&'static str] {
+        &self.fields
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn column(&self, field: &str) -> Option<&Vec<Option<f64>>> {
+        let i = self.fields.iter().position(|f| *f == field)?;
+        Some(&self.columns[i])
+    }
+
+    /// Value of `field` for record `row`; `None` when it is missing or not
+    /// kept.
+    pub fn get(&self, row: usize, field: &str) -> Option<f64> {
+        self.column(field).and_then(|column| column[row])
+    }
+
+    pub fn row(&self, row: usize) -> CohortRow<'_> {
+        CohortRow { inputs: self, row }
+    }
+
+    /// The records at `rows`, in that order.
+    pub fn select(&self, rows: &[usize]) -> Self {
+        CohortInputs {
+            fields: self.fields.clone(),
+            columns: self
+                .columns
+                .iter()
+                .map(|column| rows.iter().map(|&i| column[i]).collect())
+                .collect(),
+        }
+    }
+
+    /// Applies `method` to the kept intakes, as
+    /// [`crate::energy::adjust_batch`] does to whole records.
+    pub fn adjust(&mut self, method: EnergyAdjustment) {
+        let energy = match self.column("energy") {
+            Some(column) => column.clone(),
+            None => vec![None; self.len()],
+        };
+        for (field, column) in self.fields.iter().zip(&mut self.columns) {
+            adjust_values(field, column, &energy, method);
+        }
+    }
+}
+
+/// One record of [`CohortInputs`].
+#[derive(Debug, Clone, Copy)]
+pub struct CohortRow<'a> {
+    inputs: &'a CohortInputs,
+    row: usize,
+}
+
+impl CohortRow<'_> {
+    pub fn get(&self, field: &str) -> Option<f64> {
+        self.inputs.get(self.row, field)
+    }
+
+    /// The record with only the kept fields set, for scoring it as
+    /// per-record scorers do.
+    pub fn nutrition_vector(&self) -> NutritionVector {
+        let mut nv = NutritionVector::default();
+        for field in self.inputs.fields() {
+            if let Some(slot) = nv.field_mut(field) {
+                *slot = self.get(field);
+            }
+        }
+        nv
+    }
+}
+
+/// Median of the values, ignoring NaN. Returns NaN for an empty input.
+pub fn median(values: &[f64]) -> f64 {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        sorted[mid]
+    } else {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    }
+}
+
+/// Record indices grouped by stratum. With `by_sex` each recorded sex code
+/// forms a stratum, and records without one share a stratum of their own;
+/// otherwise the whole batch is a single stratum.
+pub fn strata(batch: &CohortInputs, by_sex: bool) -> BTreeMap<Option<i64>, Vec<usize>> {
+    let mut groups: BTreeMap<Option<i64>, Vec<usize>> = BTreeMap::new();
+    for i in 0..batch.len() {
+        let key = if by_sex {
+            batch.get(i, "sex").map(|s| s as i64)
+        } else {
+            None
+        };
+        groups.entry(key).or_default().push(i);
+    }
+    groups
+}
+
+/// Median of `value` within each record's stratum, aligned with `batch`.
+/// Records for which `value` is `None` do not contribute to the median.
+pub fn stratum_medians<F>(batch: &CohortInputs, by_sex: bool, value: F) -> Vec<f64>
+where
+    F: Fn(CohortRow) -> Option<f64>,
+{
+    let mut medians = vec![f64::NAN; batch.len()];
+    for members in strata(batch, by_sex).values() {
+        let values: Vec<f64> = members
+            .iter()
+            .filter_map(|&i| value(batch.row(i)))
+            .collect();
+        let m = median(&values);
+        for &i in members {
+            medians[i] = m;
+        }
+    }
+    medians
+}
+
+/// Sample quantile with linear interpolation between order statistics (the
+/// default definition in R and NumPy), ignoring NaN. Returns NaN for an empty
+/// input.
+pub fn quantile(values: &[f64], p: f64) -> f64 {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let h = (sorted.len() - 1) as f64 * p.clamp(0.0, 1.0);
+    let lo = h.floor() as usize;
+    let hi = (lo + 1).min(sorted.len() - 1);
+    sorted[lo] + (h - lo as f64) * (sorted[hi] - sorted[lo])
+}
+
+/// Quantile group (0 for the lowest of `groups`) of each value among
+/// `values`. A value equal to a cut point falls in the lower group. Missing
+/// values get `None`.
+pub fn quantile_groups(values: &[Option<f64>], groups: usize) -> Vec<Option<usize>> {
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    let cuts: Vec<f64> = (1..groups)
+        .map(|k| quantile(&present, k as f64 / groups as f64))
+        .collect();
+    values
+        .iter()
+        .map(|v| v.map(|v| cuts.iter().filter(|&&c| c < v).count()))
+        .collect()
+}
+
+/// [`quantile_groups`] of each record's `value` within its stratum, aligned
+/// with `batch`.
+pub fn stratum_quantile_groups<F>(
+    batch: &CohortInputs,
+    by_sex: bool,
+    groups: usize,
+    value: F,
+) -> Vec<Option<usize>>
+where
+    F: Fn(CohortRow) -> Option<f64>,
+{
+    let mut ranks = vec![None; batch.len()];
+    for members in strata(batch, by_sex).values() {
+        let values: Vec<Option<f64>> = members.iter().map(|&i| value(batch.row(i))).collect();
+        for (&i, group) in members.iter().zip(quantile_groups(&values, groups)) {
+            ranks[i] = group;
+        }
+    }
+    ranks
+}
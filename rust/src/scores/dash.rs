@@ -1,20 +1,31 @@
+//! DASH diet score (Fung et al., 2008).
+//!
+//! The original index ranks eight food and nutrient intakes into quintiles of
+//! the study population and sums the quintile numbers, from 8 to 40. It is a
+//! [`BatchScore`]; the fixed-target [`DashLinearScorer`] is kept for scoring
+//! a single record.
+
+use super::batch::{stratum_quantile_groups, BatchScore, CohortInputs, CohortRow};
 use super::{DietScore, FieldDeps};
 use crate::contracts;
+use crate::energy::EnergyAdjustment;
 use crate::nutrition_vector::NutritionVector;
 
-pub struct DashScorer;
+/// Single-record DASH variant: fruit, vegetables, whole grains, sodium and
+/// saturated fat scored linearly from 0 to 10 against fixed targets.
+pub struct DashLinearScorer;
 
-impl FieldDeps for DashScorer {
+impl FieldDeps for DashLinearScorer {
     fn name() -> &'static str {
-        "DASH"
+        "DASH_LINEAR"
     }
 
     fn required_fields() -> &'static [&'static str] {
-        contracts::required_fields("DASH")
+        contracts::required_fields("DASH_LINEAR")
     }
 }
 
-impl DietScore for DashScorer {
+impl DietScore for DashLinearScorer {
     fn evaluate(&self, nv: &NutritionVector) -> f64 {
         let fruit = (nv.total_fruits.unwrap_or(0.0) / 400.0 * 10.0).clamp(0.0, 10.0);
         let veg = (nv.vegetables.unwrap_or(0.0) / 400.0 * 10.0).clamp(0.0, 10.0);
@@ -49,3 +60,54 @@ impl DietScore for DashScorer {
         <Self as FieldDeps>::required_fields()
     }
 }
+
+type Intake = fn(CohortRow) -> Option<f64>;
+
+/// Components ranked in quintiles, and whether higher intake is better.
+const DASH_COMPONENTS: [(&str, Intake, bool); 8] = [
+    ("fruit", |r| r.get("fruit_serv"), true),
+    ("vegetables", |r| r.get("veg_serv"), true),
+    ("nuts_legumes", |r| r.get("nuts_legumes_serv"), true),
+    ("whole_grains", |r| r.get("whole_grains"), true),
+    ("low_fat_dairy", |r| r.get("low_fat_dairy_serv"), true),
+    ("sodium", |r| r.get("sodium"), false),
+    (
+        "red_processed_meat",
+        |r| r.get("red_processed_meat_serv"),
+        false,
+    ),
+    ("ssb", |r| r.get("ssb_serv"), false),
+];
+
+/// DASH score on quintiles of the batch: 1 to 5 points per component, the
+/// highest quintile earning 5 for healthy components and the lowest quintile
+/// for sodium, red and processed meat and sugar-sweetened beverages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DashCohortScorer {
+    /// Rank each record among records of its own sex only.
+    pub stratify_by_sex: bool,
+}
+
+impl BatchScore for DashCohortScorer {
+    fn name(&self) -> &'static str {
+        "DASH"
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        contracts::required_fields("DASH")
+    }
+
+    fn evaluate_inputs(&self, batch: &CohortInputs, _applied: EnergyAdjustment) -> Vec<f64> {
+        let mut scores = vec![0.0; batch.len()];
+        for (_, intake, healthy) in DASH_COMPONENTS {
+            let groups = stratum_quantile_groups(batch, self.stratify_by_sex, 5, |r| {
+                Some(intake(r).unwrap_or(0.0))
+            });
+            for (score, group) in scores.iter_mut().zip(groups) {
+                let quintile = group.unwrap_or(0) as f64 + 1.0;
+                *score += if healthy { quintile } else { 6.0 - quintile };
+            }
+        }
+        scores
+    }
+}
@@ -1,6 +1,92 @@
-use super::{DietScore, FieldDeps};
+use super::{total_points, ComponentScore, DietScore, FieldDeps};
 use crate::contracts;
 use crate::nutrition_vector::NutritionVector;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+/// One entry of the DII global standard database.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiiParameter {
+    pub name: String,
+    pub field: String,
+    pub unit: String,
+    pub mean: f64,
+    pub sd: f64,
+    pub effect: f64,
+}
+
+static DII_PARAMETERS_JSON: &str = include_str!("../../../data/dii_parameters.json");
+
+static DII_PARAMETERS: Lazy<Vec<DiiParameter>> = Lazy::new(|| {
+    let params: Vec<DiiParameter> =
+        serde_json::from_str(DII_PARAMETERS_JSON).expect("invalid dii_parameters.json");
+    for p in &params {
+        assert!(
+            NutritionVector::all_field_names().contains(&p.field.as_str()),
+            "DII parameter {} refers to unknown field {}",
+            p.name,
+            p.field
+        );
+    }
+    params
+});
+
+/// Parameters loaded from `data/dii_parameters.json`, in file order.
+pub fn parameters() -> &'static [DiiParameter] {
+    DII_PARAMETERS.as_slice()
+}
+
+/// Lowest and highest achievable scores: every parameter at its most
+/// anti-inflammatory, then its most pro-inflammatory, extreme.
+pub fn score_range() -> [f64; 2] {
+    parameters().iter().fold([0.0, 0.0], |[low, high], p| {
+        [low - p.effect.abs(), high + p.effect.abs()]
+    })
+}
+
+/// Canonical fields of DII parameters absent from `nv`, sorted by name.
+pub fn missing_parameters(nv: &NutritionVector) -> Vec<&'static str> {
+    let mut missing: Vec<&'static str> = parameters()
+        .iter()
+        .filter(|p| nv.get(&p.field).is_none())
+        .map(|p| p.field.as_str())
+        .collect();
+    missing.sort();
+    missing
+}
+
+/// Contribution of a single intake: the z-score against the global mean/SD is
+/// converted to a percentile, centred on zero and weighted by the effect score.
+pub fn parameter_score(param: &DiiParameter, intake: f64) -> f64 {
+    let z = (intake - param.mean) / param.sd;
+    let centered = 2.0 * normal_cdf(z) - 1.0;
+    centered * param.effect
+}
+
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+// Complementary error function with fractional error below 1.2e-7
+// (Numerical Recipes, section 6.2).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let ans = t * poly.exp();
+    if x >= 0.0 {
+        ans
+    } else {
+        2.0 - ans
+    }
+}
 
 pub struct DiiScorer;
 
@@ -16,22 +102,7 @@ impl FieldDeps for DiiScorer {
 
 impl DietScore for DiiScorer {
     fn evaluate(&self, nv: &NutritionVector) -> f64 {
-        // Placeholder weighted scoring reflecting inflammatory potential.
-        let mut score = 0.0;
-        // pro-inflammatory components
-        score += nv.saturated_fat.unwrap_or(0.0) * 0.1;
-        score += nv.trans_fat.unwrap_or(0.0) * 0.3;
-        score += nv.sugar.unwrap_or(0.0) * 0.05;
-        // anti-inflammatory components
-        score -= nv.fiber.unwrap_or(0.0) * 0.1;
-        score -= nv.vitamin_c.unwrap_or(0.0) * 0.005;
-        score -= nv.vitamin_a.unwrap_or(0.0) * 0.0001;
-        score -= nv.vitamin_e.unwrap_or(0.0) * 0.02;
-        score -= nv.omega3.unwrap_or(0.0) * 0.3;
-        score -= nv.zinc.unwrap_or(0.0) * 0.05;
-        score -= nv.selenium.unwrap_or(0.0) * 0.003;
-        score -= nv.magnesium.unwrap_or(0.0) * 0.01;
-        score
+        total_points(&self.components(nv))
     }
 
     fn name(&self) -> &'static str {
@@ -41,4 +112,40 @@ impl DietScore for DiiScorer {
     fn required_fields(&self) -> &'static [&'static str] {
         <Self as FieldDeps>::required_fields()
     }
+
+    /// One row per supplied parameter, against the global mean. Parameters
+    /// without an intake are left out rather than imputed at the global mean;
+    /// `explain` reports which ones were skipped.
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        parameters()
+            .iter()
+            .filter_map(|p| {
+                nv.get(&p.field).map(|intake| ComponentScore {
+                    name: p.name.as_str(),
+                    intake,
+                    standard: p.mean,
+                    points: parameter_score(p, intake),
+                    max_points: p.effect.abs(),
+                })
+            })
+            .collect()
+    }
+
+    fn component_names(&self) -> Vec<&'static str> {
+        parameters().iter().map(|p| p.name.as_str()).collect()
+    }
+
+    fn explain(&self, nv: &NutritionVector) -> Option<String> {
+        let missing = missing_parameters(nv);
+        if missing.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "{} of {} parameters not supplied: {}",
+                missing.len(),
+                parameters().len(),
+                missing.join(", ")
+            ))
+        }
+    }
 }
@@ -0,0 +1,127 @@
+//! MIND diet score for the HRS Health Care and Nutrition Study (HCNS 2013).
+//!
+//! HCNS sheets report weekly servings per food frequency item; the ingest path
+//! ([`crate::hcsn_ingest`]) sums them into the fifteen MIND components, which
+//! are scored here with the serving cut points of the reference
+//! implementation.
+
+use super::{total_points, ComponentScore, DietScore, FieldDeps};
+use crate::contracts;
+use crate::nutrition_vector::NutritionVector;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MindComponent {
+    pub name: &'static str,
+    pub field: &'static str,
+    /// Weekly servings for the full point: at least this many for healthy
+    /// components, at most this many otherwise.
+    pub full: f64,
+    /// Weekly servings for half a point.
+    pub half: f64,
+    pub healthy: bool,
+}
+
+const fn healthy(name: &'static str, field: &'static str, full: f64, half: f64) -> MindComponent {
+    MindComponent {
+        name,
+        field,
+        full,
+        half,
+        healthy: true,
+    }
+}
+
+const fn limit(name: &'static str, field: &'static str, full: f64, half: f64) -> MindComponent {
+    MindComponent {
+        name,
+        field,
+        full,
+        half,
+        healthy: false,
+    }
+}
+
+/// Olive oil is scored separately: one point when it is the primary fat.
+pub const HCNS_MIND_COMPONENTS: [MindComponent; 14] = [
+    healthy("leafy_green_vegetables", "leafy_greens_wk", 6.0, 3.0),
+    healthy("other_vegetables", "other_veg_wk", 6.0, 3.0),
+    healthy("nuts", "nuts_wk", 5.0, 3.0),
+    healthy("berries", "berries_wk", 2.0, 1.0),
+    healthy("beans", "beans_wk", 3.0, 1.5),
+    healthy("whole_grains", "whole_grains_wk", 3.0, 1.5),
+    healthy("fish", "fish_wk", 1.0, 0.5),
+    healthy("poultry", "poultry_wk", 2.0, 1.0),
+    healthy("wine", "wine_wk", 1.0, 0.5),
+    limit("red_meat", "red_meat_wk", 0.0, 1.0),
+    limit("butter", "butter_wk", 1.0, 1.5),
+    limit("cheese", "cheese_wk", 1.0, 3.0),
+    limit("pastries_sweets", "pastries_sweets_wk", 1.0, 3.0),
+    limit("fried_fast_food", "fried_food_wk", 1.0, 3.0),
+];
+
+impl MindComponent {
+    pub fn score(&self, nv: &NutritionVector) -> f64 {
+        let servings = nv.get(self.field).unwrap_or(0.0);
+        let (full, half) = if self.healthy {
+            (servings >= self.full, servings >= self.half)
+        } else {
+            (servings <= self.full, servings <= self.half)
+        };
+        if full {
+            1.0
+        } else if half {
+            0.5
+        } else {
+            0.0
+        }
+    }
+
+    pub fn breakdown(&self, nv: &NutritionVector) -> ComponentScore {
+        ComponentScore {
+            name: self.name,
+            intake: nv.get(self.field).unwrap_or(0.0),
+            standard: self.full,
+            points: self.score(nv),
+            max_points: 1.0,
+        }
+    }
+}
+
+pub struct HcnsScorer;
+
+impl FieldDeps for HcnsScorer {
+    fn name() -> &'static str {
+        "HCNS"
+    }
+
+    fn required_fields() -> &'static [&'static str] {
+        contracts::required_fields("HCNS")
+    }
+}
+
+impl DietScore for HcnsScorer {
+    fn evaluate(&self, nv: &NutritionVector) -> f64 {
+        total_points(&self.components(nv))
+    }
+
+    fn name(&self) -> &'static str {
+        <Self as FieldDeps>::name()
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        <Self as FieldDeps>::required_fields()
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        let olive_oil = nv.olive_oil_primary.unwrap_or(0.0);
+        let mut components = vec![ComponentScore {
+            name: "olive_oil",
+            intake: olive_oil,
+            standard: 1.0,
+            points: if olive_oil > 0.0 { 1.0 } else { 0.0 },
+            max_points: 1.0,
+        }];
+        components.extend(HCNS_MIND_COMPONENTS.iter().map(|c| c.breakdown(nv)));
+        components
+    }
+}
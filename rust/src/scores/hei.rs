@@ -1,35 +1,270 @@
-use super::{DietScore, FieldDeps};
+//! Healthy Eating Index scorers.
+//!
+//! HEI components are scored on densities: food group equivalents and sodium
+//! per 1,000 kcal, the (MUFA + PUFA) / SFA ratio, and the share of energy from
+//! added sugars and saturated fat. Each edition only differs in its standards.
+//! HEI-2015 and HEI-2020 apply from age 2; HEI-Toddlers-2020 covers children
+//! aged 12 through 23 months.
+
+use super::{energy_not_positive, ComponentScore, DietScore, FieldDeps};
 use crate::contracts;
+use crate::energy::EnergyAdjustment;
 use crate::nutrition_vector::NutritionVector;
 
-pub struct HeiScorer;
+/// Energy supplied by one teaspoon equivalent of added sugars (4 g).
+const KCAL_PER_TSP_SUGAR: f64 = 16.0;
+const KCAL_PER_G_FAT: f64 = 9.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Standard {
+    /// Amount per 1,000 kcal; zero points at `min`, full points at `max`.
+    Adequacy {
+        field: &'static str,
+        min: f64,
+        max: f64,
+    },
+    /// Amount per 1,000 kcal; full points at `min`, zero points at `max`.
+    Moderation {
+        field: &'static str,
+        min: f64,
+        max: f64,
+    },
+    /// Percent of energy; full points at `min`, zero points at `max`.
+    EnergyShare {
+        field: &'static str,
+        kcal_per_unit: f64,
+        min: f64,
+        max: f64,
+    },
+    /// (MUFA + PUFA) / SFA; zero points at `min`, full points at `max`.
+    FattyAcidRatio { min: f64, max: f64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeiComponent {
+    pub name: &'static str,
+    pub points: f64,
+    pub standard: Standard,
+}
+
+const fn adequacy(name: &'static str, points: f64, field: &'static str, max: f64) -> HeiComponent {
+    HeiComponent {
+        name,
+        points,
+        standard: Standard::Adequacy {
+            field,
+            min: 0.0,
+            max,
+        },
+    }
+}
+
+/// HEI-2015 standards for Americans aged 2 and older.
+pub const HEI_2015_COMPONENTS: [HeiComponent; 13] = [
+    adequacy("total_fruits", 5.0, "f_total", 0.8),
+    adequacy("whole_fruits", 5.0, "f_whole", 0.4),
+    adequacy("total_vegetables", 5.0, "v_total", 1.1),
+    adequacy("greens_and_beans", 5.0, "v_greens_beans", 0.2),
+    adequacy("whole_grains", 10.0, "g_whole", 1.5),
+    adequacy("dairy", 10.0, "d_total", 1.3),
+    adequacy("total_protein_foods", 5.0, "pf_total", 2.5),
+    adequacy("seafood_plant_proteins", 5.0, "pf_seaplant", 0.8),
+    HeiComponent {
+        name: "fatty_acids",
+        points: 10.0,
+        standard: Standard::FattyAcidRatio { min: 1.2, max: 2.5 },
+    },
+    HeiComponent {
+        name: "refined_grains",
+        points: 10.0,
+        standard: Standard::Moderation {
+            field: "g_refined",
+            min: 1.8,
+            max: 4.3,
+        },
+    },
+    HeiComponent {
+        name: "sodium",
+        points: 10.0,
+        standard: Standard::Moderation {
+            field: "sodium",
+            min: 1100.0,
+            max: 2000.0,
+        },
+    },
+    HeiComponent {
+        name: "added_sugars",
+        points: 10.0,
+        standard: Standard::EnergyShare {
+            field: "add_sugars",
+            kcal_per_unit: KCAL_PER_TSP_SUGAR,
+            min: 6.5,
+            max: 26.0,
+        },
+    },
+    HeiComponent {
+        name: "saturated_fats",
+        points: 10.0,
+        standard: Standard::EnergyShare {
+            field: "saturated_fat",
+            kcal_per_unit: KCAL_PER_G_FAT,
+            min: 8.0,
+            max: 16.0,
+        },
+    },
+];
+
+/// HEI-2020 keeps the HEI-2015 standards for ages 2 and older.
+pub const HEI_2020_COMPONENTS: [HeiComponent; 13] = HEI_2015_COMPONENTS;
+
+/// HEI-Toddlers-2020 standards for children aged 12 through 23 months. Added
+/// sugars are not recommended at this age, so full points require none.
+pub const HEI_TODDLERS_2020_COMPONENTS: [HeiComponent; 13] = [
+    adequacy("total_fruits", 5.0, "f_total", 0.7),
+    adequacy("whole_fruits", 5.0, "f_whole", 0.3),
+    adequacy("total_vegetables", 5.0, "v_total", 0.9),
+    adequacy("greens_and_beans", 5.0, "v_greens_beans", 0.1),
+    adequacy("whole_grains", 10.0, "g_whole", 1.5),
+    adequacy("dairy", 10.0, "d_total", 2.0),
+    adequacy("total_protein_foods", 5.0, "pf_total", 2.0),
+    adequacy("seafood_plant_proteins", 5.0, "pf_seaplant", 0.5),
+    HeiComponent {
+        name: "fatty_acids",
+        points: 10.0,
+        standard: Standard::FattyAcidRatio { min: 0.9, max: 1.5 },
+    },
+    HeiComponent {
+        name: "refined_grains",
+        points: 10.0,
+        standard: Standard::Moderation {
+            field: "g_refined",
+            min: 1.5,
+            max: 3.4,
+        },
+    },
+    HeiComponent {
+        name: "sodium",
+        points: 10.0,
+        standard: Standard::Moderation {
+            field: "sodium",
+            min: 1100.0,
+            max: 1700.0,
+        },
+    },
+    HeiComponent {
+        name: "added_sugars",
+        points: 10.0,
+        standard: Standard::EnergyShare {
+            field: "add_sugars",
+            kcal_per_unit: KCAL_PER_TSP_SUGAR,
+            min: 0.0,
+            max: 13.8,
+        },
+    },
+    HeiComponent {
+        name: "saturated_fats",
+        points: 10.0,
+        standard: Standard::EnergyShare {
+            field: "saturated_fat",
+            kcal_per_unit: KCAL_PER_G_FAT,
+            min: 12.2,
+            max: 18.2,
+        },
+    },
+];
+
+/// Youngest age in years scored with the standards for ages 2 and older.
+pub const HEI_MIN_AGE: f64 = 2.0;
+/// Youngest age in years scored with the toddler standards (12 months).
+pub const HEI_TODDLERS_MIN_AGE: f64 = 1.0;
+
+/// Whether `nv` describes a child aged 12 through 23 months.
+pub fn is_toddler(nv: &NutritionVector) -> bool {
+    nv.age
+        .is_some_and(|age| (HEI_TODDLERS_MIN_AGE..HEI_MIN_AGE).contains(&age))
+}
+
+// Age is optional for the adult editions so records without demographics keep
+// scoring; it only rules out records known to be younger than two.
+fn adult_not_applicable(nv: &NutritionVector) -> Option<String> {
+    match nv.age {
+        Some(age) if age < HEI_MIN_AGE => Some(format!("age {} is below {}", age, HEI_MIN_AGE)),
+        _ => energy_not_positive(nv),
+    }
+}
+
+impl HeiComponent {
+    /// Density, energy share or ratio this component is scored on, with the
+    /// points awarded between zero and `points`.
+    pub fn breakdown(&self, nv: &NutritionVector) -> ComponentScore {
+        let energy = nv.energy.unwrap_or(0.0);
+        let per_1000_kcal = |field: &str| nv.get(field).unwrap_or(0.0) / energy * 1000.0;
+        let (intake, standard, fraction) = match self.standard {
+            Standard::Adequacy { field, min, max } => {
+                let density = per_1000_kcal(field);
+                (density, max, (density - min) / (max - min))
+            }
+            Standard::Moderation { field, min, max } => {
+                let density = per_1000_kcal(field);
+                (density, min, (max - density) / (max - min))
+            }
+            Standard::EnergyShare {
+                field,
+                kcal_per_unit,
+                min,
+                max,
+            } => {
+                let pct = nv.get(field).unwrap_or(0.0) * kcal_per_unit / energy * 100.0;
+                (pct, min, (max - pct) / (max - min))
+            }
+            Standard::FattyAcidRatio { min, max } => {
+                let unsaturated = nv.mono_fat.unwrap_or(0.0) + nv.pufa.unwrap_or(0.0);
+                let saturated = nv.saturated_fat.unwrap_or(0.0);
+                let ratio = unsaturated / saturated;
+                let fraction = if saturated > 0.0 {
+                    (ratio - min) / (max - min)
+                } else if unsaturated > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                };
+                (ratio, max, fraction)
+            }
+        };
+        ComponentScore {
+            name: self.name,
+            intake,
+            standard,
+            points: (fraction * self.points).clamp(0.0, self.points),
+            max_points: self.points,
+        }
+    }
+
+    /// Points awarded for this component, between zero and `points`.
+    pub fn score(&self, nv: &NutritionVector) -> f64 {
+        self.breakdown(nv).points
+    }
+}
+
+pub fn score_components(components: &[HeiComponent], nv: &NutritionVector) -> f64 {
+    components.iter().map(|c| c.score(nv)).sum()
+}
+
+pub struct Hei2015Scorer;
 
-impl FieldDeps for HeiScorer {
+impl FieldDeps for Hei2015Scorer {
     fn name() -> &'static str {
-        "HEI"
+        "HEI_2015"
     }
 
     fn required_fields() -> &'static [&'static str] {
-        contracts::required_fields("HEI")
+        contracts::required_fields("HEI_2015")
     }
 }
 
-impl DietScore for HeiScorer {
+impl DietScore for Hei2015Scorer {
     fn evaluate(&self, nv: &NutritionVector) -> f64 {
-        let mut score = 0.0;
-        // fruit component: 0-10 points with 200g threshold
-        score += (nv.total_fruits.unwrap_or(0.0) / 200.0 * 10.0).clamp(0.0, 10.0);
-        // whole grains component: 0-10 points with 75g threshold
-        score += (nv.whole_grains.unwrap_or(0.0) / 75.0 * 10.0).clamp(0.0, 10.0);
-        // sodium moderation: linear 10 points at <=1500 mg down to 0 at 2300 mg
-        let sodium_score = if nv.sodium.unwrap_or(f64::INFINITY) <= 1500.0 {
-            10.0
-        } else if nv.sodium.unwrap_or(0.0) >= 2300.0 {
-            0.0
-        } else {
-            (2300.0 - nv.sodium.unwrap_or(0.0)) / (2300.0 - 1500.0) * 10.0
-        };
-        score + sodium_score
+        score_components(&HEI_2015_COMPONENTS, nv)
     }
 
     fn name(&self) -> &'static str {
@@ -39,4 +274,99 @@ impl DietScore for HeiScorer {
     fn required_fields(&self) -> &'static [&'static str] {
         <Self as FieldDeps>::required_fields()
     }
+
+    fn energy_adjustment(&self) -> EnergyAdjustment {
+        EnergyAdjustment::PerThousandKcal
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        HEI_2015_COMPONENTS.iter().map(|c| c.breakdown(nv)).collect()
+    }
+
+    fn not_applicable(&self, nv: &NutritionVector) -> Option<String> {
+        adult_not_applicable(nv)
+    }
+}
+
+pub struct Hei2020Scorer;
+
+impl FieldDeps for Hei2020Scorer {
+    fn name() -> &'static str {
+        "HEI_2020"
+    }
+
+    fn required_fields() -> &'static [&'static str] {
+        contracts::required_fields("HEI_2020")
+    }
+}
+
+impl DietScore for Hei2020Scorer {
+    fn evaluate(&self, nv: &NutritionVector) -> f64 {
+        score_components(&HEI_2020_COMPONENTS, nv)
+    }
+
+    fn name(&self) -> &'static str {
+        <Self as FieldDeps>::name()
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        <Self as FieldDeps>::required_fields()
+    }
+
+    fn energy_adjustment(&self) -> EnergyAdjustment {
+        EnergyAdjustment::PerThousandKcal
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        HEI_2020_COMPONENTS.iter().map(|c| c.breakdown(nv)).collect()
+    }
+
+    fn not_applicable(&self, nv: &NutritionVector) -> Option<String> {
+        adult_not_applicable(nv)
+    }
+}
+
+pub struct HeiToddlers2020Scorer;
+
+impl FieldDeps for HeiToddlers2020Scorer {
+    fn name() -> &'static str {
+        "HEI_TODDLERS_2020"
+    }
+
+    fn required_fields() -> &'static [&'static str] {
+        contracts::required_fields("HEI_TODDLERS_2020")
+    }
+}
+
+impl DietScore for HeiToddlers2020Scorer {
+    fn evaluate(&self, nv: &NutritionVector) -> f64 {
+        score_components(&HEI_TODDLERS_2020_COMPONENTS, nv)
+    }
+
+    fn name(&self) -> &'static str {
+        <Self as FieldDeps>::name()
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        <Self as FieldDeps>::required_fields()
+    }
+
+    fn energy_adjustment(&self) -> EnergyAdjustment {
+        EnergyAdjustment::PerThousandKcal
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        HEI_TODDLERS_2020_COMPONENTS.iter().map(|c| c.breakdown(nv)).collect()
+    }
+
+    fn not_applicable(&self, nv: &NutritionVector) -> Option<String> {
+        if is_toddler(nv) {
+            energy_not_positive(nv)
+        } else {
+            Some(format!(
+                "age {} is outside 12 to 23 months",
+                nv.age.unwrap_or(f64::NAN)
+            ))
+        }
+    }
 }
@@ -0,0 +1,178 @@
+//! Mediterranean Diet Index built on the PREDIMED serving targets.
+//!
+//! MEDI awards one point per target met; MEDI_V2 scores each component
+//! linearly from 0 to 5 points instead. Intakes are servings per day except
+//! alcohol, which is converted from grams to standard drinks.
+
+use super::ahei::GRAMS_PER_DRINK;
+use super::{total_points, ComponentScore, DietScore, FieldDeps};
+use crate::contracts;
+use crate::nutrition_vector::NutritionVector;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MediComponent {
+    pub name: &'static str,
+    pub field: &'static str,
+    /// Amount of `field` in one serving.
+    pub per_serving: f64,
+    /// Daily servings to reach (`healthy`) or to stay below.
+    pub target: f64,
+    pub healthy: bool,
+}
+
+const fn serving(
+    name: &'static str,
+    field: &'static str,
+    target: f64,
+    healthy: bool,
+) -> MediComponent {
+    MediComponent {
+        name,
+        field,
+        per_serving: 1.0,
+        target,
+        healthy,
+    }
+}
+
+pub const MEDI_COMPONENTS: [MediComponent; 11] = [
+    serving("olive_oil", "olive_oil_serv", 5.0, true),
+    serving("fruit", "fruit_serv", 3.0, true),
+    serving("vegetables", "veg_serv", 2.0, true),
+    serving("legumes", "legumes_serv", 3.0 / 7.0, true),
+    serving("nuts", "nuts_serv", 3.0 / 7.0, true),
+    serving("fish_seafood", "fish_serv", 3.0 / 7.0, true),
+    MediComponent {
+        name: "alcohol",
+        field: "alcohol",
+        per_serving: GRAMS_PER_DRINK,
+        target: 1.0,
+        healthy: true,
+    },
+    serving("ssb", "ssb_serv", 1.0, false),
+    serving("sweets", "sweets_serv", 2.0 / 7.0, false),
+    serving("discretionary_fat", "discret_fat_serv", 1.0, false),
+    serving("red_processed_meat", "red_processed_meat_serv", 1.0, false),
+];
+
+impl MediComponent {
+    pub fn servings(&self, nv: &NutritionVector) -> f64 {
+        nv.get(self.field).unwrap_or(0.0) / self.per_serving
+    }
+
+    /// One point when the target is met.
+    pub fn binary_score(&self, nv: &NutritionVector) -> f64 {
+        let servings = self.servings(nv);
+        let met = if self.healthy {
+            servings >= self.target
+        } else {
+            servings < self.target
+        };
+        if met {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Up to 5 points: proportional to intake up to the target for healthy
+    /// components, falling from 5 at zero intake to 0 at the limit otherwise.
+    pub fn graded_score(&self, nv: &NutritionVector) -> f64 {
+        let fraction = self.servings(nv) / self.target;
+        let fraction = if self.healthy {
+            fraction
+        } else {
+            1.0 - fraction
+        };
+        (fraction * 5.0).clamp(0.0, 5.0)
+    }
+
+    /// MEDI table row: one point when the target is met.
+    pub fn binary_component(&self, nv: &NutritionVector) -> ComponentScore {
+        ComponentScore {
+            name: self.name,
+            intake: self.servings(nv),
+            standard: self.target,
+            points: self.binary_score(nv),
+            max_points: 1.0,
+        }
+    }
+
+    /// MEDI_V2 table row: full points at the target for healthy components,
+    /// at zero intake otherwise.
+    pub fn graded_component(&self, nv: &NutritionVector) -> ComponentScore {
+        ComponentScore {
+            name: self.name,
+            intake: self.servings(nv),
+            standard: if self.healthy { self.target } else { 0.0 },
+            points: self.graded_score(nv),
+            max_points: 5.0,
+        }
+    }
+}
+
+pub struct MediScorer;
+
+impl FieldDeps for MediScorer {
+    fn name() -> &'static str {
+        "MEDI"
+    }
+
+    fn required_fields() -> &'static [&'static str] {
+        contracts::required_fields("MEDI")
+    }
+}
+
+impl DietScore for MediScorer {
+    fn evaluate(&self, nv: &NutritionVector) -> f64 {
+        total_points(&self.components(nv))
+    }
+
+    fn name(&self) -> &'static str {
+        <Self as FieldDeps>::name()
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        <Self as FieldDeps>::required_fields()
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        MEDI_COMPONENTS
+            .iter()
+            .map(|c| c.binary_component(nv))
+            .collect()
+    }
+}
+
+pub struct MediV2Scorer;
+
+impl FieldDeps for MediV2Scorer {
+    fn name() -> &'static str {
+        "MEDI_V2"
+    }
+
+    fn required_fields() -> &'static [&'static str] {
+        contracts::required_fields("MEDI_V2")
+    }
+}
+
+impl DietScore for MediV2Scorer {
+    fn evaluate(&self, nv: &NutritionVector) -> f64 {
+        total_points(&self.components(nv))
+    }
+
+    fn name(&self) -> &'static str {
+        <Self as FieldDeps>::name()
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        <Self as FieldDeps>::required_fields()
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        MEDI_COMPONENTS
+            .iter()
+            .map(|c| c.graded_component(nv))
+            .collect()
+    }
+}
@@ -1,4 +1,7 @@
-//! Available scorers: AHEI, HEI, DASH, aMED, DII, ACS2020, PHDI, DASHI, MIND.
+//! Available scorers: AHEI, AHEIP, HEI_2015, HEI_2020, HEI_TODDLERS_2020,
+//! DASH_LINEAR, aMED, DII, ACS2020, PHDI, PHDI_ENERGY_ADJUSTED, DASHI, MIND,
+//! MEDI, MEDI_V2, HCNS. Population-based scorers (AHEI_COHORT, DASH,
+//! aMED_COHORT, ACS2020_V1/V2/V3) implement [`BatchScore`].
 //!
 //! Partial evaluation is supported through [`evaluate_allow_partial`](crate::eval::evaluate_allow_partial).
 //! Each scorer returns a [`ScoreInfo`](crate::eval::ScoreInfo) indicating
@@ -9,33 +12,102 @@
 //! Helper functions like [`format_skipped_scores`](crate::eval::format_skipped_scores)
 //! can be used to present this information outside of the CLI.
 
+use crate::energy::EnergyAdjustment;
 use crate::nutrition_vector::NutritionVector;
+use serde::Serialize;
 
 pub trait FieldDeps {
     fn name() -> &'static str;
     fn required_fields() -> &'static [&'static str];
 }
 
+/// One row of an index's component table.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct ComponentScore {
+    pub name: &'static str,
+    /// Intake in the unit the standard is expressed in, e.g. cup equivalents
+    /// per 1,000 kcal for HEI or percent of energy for added sugars.
+    pub intake: f64,
+    /// Intake at which full points are awarded: the minimum for components
+    /// to encourage, the maximum for components to limit. DII parameters are
+    /// scored against the global mean intake instead.
+    pub standard: f64,
+    pub points: f64,
+    pub max_points: f64,
+}
+
 pub trait DietScore {
     fn name(&self) -> &'static str;
     fn evaluate(&self, nv: &NutritionVector) -> f64;
     fn required_fields(&self) -> &'static [&'static str];
+    /// Optional note attached to a computed score, e.g. inputs that were
+    /// available to the index but not supplied.
+    fn explain(&self, _nv: &NutritionVector) -> Option<String> {
+        None
+    }
+    /// Reason the index is not defined for this record, e.g. an age outside
+    /// the population an edition was designed for.
+    fn not_applicable(&self, _nv: &NutritionVector) -> Option<String> {
+        None
+    }
+    /// Points per component, in the order of the original publication.
+    /// Indices without a component table return an empty list.
+    fn components(&self, _nv: &NutritionVector) -> Vec<ComponentScore> {
+        Vec::new()
+    }
+    /// Names of the components [`Self::components`] reports, in order,
+    /// without a record to score, e.g. for the columns of a table.
+    fn component_names(&self) -> Vec<&'static str> {
+        self.components(&NutritionVector::default())
+            .iter()
+            .map(|c| c.name)
+            .collect()
+    }
+    /// Energy adjustment the standards of the index are written for, e.g.
+    /// densities per 1,000 kcal for HEI. Scorers receive intakes as reported
+    /// and apply it themselves, so a run-level adjustment never reaches them;
+    /// it is listed in [`registry::ScoreMeta`].
+    fn energy_adjustment(&self) -> EnergyAdjustment {
+        EnergyAdjustment::None
+    }
+}
+
+/// Total of a component table.
+pub fn total_points(components: &[ComponentScore]) -> f64 {
+    components.iter().map(|c| c.points).sum()
 }
 
 pub fn capped_score(value: f64, max: f64) -> f64 {
     (value / max * 10.0).clamp(0.0, 10.0)
 }
 
+/// Why a record without a positive energy cannot be scored on densities or
+/// shares of energy.
+pub const ENERGY_NOT_POSITIVE: &str = "energy must be positive";
+
+/// Reason a record cannot be scored on densities or shares of energy, if
+/// any.
+pub fn energy_not_positive(nv: &NutritionVector) -> Option<String> {
+    match nv.energy {
+        Some(kcal) if kcal > 0.0 => None,
+        _ => Some(ENERGY_NOT_POSITIVE.to_string()),
+    }
+}
+
 pub mod acs2020;
 pub mod ahei;
+pub mod aheip;
 pub mod amed;
+pub mod batch;
 pub mod dash;
 pub mod dashi;
 pub mod dii;
+pub mod hcns;
 pub mod hei;
+pub mod medi;
 pub mod mind;
 pub mod phdi;
 pub mod registry;
 
-/// Available scorers: AHEI, HEI, DASH, aMED, DII, ACS2020, PHDI, DASHI, MIND
+pub use batch::BatchScore;
 pub use registry::all_scorers;
@@ -1,7 +1,173 @@
-use super::{capped_score, DietScore, FieldDeps};
+//! Planetary Health Diet Index (Cacau et al., 2021) for the EAT-Lancet
+//! reference diet.
+//!
+//! Fifteen components: legumes and soy are worth up to 5 points, every other
+//! component up to 10, for a maximum of 140. Food groups are grams per day;
+//! added fats and added sugar are scored as percent of energy. Each component
+//! is linear between the intake earning zero points and the intake earning
+//! full points, so the same rule covers groups to encourage and to limit.
+//!
+//! PHDI scores intakes as reported, which is what both reference versions do
+//! (V1 takes the fats and sugar already as percent of energy, V2 as grams
+//! with total energy). PHDI_ENERGY_ADJUSTED first rescales the food groups to
+//! the 2,500 kcal of the EAT-Lancet reference diet, so that records with
+//! different energy intakes are compared on the same basis.
+
+use super::ahei::{sex_not_coded, FEMALE};
+use super::{energy_not_positive, total_points, ComponentScore, DietScore, FieldDeps};
 use crate::contracts;
+use crate::energy::EnergyAdjustment;
 use crate::nutrition_vector::NutritionVector;
 
+/// Energy of the EAT-Lancet reference diet.
+pub const REFERENCE_KCAL: f64 = 2500.0;
+/// [`REFERENCE_KCAL`] as declared by PHDI_ENERGY_ADJUSTED.
+pub const ENERGY_ADJUSTMENT: EnergyAdjustment =
+    EnergyAdjustment::PerReferenceKcal(REFERENCE_KCAL as u32);
+const KCAL_PER_G_FAT: f64 = 9.0;
+const KCAL_PER_G_SUGAR: f64 = 4.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Intake {
+    /// Grams per day of a food group.
+    Grams,
+    /// Percent of energy, from grams per day at this many kcal per gram.
+    EnergyShare(f64),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PhdiComponent {
+    pub name: &'static str,
+    pub field: &'static str,
+    pub intake: Intake,
+    pub points: f64,
+    /// Intake earning zero points.
+    pub zero_at: f64,
+    /// Intake earning full points.
+    pub full_at: f64,
+}
+
+const fn grams(
+    name: &'static str,
+    field: &'static str,
+    zero_at: f64,
+    full_at: f64,
+) -> PhdiComponent {
+    PhdiComponent {
+        name,
+        field,
+        intake: Intake::Grams,
+        points: 10.0,
+        zero_at,
+        full_at,
+    }
+}
+
+const fn energy_share(
+    name: &'static str,
+    field: &'static str,
+    kcal_per_g: f64,
+    zero_at: f64,
+    full_at: f64,
+) -> PhdiComponent {
+    PhdiComponent {
+        name,
+        field,
+        intake: Intake::EnergyShare(kcal_per_g),
+        points: 10.0,
+        zero_at,
+        full_at,
+    }
+}
+
+/// Components for a record of the given sex code; only the whole grain
+/// target differs between women and men.
+pub fn phdi_components(sex: f64) -> [PhdiComponent; 15] {
+    let whole_grain_target = if sex == FEMALE { 75.0 } else { 90.0 };
+    [
+        grams("whole_grains", "whole_grains", 0.0, whole_grain_target),
+        grams("starchy_vegetables", "starchy_veg", 200.0, 50.0),
+        grams("vegetables", "vegetables", 0.0, 300.0),
+        grams("fruit", "total_fruits", 0.0, 200.0),
+        grams("dairy", "dairy", 1000.0, 250.0),
+        grams("red_processed_meat", "red_meat", 100.0, 14.0),
+        grams("poultry", "poultry", 100.0, 29.0),
+        grams("eggs", "eggs", 120.0, 13.0),
+        grams("fish", "fish", 0.0, 28.0),
+        grams("nuts", "nuts", 0.0, 50.0),
+        PhdiComponent {
+            points: 5.0,
+            ..grams("legumes", "legumes", 0.0, 100.0)
+        },
+        PhdiComponent {
+            points: 5.0,
+            ..grams("soy", "soy", 0.0, 50.0)
+        },
+        energy_share(
+            "added_fat_unsaturated",
+            "added_fat_unsat",
+            KCAL_PER_G_FAT,
+            3.5,
+            21.0,
+        ),
+        energy_share(
+            "added_fat_saturated_trans",
+            "added_fat_sat_trans",
+            KCAL_PER_G_FAT,
+            10.0,
+            0.0,
+        ),
+        energy_share("added_sugar", "added_sugar_g", KCAL_PER_G_SUGAR, 25.0, 5.0),
+    ]
+}
+
+impl PhdiComponent {
+    /// Intake in the unit of the cut points. Gram intakes are multiplied by
+    /// `scale`; energy shares do not depend on it.
+    pub fn intake(&self, nv: &NutritionVector, scale: f64) -> f64 {
+        let value = nv.get(self.field).unwrap_or(0.0);
+        match self.intake {
+            Intake::Grams => value * scale,
+            Intake::EnergyShare(kcal_per_g) => {
+                value * kcal_per_g / nv.energy.unwrap_or(0.0) * 100.0
+            }
+        }
+    }
+
+    pub fn score(&self, intake: f64) -> f64 {
+        let fraction = (intake - self.zero_at) / (self.full_at - self.zero_at);
+        fraction.clamp(0.0, 1.0) * self.points
+    }
+}
+
+fn scores(nv: &NutritionVector, scale: f64) -> Vec<ComponentScore> {
+    phdi_components(nv.sex.unwrap_or(FEMALE))
+        .iter()
+        .map(|c| {
+            let intake = c.intake(nv, scale);
+            ComponentScore {
+                name: c.name,
+                intake,
+                standard: c.full_at,
+                points: c.score(intake),
+                max_points: c.points,
+            }
+        })
+        .collect()
+}
+
+/// Points for each component of PHDI, in the order of the original
+/// publication.
+pub fn component_scores(nv: &NutritionVector) -> Vec<ComponentScore> {
+    scores(nv, 1.0)
+}
+
+/// Points for each component of PHDI_ENERGY_ADJUSTED, with food groups
+/// rescaled to [`REFERENCE_KCAL`].
+pub fn energy_adjusted_component_scores(nv: &NutritionVector) -> Vec<ComponentScore> {
+    scores(nv, REFERENCE_KCAL / nv.energy.unwrap_or(0.0))
+}
+
 pub struct PhdiScorer;
 
 impl FieldDeps for PhdiScorer {
@@ -16,29 +182,41 @@ impl FieldDeps for PhdiScorer {
 
 impl DietScore for PhdiScorer {
     fn evaluate(&self, nv: &NutritionVector) -> f64 {
-        let veg = capped_score(nv.vegetables.unwrap_or(0.0), 300.0);
-        let legumes = capped_score(nv.legumes.unwrap_or(0.0), 100.0);
-        let grains = capped_score(nv.whole_grains.unwrap_or(0.0), 90.0);
-        let unsat_fat_g = (nv.fat.unwrap_or(0.0)
-            - nv.saturated_fat.unwrap_or(0.0)
-            - nv.trans_fat.unwrap_or(0.0))
-        .max(0.0);
-        let unsat_fat = capped_score(unsat_fat_g, 20.0);
-        let red_meat = (10.0 - capped_score(nv.red_meat.unwrap_or(0.0), 100.0)).clamp(0.0, 10.0);
-        let sugar = (10.0 - capped_score(nv.sugar.unwrap_or(0.0), 50.0)).clamp(0.0, 10.0);
-        let refined =
-            (10.0 - capped_score(nv.refined_grains.unwrap_or(0.0), 150.0)).clamp(0.0, 10.0);
-        let energy = if let Some(kcal) = nv.energy {
-            if kcal >= 1500.0 && kcal <= 2500.0 {
-                10.0
-            } else {
-                0.0
-            }
-        } else {
-            10.0
-        };
+        total_points(&component_scores(nv))
+    }
 
-        veg + legumes + grains + unsat_fat + red_meat + sugar + refined + energy
+    fn name(&self) -> &'static str {
+        <Self as FieldDeps>::name()
+    }
+
+    fn required_fields(&self) -> &'static [&'static str] {
+        <Self as FieldDeps>::required_fields()
+    }
+
+    fn not_applicable(&self, nv: &NutritionVector) -> Option<String> {
+        sex_not_coded(nv).or_else(|| energy_not_positive(nv))
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        component_scores(nv)
+    }
+}
+
+pub struct PhdiEnergyAdjustedScorer;
+
+impl FieldDeps for PhdiEnergyAdjustedScorer {
+    fn name() -> &'static str {
+        "PHDI_ENERGY_ADJUSTED"
+    }
+
+    fn required_fields() -> &'static [&'static str] {
+        contracts::required_fields("PHDI_ENERGY_ADJUSTED")
+    }
+}
+
+impl DietScore for PhdiEnergyAdjustedScorer {
+    fn evaluate(&self, nv: &NutritionVector) -> f64 {
+        total_points(&energy_adjusted_component_scores(nv))
     }
 
     fn name(&self) -> &'static str {
@@ -48,4 +226,16 @@ impl DietScore for PhdiScorer {
     fn required_fields(&self) -> &'static [&'static str] {
         <Self as FieldDeps>::required_fields()
     }
+
+    fn not_applicable(&self, nv: &NutritionVector) -> Option<String> {
+        sex_not_coded(nv).or_else(|| energy_not_positive(nv))
+    }
+
+    fn components(&self, nv: &NutritionVector) -> Vec<ComponentScore> {
+        energy_adjusted_component_scores(nv)
+    }
+
+    fn energy_adjustment(&self) -> EnergyAdjustment {
+        ENERGY_ADJUSTMENT
+    }
 }
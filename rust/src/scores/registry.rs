@@ -1,4 +1,6 @@
-use super::{DietScore, FieldDeps};
+use super::{BatchScore, DietScore};
+use crate::energy::EnergyAdjustment;
+use crate::nutrition_vector::NutritionVector;
 use serde::Serialize;
 
 #[macro_export]
@@ -6,14 +8,21 @@ macro_rules! register_scores {
     () => {{
         let scores: Vec<Box<dyn $crate::scores::DietScore>> = vec![
             Box::new($crate::scores::ahei::Ahei),
-            Box::new($crate::scores::hei::HeiScorer),
-            Box::new($crate::scores::dash::DashScorer),
+            Box::new($crate::scores::aheip::AheipScorer),
+            Box::new($crate::scores::hei::Hei2015Scorer),
+            Box::new($crate::scores::hei::Hei2020Scorer),
+            Box::new($crate::scores::hei::HeiToddlers2020Scorer),
+            Box::new($crate::scores::dash::DashLinearScorer),
             Box::new($crate::scores::dashi::DashiScorer),
             Box::new($crate::scores::amed::AMedScorer),
             Box::new($crate::scores::dii::DiiScorer),
             Box::new($crate::scores::phdi::PhdiScorer),
+            Box::new($crate::scores::phdi::PhdiEnergyAdjustedScorer),
             Box::new($crate::scores::acs2020::Acs2020Scorer),
             Box::new($crate::scores::mind::MindScorer),
+            Box::new($crate::scores::medi::MediScorer),
+            Box::new($crate::scores::medi::MediV2Scorer),
+            Box::new($crate::scores::hcns::HcnsScorer),
         ];
         scores
     }};
@@ -23,52 +32,73 @@ pub fn all_scorers() -> Vec<Box<dyn DietScore>> {
     register_scores!()
 }
 
+#[macro_export]
+macro_rules! register_batch_scores {
+    () => {{
+        use $crate::scores::acs2020::{Acs2020CohortScorer, Acs2020Version};
+        let scores: Vec<Box<dyn $crate::scores::BatchScore>> = vec![
+            Box::new($crate::scores::ahei::AheiCohortScorer),
+            Box::new($crate::scores::amed::AMedCohortScorer::default()),
+            Box::new($crate::scores::dash::DashCohortScorer::default()),
+            Box::new(Acs2020CohortScorer(Acs2020Version::V1)),
+            Box::new(Acs2020CohortScorer(Acs2020Version::V2)),
+            Box::new(Acs2020CohortScorer(Acs2020Version::V3)),
+        ];
+        scores
+    }};
+}
+
+/// Scorers that need the whole dataset, see [`BatchScore`].
+pub fn all_batch_scorers() -> Vec<Box<dyn BatchScore>> {
+    register_batch_scores!()
+}
+
+/// Look up a batch scorer by its registered name, e.g. `ACS2020_V2`.
+pub fn batch_scorer(name: &str) -> Option<Box<dyn BatchScore>> {
+    all_batch_scorers().into_iter().find(|s| s.name() == name)
+}
+
+/// Fields any batch scorer reads, see [`BatchScore::input_fields`], in the
+/// order of [`NutritionVector::all_field_names`].
+pub fn cohort_fields() -> Vec<&'static str> {
+    let read: Vec<&'static str> = all_batch_scorers()
+        .iter()
+        .flat_map(|s| s.input_fields())
+        .collect();
+    NutritionVector::all_field_names()
+        .iter()
+        .copied()
+        .filter(|f| read.contains(f))
+        .collect()
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct ScoreMeta {
     pub name: &'static str,
     pub required_fields: &'static [&'static str],
+    /// Scored against the whole dataset, see [`BatchScore`].
+    pub batch: bool,
+    /// Adjustment the index applies itself, or for batch scorers the one
+    /// applied to the batch unless the run picks another.
+    pub energy_adjustment: EnergyAdjustment,
 }
 
 pub fn all_score_metadata() -> Vec<ScoreMeta> {
-    let mut metas = vec![
-        ScoreMeta {
-            name: <crate::scores::ahei::Ahei as FieldDeps>::name(),
-            required_fields: <crate::scores::ahei::Ahei as FieldDeps>::required_fields(),
-        },
-        ScoreMeta {
-            name: <crate::scores::hei::HeiScorer as FieldDeps>::name(),
-            required_fields: <crate::scores::hei::HeiScorer as FieldDeps>::required_fields(),
-        },
-        ScoreMeta {
-            name: <crate::scores::dash::DashScorer as FieldDeps>::name(),
-            required_fields: <crate::scores::dash::DashScorer as FieldDeps>::required_fields(),
-        },
-        ScoreMeta {
-            name: <crate::scores::dashi::DashiScorer as FieldDeps>::name(),
-            required_fields: <crate::scores::dashi::DashiScorer as FieldDeps>::required_fields(),
-        },
-        ScoreMeta {
-            name: <crate::scores::amed::AMedScorer as FieldDeps>::name(),
-            required_fields: <crate::scores::amed::AMedScorer as FieldDeps>::required_fields(),
-        },
-        ScoreMeta {
-            name: <crate::scores::dii::DiiScorer as FieldDeps>::name(),
-            required_fields: <crate::scores::dii::DiiScorer as FieldDeps>::required_fields(),
-        },
-        ScoreMeta {
-            name: <crate::scores::phdi::PhdiScorer as FieldDeps>::name(),
-            required_fields: <crate::scores::phdi::PhdiScorer as FieldDeps>::required_fields(),
-        },
-        ScoreMeta {
-            name: <crate::scores::acs2020::Acs2020Scorer as FieldDeps>::name(),
-            required_fields: <crate::scores::acs2020::Acs2020Scorer as FieldDeps>::required_fields(
-            ),
-        },
-        ScoreMeta {
-            name: <crate::scores::mind::MindScorer as FieldDeps>::name(),
-            required_fields: <crate::scores::mind::MindScorer as FieldDeps>::required_fields(),
-        },
-    ];
+    let mut metas: Vec<ScoreMeta> = all_scorers()
+        .iter()
+        .map(|s| ScoreMeta {
+            name: s.name(),
+            required_fields: s.required_fields(),
+            batch: false,
+            energy_adjustment: s.energy_adjustment(),
+        })
+        .collect();
+    metas.extend(all_batch_scorers().iter().map(|s| ScoreMeta {
+        name: s.name(),
+        required_fields: s.required_fields(),
+        batch: true,
+        energy_adjustment: s.energy_adjustment(),
+    }));
     metas.sort_by(|a, b| a.name.cmp(b.name));
     metas
 }
@@ -0,0 +1,437 @@
+//! Row-by-row scoring of tables.
+//!
+//! [`ScoreStream`] reads one record at a time, from delimited text or from
+//! records already parsed, translates it with the [`SourceHeaders`] detected
+//! from the headers and scores it with the per-record scorers. Coverage is
+//! accumulated as counts, running sums and bounded quantile sketches, plus
+//! one key per distinct identifier to flag identifiers shared by several
+//! rows.
+//!
+//! The cohort scorers of [`crate::scores::batch`] need every row at once, so
+//! scoring them is not bounded in memory. [`ScoreStream::cohort_scores`]
+//! reads the rows once for them and keeps the fields they read, some twenty
+//! values per row, then one value per cohort index per row;
+//! [`ScoreStream::with_cohort_scores`] merges those into a second pass that
+//! reads the same rows again.
+
+use crate::energy::EnergyAdjustment;
+use crate::eval::{evaluate_cohort, CohortScores};
+use crate::food_matching::DEFAULT_MATCH_THRESHOLD;
+use crate::food_patterns::FoodPatternTable;
+use crate::nhanes_ingest::RecallDays;
+use crate::nutrition_vector::NutritionVector;
+use crate::output::{TableLayout, TableSink};
+use crate::record_ids::{id_key, record_id, resolve_id_columns};
+use crate::scores::batch::CohortInputs;
+use crate::scores::registry::cohort_fields;
+use crate::survey::{
+    design_columns, design_variables, dietary_weight, QuantileSketch, WeightedSummary,
+};
+use crate::tabular::{record_map, score_record, units_row, ScoredRow, SourceHeaders};
+use crate::units::ColumnUnits;
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// Rows per chunk handed to a [`TableSink`].
+pub const CHUNK_ROWS: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    pub delimiter: u8,
+    pub recall_days: RecallDays,
+    /// Identifier columns; empty for the defaults of
+    /// [`crate::record_ids::DEFAULT_ID_COLUMNS`].
+    pub id_columns: Vec<String>,
+    /// Similarity from 0 to 1 a food column name needs to be matched to a
+    /// food item, see [`crate::food_matching`].
+    pub match_threshold: f64,
+    /// Food pattern equivalents that food columns resolve through by food
+    /// code or description, see [`crate::food_patterns`].
+    pub food_patterns: Option<Arc<FoodPatternTable>>,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions {
+            delimiter: b',',
+            recall_days: RecallDays::default(),
+            id_columns: Vec::new(),
+            match_threshold: DEFAULT_MATCH_THRESHOLD,
+            food_patterns: None,
+        }
+    }
+}
+
+impl StreamOptions {
+    pub fn new(delimiter: u8) -> Self {
+        StreamOptions {
+            delimiter,
+            ..Default::default()
+        }
+    }
+}
+
+/// Records of a delimited table, keyed by header.
+pub struct DelimitedRecords<R: Read> {
+    reader: csv::Reader<R>,
+    headers: Vec<String>,
+    /// First data row, read while looking for a units row.
+    pending: Option<csv::StringRecord>,
+}
+
+impl<R: Read> Iterator for DelimitedRecords<R> {
+    type Item = Result<HashMap<String, Value>, csv::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = csv::StringRecord::new();
+        loop {
+            if let Some(pending) = self.pending.take() {
+                record = pending;
+                if record.iter().all(str::is_empty) {
+                    continue;
+                }
+                break;
+            }
+            match self.reader.read_record(&mut record) {
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+                Ok(true) if record.iter().all(str::is_empty) => continue,
+                Ok(true) => break,
+            }
+        }
+        Some(Ok(record_map(&self.headers, &record)))
+    }
+}
+
+/// One record of a stream, not yet translated.
+struct StreamRecord<R> {
+    id: BTreeMap<String, Value>,
+    design: BTreeMap<String, Value>,
+    raw: R,
+}
+
+/// Scored rows of a table, in input order.
+pub struct ScoreStream<I> {
+    records: I,
+    source: SourceHeaders,
+    id_columns: Vec<String>,
+    design_columns: Vec<String>,
+    recall_days: RecallDays,
+    match_threshold: f64,
+    food_patterns: Option<Arc<FoodPatternTable>>,
+    next_row: usize,
+    /// Units of the columns after translation.
+    units: ColumnUnits,
+    coverage: CoverageAccumulator,
+    /// Cohort scores of the rows, see [`Self::with_cohort_scores`].
+    cohort: Option<CohortScores>,
+}
+
+impl<R: Read> ScoreStream<DelimitedRecords<R>> {
+    /// Reads the header line of `input` and detects its source format. A
+    /// units row under the header gives the units of the columns.
+    pub fn new(input: R, options: &StreamOptions) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(input);
+        let headers: Vec<String> = reader
+            .headers()?
+            .iter()
+            .map(|h| h.trim_start_matches('\u{feff}').to_string())
+            .collect();
+        let mut first = csv::StringRecord::new();
+        let (units, pending) = if reader.read_record(&mut first)? {
+            match units_row(&headers, &first) {
+                Some(units) => (units, None),
+                None => (HashMap::new(), Some(first)),
+            }
+        } else {
+            (HashMap::new(), None)
+        };
+        let records = DelimitedRecords {
+            reader,
+            headers: headers.clone(),
+            pending,
+        };
+        Ok(Self::from_records(records, &headers, units, options))
+    }
+}
+
+impl<I, R> ScoreStream<I>
+where
+    I: Iterator<Item = Result<R, csv::Error>>,
+    R: Borrow<HashMap<String, Value>>,
+{
+    /// Scores records keyed by column name, owned or borrowed, given the `headers` found across
+    /// them and the `units` of their columns. The delimiter of `options` is
+    /// not used.
+    pub fn from_records(
+        records: I,
+        headers: &[String],
+        units: ColumnUnits,
+        options: &StreamOptions,
+    ) -> Self {
+        let source = SourceHeaders::detect(headers);
+        ScoreStream {
+            units: source.translate_units(units),
+            source,
+            records,
+            id_columns: resolve_id_columns(headers, &options.id_columns),
+            design_columns: design_columns(headers),
+            recall_days: options.recall_days,
+            match_threshold: options.match_threshold,
+            food_patterns: options.food_patterns.clone(),
+            next_row: 0,
+            coverage: CoverageAccumulator::default(),
+            cohort: None,
+        }
+    }
+
+    pub fn id_columns(&self) -> &[String] {
+        &self.id_columns
+    }
+
+    /// Survey design columns of the table, see [`crate::survey`].
+    pub fn design_columns(&self) -> &[String] {
+        &self.design_columns
+    }
+
+    /// Column units read from a units row, empty without one.
+    pub fn units(&self) -> &ColumnUnits {
+        &self.units
+    }
+
+    /// Coverage of the rows read so far.
+    pub fn coverage(&self) -> StreamCoverage {
+        self.coverage.summary()
+    }
+
+    /// Scores the cohort indices of all remaining rows, with the energy
+    /// adjustment of [`crate::eval::evaluate_batch_with_adjustment`], for
+    /// [`Self::with_cohort_scores`]. Memory grows with the rows: the
+    /// [`cohort_fields`] of each row are kept until the cohort is scored.
+    pub fn cohort_scores(
+        mut self,
+        adjustment: Option<EnergyAdjustment>,
+    ) -> Result<CohortScores, csv::Error> {
+        let mut inputs = CohortInputs::new(&cohort_fields());
+        while let Some(record) = self.next_record() {
+            let record = record?;
+            let (translated, _) = self.source.apply(record.raw.borrow(), self.recall_days);
+            inputs.push(&NutritionVector::from_partial_map_quiet(
+                &translated,
+                &self.units,
+                self.match_threshold,
+                self.food_patterns.as_deref(),
+            ));
+        }
+        Ok(evaluate_cohort(&inputs, adjustment))
+    }
+
+    /// Adds `cohort`, the [`Self::cohort_scores`] of an earlier pass over
+    /// the same rows, to the rows of this stream in order.
+    pub fn with_cohort_scores(mut self, cohort: CohortScores) -> Self {
+        self.cohort = Some(cohort);
+        self
+    }
+
+    fn next_record(&mut self) -> Option<Result<StreamRecord<R>, csv::Error>> {
+        let raw = match self.records.next()? {
+            Ok(raw) => raw,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok(StreamRecord {
+            id: record_id(raw.borrow(), &self.id_columns),
+            design: design_variables(raw.borrow()),
+            raw,
+        }))
+    }
+}
+
+impl<I, R> Iterator for ScoreStream<I>
+where
+    I: Iterator<Item = Result<R, csv::Error>>,
+    R: Borrow<HashMap<String, Value>>,
+{
+    type Item = Result<ScoredRow, csv::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.next_record()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        let weight = dietary_weight(&record.design, self.recall_days);
+        let (translated, recall) = self.source.apply(record.raw.borrow(), self.recall_days);
+        let mut result = score_record(
+            &translated,
+            &self.units,
+            recall,
+            self.match_threshold,
+            self.food_patterns.as_deref(),
+        );
+        let missing = &result.trace.missing_fields;
+        if let Some(cohort) = self
+            .cohort
+            .as_ref()
+            .and_then(|c| c.result(self.next_row, missing))
+        {
+            result.merge(cohort);
+        }
+        let row = ScoredRow {
+            row: self.next_row,
+            id: record.id,
+            design: record.design,
+            result,
+        };
+        self.next_row += 1;
+        self.coverage.add(&row, &self.id_columns, weight);
+        Some(Ok(row))
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct StreamCoverage {
+    pub rows: usize,
+    pub percent_scored: BTreeMap<String, f64>,
+    pub most_missing: Vec<(String, usize)>,
+    pub alias_hits: BTreeMap<String, usize>,
+    /// Rows lacking an identifier, when identifier columns were found.
+    pub missing_ids: usize,
+    /// Identifiers shared by several rows, with the positions of those rows.
+    pub duplicate_ids: BTreeMap<String, Vec<usize>>,
+    /// Weighted means and percentiles of valid scores, when rows carry
+    /// dietary weights. Percentiles are approximate on long streams, see
+    /// [`QuantileSketch`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub weighted: BTreeMap<String, WeightedSummary>,
+}
+
+#[derive(Debug, Default)]
+struct CoverageAccumulator {
+    rows: usize,
+    missing_ids: usize,
+    /// First row of every identifier seen.
+    first_rows: HashMap<String, usize>,
+    duplicate_ids: BTreeMap<String, Vec<usize>>,
+    scored: HashMap<String, usize>,
+    missing: HashMap<&'static str, usize>,
+    aliases: HashMap<String, usize>,
+    weighted: HashMap<String, QuantileSketch>,
+}
+
+impl CoverageAccumulator {
+    fn add(&mut self, row: &ScoredRow, id_columns: &[String], weight: Option<f64>) {
+        self.rows += 1;
+        if row.id.len() < id_columns.len() {
+            self.missing_ids += 1;
+        } else if !id_columns.is_empty() {
+            let key = id_key(id_columns, &row.id);
+            match self.first_rows.get(&key) {
+                Some(&first) => self
+                    .duplicate_ids
+                    .entry(key)
+                    .or_insert_with(|| vec![first])
+                    .push(row.row),
+                None => {
+                    self.first_rows.insert(key, row.row);
+                }
+            }
+        }
+        let trace = &row.result.trace;
+        for field in &trace.missing_fields {
+            *self.missing.entry(*field).or_insert(0) += 1;
+        }
+        for (alias, _) in &trace.aliases_applied {
+            *self.aliases.entry(alias.clone()).or_insert(0) += 1;
+        }
+        for (name, info) in &row.result.scores {
+            let Some(value) = info.value else { continue };
+            *self.scored.entry(name.clone()).or_insert(0) += 1;
+            if let (Some(w), true) = (weight, info.valid) {
+                self.weighted.entry(name.clone()).or_default().add(value, w);
+            }
+        }
+    }
+
+    fn summary(&self) -> StreamCoverage {
+        let mut most_missing: Vec<(String, usize)> = self
+            .missing
+            .iter()
+            .map(|(f, c)| (f.to_string(), *c))
+            .collect();
+        most_missing.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        StreamCoverage {
+            rows: self.rows,
+            percent_scored: self
+                .scored
+                .iter()
+                .map(|(name, c)| (name.clone(), *c as f64 * 100.0 / self.rows.max(1) as f64))
+                .collect(),
+            most_missing,
+            alias_hits: self.aliases.clone().into_iter().collect(),
+            missing_ids: self.missing_ids,
+            duplicate_ids: self.duplicate_ids.clone(),
+            weighted: self
+                .weighted
+                .iter()
+                .filter_map(|(name, sketch)| sketch.summary().map(|s| (name.clone(), s)))
+                .collect(),
+        }
+    }
+}
+
+/// Writes streamed rows to `sink` in chunks of [`CHUNK_ROWS`], all in
+/// `layout`, e.g. [`TableLayout::from_registry`]. Returns the number of rows
+/// written.
+pub fn write_table_stream(
+    rows: impl Iterator<Item = Result<ScoredRow, csv::Error>>,
+    layout: &TableLayout,
+    sink: &mut dyn TableSink,
+) -> anyhow::Result<usize> {
+    let mut chunk = Vec::with_capacity(CHUNK_ROWS);
+    let mut written = 0;
+    let mut rows = rows.peekable();
+    loop {
+        if let Some(row) = rows.next() {
+            chunk.push(row?);
+        }
+        let last = rows.peek().is_none();
+        if chunk.len() == CHUNK_ROWS || (last && (!chunk.is_empty() || written == 0)) {
+            sink.write_chunk(&layout.table(&chunk))?;
+            written += chunk.len();
+            chunk.clear();
+        }
+        if last {
+            break;
+        }
+    }
+    sink.finish()?;
+    Ok(written)
+}
+
+/// Writes streamed rows as a JSON array, one row at a time. Returns the
+/// number of rows written.
+pub fn write_json_stream(
+    rows: impl Iterator<Item = Result<ScoredRow, csv::Error>>,
+    out: &mut dyn Write,
+) -> anyhow::Result<usize> {
+    let mut written = 0;
+    write!(out, "[")?;
+    for row in rows {
+        if written > 0 {
+            write!(out, ",")?;
+        }
+        writeln!(out)?;
+        serde_json::to_writer_pretty(&mut *out, &row?)?;
+        written += 1;
+    }
+    writeln!(out, "\n]")?;
+    out.flush()?;
+    Ok(written)
+}
@@ -0,0 +1,169 @@
+//! Survey design variables and weighted population summaries.
+//!
+//! NHANES estimates need the dietary day weights and the masked design
+//! variables (strata and PSU). They are carried from the input rows to the
+//! output unchanged so results can be analysed with survey software without
+//! re-joining by row position; the weighted summaries here are point
+//! estimates only.
+
+use crate::nhanes_ingest::RecallDays;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Dietary day weights and design variables. The respondent identifier
+/// (`SEQN`) is an identifier column, see [`crate::record_ids`].
+pub const DESIGN_COLUMNS: &[&str] = &["WTDRD1", "WTDR2D", "SDMVPSU", "SDMVSTRA"];
+
+/// Percentiles reported in [`WeightedSummary`].
+pub const SUMMARY_PERCENTILES: &[f64] = &[5.0, 25.0, 50.0, 75.0, 95.0];
+
+/// Values a [`QuantileSketch`] holds before merging neighbours.
+pub const SKETCH_CAPACITY: usize = 2048;
+
+/// Design columns present in `headers`, under their upper-case names and in
+/// the order of [`DESIGN_COLUMNS`].
+pub fn design_columns(headers: &[String]) -> Vec<String> {
+    DESIGN_COLUMNS
+        .iter()
+        .filter(|c| headers.iter().any(|h| h.trim().eq_ignore_ascii_case(c)))
+        .map(|c| c.to_string())
+        .collect()
+}
+
+/// Design columns present in `row`, under their upper-case names.
+pub fn design_variables(row: &HashMap<String, Value>) -> BTreeMap<String, Value> {
+    row.iter()
+        .filter_map(|(k, v)| {
+            let upper = k.trim().to_ascii_uppercase();
+            DESIGN_COLUMNS
+                .contains(&upper.as_str())
+                .then(|| (upper, v.clone()))
+        })
+        .collect()
+}
+
+/// Dietary weight matching the recall days used: the two-day weight when
+/// both days are combined, the day 1 weight otherwise or when no two-day
+/// weight is given.
+pub fn dietary_weight(design: &BTreeMap<String, Value>, mode: RecallDays) -> Option<f64> {
+    let day1 = design.get("WTDRD1").and_then(Value::as_f64);
+    let two_day = design.get("WTDR2D").and_then(Value::as_f64);
+    match mode {
+        RecallDays::Day1Only => day1,
+        RecallDays::MeanOfDays | RecallDays::RequireBoth => two_day.or(day1),
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct WeightedSummary {
+    /// Records with a score and a positive weight.
+    pub n: usize,
+    pub weight_total: f64,
+    pub mean: f64,
+    /// Weighted percentiles keyed by `p5`, `p25`, ...
+    pub percentiles: BTreeMap<String, f64>,
+}
+
+/// Weighted mean and percentiles of `values`. Pairs with a non-positive or
+/// non-finite weight or value are ignored; returns `None` when none remain.
+pub fn weighted_summary(pairs: &[(f64, f64)]) -> Option<WeightedSummary> {
+    let mut sorted: Vec<(f64, f64)> = pairs
+        .iter()
+        .copied()
+        .filter(|(v, w)| v.is_finite() && w.is_finite() && *w > 0.0)
+        .collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let weight_total: f64 = sorted.iter().map(|(_, w)| w).sum();
+    let mean = sorted.iter().map(|(v, w)| v * w).sum::<f64>() / weight_total;
+    Some(WeightedSummary {
+        n: sorted.len(),
+        weight_total,
+        mean,
+        percentiles: percentiles(&sorted, weight_total),
+    })
+}
+
+/// Weighted values summarised in bounded memory, for streams too long to
+/// keep. Up to [`SKETCH_CAPACITY`] values are held as they are; when full,
+/// neighbouring values are merged pairwise into their weighted mean. The
+/// count, total weight and mean stay exact, the percentiles become
+/// approximate once values have been merged.
+#[derive(Debug, Clone, Default)]
+pub struct QuantileSketch {
+    n: usize,
+    weight_total: f64,
+    weighted_sum: f64,
+    // (value, weight)
+    centroids: Vec<(f64, f64)>,
+}
+
+impl QuantileSketch {
+    /// Adds a value; non-positive or non-finite weights and values are
+    /// ignored, as in [`weighted_summary`].
+    pub fn add(&mut self, value: f64, weight: f64) {
+        if !(value.is_finite() && weight.is_finite() && weight > 0.0) {
+            return;
+        }
+        self.n += 1;
+        self.weight_total += weight;
+        self.weighted_sum += weight * value;
+        self.centroids.push((value, weight));
+        if self.centroids.len() >= SKETCH_CAPACITY {
+            self.centroids.sort_by(|a, b| a.0.total_cmp(&b.0));
+            self.centroids = self
+                .centroids
+                .chunks(2)
+                .map(|pair| {
+                    let weight: f64 = pair.iter().map(|(_, w)| w).sum();
+                    let value = pair.iter().map(|(v, w)| v * w).sum::<f64>() / weight;
+                    (value, weight)
+                })
+                .collect();
+        }
+    }
+
+    /// Summary of the values added so far, `None` before the first.
+    pub fn summary(&self) -> Option<WeightedSummary> {
+        if self.n == 0 {
+            return None;
+        }
+        let mut sorted = self.centroids.clone();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(WeightedSummary {
+            n: self.n,
+            weight_total: self.weight_total,
+            mean: self.weighted_sum / self.weight_total,
+            percentiles: percentiles(&sorted, self.weight_total),
+        })
+    }
+}
+
+fn percentiles(sorted: &[(f64, f64)], weight_total: f64) -> BTreeMap<String, f64> {
+    SUMMARY_PERCENTILES
+        .iter()
+        .map(|&p| {
+            (
+                format!("p{}", p),
+                weighted_quantile(sorted, weight_total, p / 100.0),
+            )
+        })
+        .collect()
+}
+
+// Smallest value whose cumulative weight reaches `p` of the total, on pairs
+// sorted by value.
+fn weighted_quantile(sorted: &[(f64, f64)], weight_total: f64, p: f64) -> f64 {
+    let target = p * weight_total;
+    let mut cumulative = 0.0;
+    for &(value, weight) in sorted {
+        cumulative += weight;
+        if cumulative >= target {
+            return value;
+        }
+    }
+    sorted[sorted.len() - 1].0
+}
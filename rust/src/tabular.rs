@@ -0,0 +1,225 @@
+//! Delimited tables (CSV, TSV) and the header resolution shared by the CLI
+//! and the WASM build.
+//!
+//! Cells are read as numbers where they parse, as text otherwise; empty and
+//! `NA`-style cells are left out so the field counts as missing. Headers of
+//! ACS2020, HCSN and NHANES exports are then translated to canonical fields;
+//! other headers go through the field aliases when the rows are mapped.
+
+use crate::acs2020_ingest::{is_acs2020_sheet, resolve_acs2020_headers};
+use crate::eval::{evaluate_allow_partial, ScoreResult};
+use crate::food_patterns::FoodPatternTable;
+use crate::hcsn_ingest::{aggregate_hcsn_row, is_hcsn_sheet, resolve_hcsn_headers};
+use crate::nhanes_ingest::{aggregate_nhanes_row, is_nhanes_sheet, RecallDays, RecallSummary};
+use crate::nutrition_vector::NutritionVector;
+use crate::stream::{ScoreStream, StreamOptions};
+use crate::units::{is_units_row, ColumnUnits};
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Cell contents treated as missing.
+pub const MISSING_MARKERS: &[&str] = &["na", "n/a", "nan", "null", "."];
+
+/// Tab for `.tsv` and `.tab` files, comma otherwise.
+pub fn delimiter_for(path: &Path) -> u8 {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("tsv") || ext.eq_ignore_ascii_case("tab") => b'\t',
+        _ => b',',
+    }
+}
+
+/// One map per data row, keyed by the trimmed header, and the column units
+/// of a units row under the header, if there is one.
+pub fn read_delimited(
+    text: &str,
+    delimiter: u8,
+) -> Result<(Vec<HashMap<String, Value>>, ColumnUnits), csv::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    let mut rows = Vec::new();
+    let mut units = HashMap::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        match units_row(&headers, &record) {
+            Some(row_units) if i == 0 => units = row_units,
+            _ => rows.push(record_map(&headers, &record)),
+        }
+    }
+    Ok((rows, units))
+}
+
+/// Column units of `record` when it is a units row, see
+/// [`crate::units::is_units_row`].
+pub fn units_row(headers: &[String], record: &csv::StringRecord) -> Option<ColumnUnits> {
+    is_units_row(record.iter()).then(|| {
+        headers
+            .iter()
+            .zip(record.iter())
+            .filter(|(_, unit)| !unit.is_empty())
+            .map(|(h, unit)| (h.clone(), unit.to_string()))
+            .collect()
+    })
+}
+
+/// One record keyed by header, with typed cells.
+pub fn record_map(headers: &[String], record: &csv::StringRecord) -> HashMap<String, Value> {
+    headers
+        .iter()
+        .zip(record.iter())
+        .filter_map(|(h, cell)| cell_value(cell).map(|v| (h.clone(), v)))
+        .collect()
+}
+
+fn cell_value(cell: &str) -> Option<Value> {
+    if cell.is_empty() || MISSING_MARKERS.iter().any(|m| cell.eq_ignore_ascii_case(m)) {
+        return None;
+    }
+    if let Ok(int) = cell.parse::<i64>() {
+        return Some(Value::from(int));
+    }
+    match cell.parse::<f64>() {
+        Ok(num) => serde_json::Number::from_f64(num).map(Value::Number),
+        Err(_) => Some(Value::String(cell.to_string())),
+    }
+}
+
+/// Source format of a table, detected from its headers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceHeaders {
+    Acs2020(HashMap<String, &'static str>),
+    Hcsn(HashMap<String, &'static str>),
+    /// Headers in file order, see [`aggregate_nhanes_row`].
+    Nhanes(Vec<String>),
+    /// Canonical names or field aliases, resolved when rows are mapped.
+    Canonical,
+}
+
+impl SourceHeaders {
+    pub fn detect(headers: &[String]) -> Self {
+        if is_acs2020_sheet(headers) {
+            SourceHeaders::Acs2020(resolve_acs2020_headers(headers))
+        } else if is_hcsn_sheet(headers) {
+            SourceHeaders::Hcsn(resolve_hcsn_headers(headers))
+        } else if is_nhanes_sheet(headers) {
+            SourceHeaders::Nhanes(headers.to_vec())
+        } else {
+            SourceHeaders::Canonical
+        }
+    }
+
+    /// Units keyed by the columns [`Self::apply`] produces. Units of columns
+    /// that NHANES and HCSN rows aggregate are not carried over.
+    pub fn translate_units(&self, units: ColumnUnits) -> ColumnUnits {
+        match self {
+            SourceHeaders::Acs2020(map) => units
+                .into_iter()
+                .map(|(k, unit)| match map.get(&k) {
+                    Some(&canon) => (canon.to_string(), unit),
+                    None => (k, unit),
+                })
+                .collect(),
+            SourceHeaders::Hcsn(_) | SourceHeaders::Nhanes(_) => HashMap::new(),
+            SourceHeaders::Canonical => units,
+        }
+    }
+
+    /// Translates one row; canonical rows are borrowed as they are. Returns
+    /// how the NHANES recall days were combined, empty for other sources.
+    pub fn apply<'a>(
+        &self,
+        row: &'a HashMap<String, Value>,
+        recall_mode: RecallDays,
+    ) -> (Cow<'a, HashMap<String, Value>>, RecallSummary) {
+        match self {
+            SourceHeaders::Acs2020(map) => {
+                let row = row
+                    .iter()
+                    .map(|(k, v)| match map.get(k) {
+                        Some(&canon) => (canon.to_string(), v.clone()),
+                        None => (k.clone(), v.clone()),
+                    })
+                    .collect();
+                (Cow::Owned(row), RecallSummary::default())
+            }
+            SourceHeaders::Hcsn(map) => (
+                Cow::Owned(aggregate_hcsn_row(row, map)),
+                RecallSummary::default(),
+            ),
+            SourceHeaders::Nhanes(headers) => {
+                let (row, recall) = aggregate_nhanes_row(row, headers, recall_mode);
+                (Cow::Owned(row), recall)
+            }
+            SourceHeaders::Canonical => (Cow::Borrowed(row), RecallSummary::default()),
+        }
+    }
+}
+
+/// Scores one translated row with [`evaluate_allow_partial`], keeping the
+/// alias and unit trace of the mapping. `units` gives the units of columns
+/// that declare one; `recall` is how the row's recall days were combined;
+/// food columns are matched at `match_threshold`, and through
+/// `food_patterns` when one is given.
+pub fn score_record(
+    record: &HashMap<String, Value>,
+    units: &ColumnUnits,
+    recall: RecallSummary,
+    match_threshold: f64,
+    food_patterns: Option<&FoodPatternTable>,
+) -> ScoreResult {
+    let (nv, trace) =
+        NutritionVector::from_partial_map_with_units(record, units, match_threshold, food_patterns);
+    let mut result = evaluate_allow_partial(&nv);
+    result.trace.aliases_applied = trace.aliases_applied;
+    result.trace.conflicting_aliases =
+        [recall.conflicting_aliases, trace.conflicting_aliases].concat();
+    result.trace.translation_log = trace.translation_log;
+    result.trace.recall_days = recall.days;
+    result.trace.unit_conversions = trace.unit_conversions;
+    result.trace.unit_mismatches = trace.unit_mismatches;
+    result.trace.food_suggestions = trace.food_suggestions;
+    result.trace.unresolved_ingredients = trace.unresolved_ingredients;
+    result
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoredRow {
+    /// Position of the data row, from 0.
+    pub row: usize,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub id: BTreeMap<String, Value>,
+    /// Survey design columns of the input row, see [`crate::survey`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub design: BTreeMap<String, Value>,
+    #[serde(flatten)]
+    pub result: ScoreResult,
+}
+
+/// Reads a delimited table and scores every row with
+/// [`evaluate_allow_partial`] and with the cohort indices of
+/// [`crate::scores::batch`], as the CLI does, keeping the default identifier
+/// column. Use [`crate::stream::ScoreStream`] for tables too large to hold
+/// in memory.
+pub fn score_delimited(
+    text: &str,
+    delimiter: u8,
+    recall_mode: RecallDays,
+) -> Result<Vec<ScoredRow>, csv::Error> {
+    let options = StreamOptions {
+        recall_days: recall_mode,
+        ..StreamOptions::new(delimiter)
+    };
+    let cohort = ScoreStream::new(text.as_bytes(), &options)?.cohort_scores(None)?;
+    ScoreStream::new(text.as_bytes(), &options)?
+        .with_cohort_scores(cohort)
+        .collect()
+}
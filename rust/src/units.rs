@@ -0,0 +1,167 @@
+//! Measurement units of input values and their conversion to the canonical
+//! units of `schema/default_units.json`.
+//!
+//! A value's unit comes from, in order of precedence, a units header row or
+//! an explicit column-to-unit map, a unit suffix on the column name
+//! (`sodium_g`, `energy_kj`), or the FDC `unitName` of a nutrient. Values
+//! without a declared unit are taken to be in the canonical unit. Mass and
+//! energy units are converted. A value declared in a unit that cannot be
+//! converted to its mass or energy canonical unit (sodium in `cups`, energy
+//! in `g`) is reported and dropped rather than scored.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+
+static DEFAULT_UNITS_JSON: &str = include_str!("../../schema/default_units.json");
+
+static CANONICAL_UNITS: Lazy<HashMap<String, String>> =
+    Lazy::new(|| serde_json::from_str(DEFAULT_UNITS_JSON).expect("invalid default_units.json"));
+
+/// Declared units keyed by column name.
+pub type ColumnUnits = HashMap<String, String>;
+
+/// Canonical unit of `field`, as declared in `schema/default_units.json`.
+pub fn canonical_unit(field: &str) -> Option<&'static str> {
+    CANONICAL_UNITS.get(field).map(String::as_str)
+}
+
+/// Spellings accepted for each convertible unit, lower case.
+const UNIT_SPELLINGS: &[(&str, &[&str])] = &[
+    ("g", &["g", "gram", "grams", "gm"]),
+    ("mg", &["mg", "milligram", "milligrams"]),
+    ("mcg", &["mcg", "ug", "µg", "μg", "microgram", "micrograms"]),
+    ("kcal", &["kcal", "kilocalorie", "kilocalories", "calories"]),
+    ("kj", &["kj", "kilojoule", "kilojoules"]),
+];
+
+/// Grams per unit for mass, kilocalories per unit for energy.
+const UNIT_SCALE: &[(&str, &str, f64)] = &[
+    ("g", "mass", 1.0),
+    ("mg", "mass", 1e-3),
+    ("mcg", "mass", 1e-6),
+    ("kcal", "energy", 1.0),
+    ("kj", "energy", 1.0 / 4.184),
+];
+
+/// Column suffixes that declare a unit, checked longest first.
+const UNIT_SUFFIXES: &[(&str, &str)] = &[
+    ("_kcal", "kcal"),
+    ("_mcg", "mcg"),
+    ("_kj", "kj"),
+    ("_mg", "mg"),
+    ("_ug", "mcg"),
+    ("_g", "g"),
+];
+
+/// Standard spelling of a convertible unit, ignoring case and surrounding
+/// spaces. Other units (servings, cup equivalents, IU, ...) give `None`.
+pub fn normalize_unit(raw: &str) -> Option<&'static str> {
+    let lower = raw.trim().to_lowercase();
+    UNIT_SPELLINGS
+        .iter()
+        .find(|(_, spellings)| spellings.contains(&lower.as_str()))
+        .map(|(unit, _)| *unit)
+}
+
+/// Factor converting a value in `from` to `to`, when both are the same kind
+/// of unit.
+pub fn conversion_factor(from: &str, to: &str) -> Option<f64> {
+    let scale = |u: &str| UNIT_SCALE.iter().find(|(n, _, _)| *n == u);
+    match (scale(from), scale(to)) {
+        (Some((_, kind_a, a)), Some((_, kind_b, b))) if kind_a == kind_b => Some(a / b),
+        _ => None,
+    }
+}
+
+/// Splits a unit suffix off a column name: `sodium_g` gives
+/// `("sodium", "g")`.
+pub fn split_unit_suffix(column: &str) -> Option<(&str, &'static str)> {
+    let lower = column.to_ascii_lowercase();
+    UNIT_SUFFIXES.iter().find_map(|(suffix, unit)| {
+        (lower.ends_with(suffix) && lower.len() > suffix.len())
+            .then(|| (&column[..column.len() - suffix.len()], *unit))
+    })
+}
+
+/// Whether `cells` look like a units row under the header: every non-empty
+/// cell is text and at least one names a convertible unit.
+pub fn is_units_row<'a>(cells: impl IntoIterator<Item = &'a str>) -> bool {
+    let mut any_unit = false;
+    for cell in cells {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            continue;
+        }
+        if cell.parse::<f64>().is_ok() {
+            return false;
+        }
+        any_unit |= normalize_unit(cell).is_some();
+    }
+    any_unit
+}
+
+/// A value converted from its declared unit to the canonical one.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UnitConversion {
+    pub column: String,
+    pub field: &'static str,
+    pub from: &'static str,
+    pub to: &'static str,
+    pub factor: f64,
+}
+
+/// A value whose declared unit cannot be converted to the canonical one; the
+/// value is not used.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UnitMismatch {
+    pub column: String,
+    pub field: &'static str,
+    pub unit: String,
+    pub expected: &'static str,
+}
+
+/// Outcome of bringing one value to its field's canonical unit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Converted {
+    /// Already canonical, no unit declared, or neither unit a mass or energy.
+    Unchanged(f64),
+    Converted(f64, UnitConversion),
+    Mismatch(UnitMismatch),
+}
+
+/// Brings `value`, declared in `unit`, to the canonical unit of `field`.
+pub fn to_canonical(
+    column: &str,
+    field: &'static str,
+    value: f64,
+    unit: Option<&str>,
+) -> Converted {
+    let (Some(raw), Some(expected)) = (unit, canonical_unit(field)) else {
+        return Converted::Unchanged(value);
+    };
+    let declared = normalize_unit(raw);
+    // Labels such as `cups` for cup equivalents cannot be checked.
+    let unverifiable = declared.is_none() && normalize_unit(expected).is_none();
+    if declared == Some(expected) || raw.trim().eq_ignore_ascii_case(expected) || unverifiable {
+        return Converted::Unchanged(value);
+    }
+    match declared.and_then(|from| conversion_factor(from, expected).map(|f| (from, f))) {
+        Some((from, factor)) => Converted::Converted(
+            value * factor,
+            UnitConversion {
+                column: column.to_string(),
+                field,
+                from,
+                to: expected,
+                factor,
+            },
+        ),
+        None => Converted::Mismatch(UnitMismatch {
+            column: column.to_string(),
+            field,
+            unit: raw.trim().to_string(),
+            expected,
+        }),
+    }
+}
@@ -1,12 +1,19 @@
-use crate::acs2020_ingest::{is_acs2020_sheet, resolve_acs2020_headers};
-use crate::eval::evaluate_allow_partial;
-use crate::hcsn_ingest::{is_hcsn_sheet, resolve_hcsn_headers};
-use crate::nhanes_ingest::{is_nhanes_sheet, resolve_nhanes_headers};
+use crate::energy::EnergyAdjustment;
+use crate::eval::IndexError;
+use crate::food_matching::DEFAULT_MATCH_THRESHOLD;
+use crate::nhanes_ingest::RecallDays;
 use crate::nutrition_vector::{InputTrace, NutritionVector};
+use crate::scores::ComponentScore;
+use crate::stream::{ScoreStream, StreamOptions};
+use crate::tabular::ScoredRow;
 use crate::unmapped_monitor::UNMAPPED_MONITOR;
 use console_error_panic_hook;
+use serde::ser::{Error as _, SerializeSeq, SerializeStruct};
+use serde::{Serialize, Serializer};
 use serde_json;
 use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(start)]
@@ -16,148 +23,150 @@ pub fn init() {
 
 #[wasm_bindgen]
 pub fn score_json(json: &str) -> Result<JsValue, JsValue> {
-    let val: Value = serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
-    let data_val = match &val {
-        Value::Array(_) => val.clone(),
+    let mut val: Value =
+        serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    // Rows are moved out of the payload rather than copied.
+    let data_val = match &mut val {
+        Value::Array(rows) => Value::Array(std::mem::take(rows)),
         Value::Object(map) => map
-            .get("values")
-            .cloned()
+            .remove("values")
             .ok_or_else(|| JsValue::from_str("expected array or object with 'values'"))?,
         _ => return Err(JsValue::from_str("invalid json")),
     };
-    let arr_str = serde_json::to_string(&data_val).unwrap();
-    let mut records: Vec<std::collections::HashMap<String, Value>> =
-        serde_json::from_str(&arr_str).map_err(|e| JsValue::from_str(&e.to_string()))?;
-
-    if let Some(first) = records.first() {
-        let headers: Vec<String> = first.keys().cloned().collect();
-        if is_acs2020_sheet(&headers) {
-            let map = resolve_acs2020_headers(&headers);
-            for row in &mut records {
-                let mut new_row = std::collections::HashMap::new();
-                for (k, v) in row.iter() {
-                    if let Some(&canon) = map.get(k) {
-                        new_row.insert(canon.to_string(), v.clone());
-                    } else {
-                        new_row.insert(k.clone(), v.clone());
-                    }
-                }
-                *row = new_row;
-            }
-        } else if is_hcsn_sheet(&headers) {
-            let map = resolve_hcsn_headers(&headers);
-            for row in &mut records {
-                let mut new_row = std::collections::HashMap::new();
-                for (k, v) in row.iter() {
-                    if let Some(&canon) = map.get(k) {
-                        new_row.insert(canon.to_string(), v.clone());
-                    } else {
-                        new_row.insert(k.clone(), v.clone());
-                    }
-                }
-                *row = new_row;
-            }
-        } else if is_nhanes_sheet(&headers) {
-            let map = resolve_nhanes_headers(&headers);
-            for row in &mut records {
-                let mut new_row = std::collections::HashMap::new();
-                for (k, v) in row.iter() {
-                    if let Some(&canon) = map.get(k) {
-                        new_row.insert(canon.to_string(), v.clone());
-                    } else {
-                        new_row.insert(k.clone(), v.clone());
-                    }
-                }
-                *row = new_row;
-            }
-        }
-    }
-
-    #[derive(serde::Serialize)]
-    struct RowOutput {
-        scores: std::collections::BTreeMap<String, Option<f64>>,
-        validity: std::collections::BTreeMap<String, (bool, Option<String>)>,
-        trace: InputTrace,
-        errors: Vec<crate::eval::IndexError>,
-    }
-
-    let mut out: Vec<RowOutput> = Vec::new();
-    let mut score_counts: std::collections::HashMap<String, usize> =
-        std::collections::HashMap::new();
-    let mut missing_counts: std::collections::HashMap<&'static str, usize> =
-        std::collections::HashMap::new();
-    let mut alias_counts: std::collections::HashMap<String, usize> =
-        std::collections::HashMap::new();
+    // Optional run-level energy adjustment for cohort-relative indices.
+    let adjustment = match val.get("energy_adjustment").and_then(Value::as_str) {
+        Some(name) => Some(EnergyAdjustment::from_name(name).ok_or_else(|| {
+            JsValue::from_str(&format!("unknown energy_adjustment '{}'", name))
+        })?),
+        None => None,
+    };
+    let recall_mode = match val.get("recall_days").and_then(Value::as_str) {
+        Some(name) => RecallDays::from_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown recall_days '{}'", name)))?,
+        None => RecallDays::default(),
+    };
+    // Similarity a food column name needs to be matched to a food item.
+    let match_threshold = val
+        .get("match_threshold")
+        .and_then(Value::as_f64)
+        .unwrap_or(DEFAULT_MATCH_THRESHOLD);
+    // Identifier columns: a name or list of names, else the default aliases.
+    let designated: Vec<String> = match val.get("id_columns") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::String(name)) => vec![name.clone()],
+        Some(Value::Array(names)) => names
+            .iter()
+            .map(|n| {
+                n.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| JsValue::from_str("id_columns must be strings"))
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(JsValue::from_str("id_columns must be a string or array")),
+    };
+    let records: Vec<HashMap<String, Value>> =
+        serde_json::from_value(data_val).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    // Optional units of the input columns, e.g. {"sodium": "g"}.
+    let units: HashMap<String, String> = match val.get("units") {
+        Some(u) => serde_json::from_value(u.clone())
+            .map_err(|_| JsValue::from_str("units must map column names to unit strings"))?,
+        None => HashMap::new(),
+    };
 
-    for map in records {
-        let (nv, trace) = NutritionVector::from_partial_map(&map);
-        let mut result = evaluate_allow_partial(&nv);
-        result.trace.aliases_applied = trace.aliases_applied.clone();
-        result.trace.conflicting_aliases = trace.conflicting_aliases.clone();
-        result.trace.translation_log = trace.translation_log.clone();
+    let mut headers: Vec<String> = records.iter().flat_map(|r| r.keys().cloned()).collect();
+    headers.sort();
+    headers.dedup();
+    let options = StreamOptions {
+        recall_days: recall_mode,
+        id_columns: designated,
+        match_threshold,
+        ..Default::default()
+    };
+    // The rows are scored as the CLI streams a table: a first pass for the
+    // cohort indices, which keeps only the fields they read, then the
+    // per-record scorers, serialized row by row.
+    let cohort =
+        ScoreStream::from_records(records.iter().map(Ok), &headers, units.clone(), &options)
+            .cohort_scores(adjustment)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let stream = ScoreStream::from_records(records.into_iter().map(Ok), &headers, units, &options)
+        .with_cohort_scores(cohort);
+    let output = Output(RefCell::new(stream));
+    serde_wasm_bindgen::to_value(&output).map_err(|e| JsValue::from_str(&e.to_string()))
+}
 
-        for field in &result.trace.missing_fields {
-            *missing_counts.entry(*field).or_insert(0) += 1;
-        }
-        for (alias, _) in &result.trace.aliases_applied {
-            *alias_counts.entry(alias.clone()).or_insert(0) += 1;
-        }
+#[derive(Serialize)]
+struct RowOutput {
+    /// Identifier columns of the input row, verbatim.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    id: BTreeMap<String, Value>,
+    /// Survey design columns of the input row.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    design: BTreeMap<String, Value>,
+    scores: BTreeMap<String, Option<f64>>,
+    validity: BTreeMap<String, (bool, Option<String>)>,
+    components: BTreeMap<String, Vec<ComponentScore>>,
+    trace: InputTrace,
+    errors: Vec<IndexError>,
+}
 
-        let mut scores_map = std::collections::BTreeMap::new();
-        let mut validity_map = std::collections::BTreeMap::new();
-        for name in result.ordered_names {
-            if let Some(info) = result.scores.get(&name) {
-                if info.value.is_some() {
-                    *score_counts.entry(name.clone()).or_insert(0) += 1;
+impl From<ScoredRow> for RowOutput {
+    fn from(mut row: ScoredRow) -> Self {
+        let mut scores = BTreeMap::new();
+        let mut validity = BTreeMap::new();
+        let mut components = BTreeMap::new();
+        for name in row.result.ordered_names {
+            if let Some(info) = row.result.scores.remove(&name) {
+                scores.insert(name.clone(), info.value);
+                validity.insert(name.clone(), (info.valid, info.explanation));
+                if !info.components.is_empty() {
+                    components.insert(name, info.components);
                 }
-                scores_map.insert(name.clone(), info.value);
-                validity_map.insert(name.clone(), (info.valid, info.explanation.clone()));
             }
         }
-
-        out.push(RowOutput {
-            scores: scores_map,
-            validity: validity_map,
-            trace: result.trace,
-            errors: result.errors,
-        });
-    }
-
-    let total_rows = out.len() as f64;
-    #[derive(serde::Serialize)]
-    struct Coverage {
-        percent_scored: std::collections::BTreeMap<String, f64>,
-        most_missing: Vec<(String, usize)>,
-        alias_hits: std::collections::BTreeMap<String, usize>,
+        RowOutput {
+            id: row.id,
+            design: row.design,
+            scores,
+            validity,
+            components,
+            trace: row.result.trace,
+            errors: row.result.errors,
+        }
     }
+}
 
-    let mut pct = std::collections::BTreeMap::new();
-    for (idx, count) in &score_counts {
-        pct.insert(idx.clone(), (*count as f64) * 100.0 / total_rows.max(1.0));
+/// `{"rows": [...], "coverage": {...}}` of a stream, each row serialized as
+/// it is scored; the coverage follows once every row was read.
+struct Output<I>(RefCell<ScoreStream<I>>);
+
+/// The rows of an [`Output`].
+struct Rows<'a, I>(&'a RefCell<ScoreStream<I>>);
+
+impl<I> Serialize for Rows<'_, I>
+where
+    I: Iterator<Item = Result<HashMap<String, Value>, csv::Error>>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for row in self.0.borrow_mut().by_ref() {
+            let row = row.map_err(S::Error::custom)?;
+            seq.serialize_element(&RowOutput::from(row))?;
+        }
+        seq.end()
     }
-    let mut miss_vec: Vec<(String, usize)> = missing_counts
-        .into_iter()
-        .map(|(f, c)| (f.to_string(), c))
-        .collect();
-    miss_vec.sort_by(|a, b| b.1.cmp(&a.1));
-    let alias_vec: std::collections::BTreeMap<String, usize> = alias_counts.into_iter().collect();
+}
 
-    #[derive(serde::Serialize)]
-    struct Output {
-        rows: Vec<RowOutput>,
-        coverage: Coverage,
+impl<I> Serialize for Output<I>
+where
+    I: Iterator<Item = Result<HashMap<String, Value>, csv::Error>>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut out = serializer.serialize_struct("Output", 2)?;
+        out.serialize_field("rows", &Rows(&self.0))?;
+        out.serialize_field("coverage", &self.0.borrow().coverage())?;
+        out.end()
     }
-
-    let coverage = Coverage {
-        percent_scored: pct,
-        most_missing: miss_vec,
-        alias_hits: alias_vec,
-    };
-    let result = Output {
-        rows: out,
-        coverage,
-    };
-    serde_wasm_bindgen::to_value(&result).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
 #[wasm_bindgen]
@@ -167,7 +176,7 @@ pub fn required_fields() -> JsValue {
 
 #[wasm_bindgen]
 pub fn missing_fields(json: &str) -> Result<JsValue, JsValue> {
-    let map: std::collections::HashMap<String, Value> =
+    let map: HashMap<String, Value> =
         serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
     let (nv, _) = NutritionVector::from_partial_map(&map);
     let missing = nv.missing_fields();
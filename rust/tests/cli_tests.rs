@@ -0,0 +1,119 @@
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn write_input(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dietarycodex_cli_{}", name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_dietarycodex"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn rows(output: &Output) -> Vec<Value> {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice::<Value>(&output.stdout)
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+fn dash(row: &Value) -> &Value {
+    &row["scores"]["DASH"]
+}
+
+// Same sodium density: raw intakes rank the smaller eater better.
+const DASH_ROWS: &str = "\
+energy,sodium,sex,fruit_serv,veg_serv,nuts_legumes_serv,whole_grains,low_fat_dairy_serv,red_processed_meat_serv,ssb_serv
+1500,2250,2,2,3,1,50,1,0.5,0
+2500,3750,2,2,3,1,50,1,0.5,0
+";
+
+#[test]
+fn energy_adjustment_flag_reaches_the_cohort_pass() {
+    let input = write_input("energy.csv", DASH_ROWS);
+    let input = input.to_str().unwrap();
+
+    let raw = rows(&run(&[input]));
+    assert!(dash(&raw[0])["value"].as_f64() > dash(&raw[1])["value"].as_f64());
+    assert_eq!(dash(&raw[0])["explanation"], Value::Null);
+
+    let adjusted = rows(&run(&[input, "--energy-adjustment", "residual"]));
+    assert_eq!(dash(&adjusted[0])["value"], dash(&adjusted[1])["value"]);
+    assert_eq!(
+        dash(&adjusted[0])["explanation"],
+        "energy adjustment: residual"
+    );
+
+    let density = rows(&run(&[input, "--energy-adjustment", "density"]));
+    assert_eq!(
+        dash(&density[0])["explanation"],
+        "energy adjustment: nutrient_density"
+    );
+}
+
+#[test]
+fn unknown_energy_adjustment_is_rejected() {
+    let input = write_input("energy_unknown.csv", DASH_ROWS);
+    let output = run(&[input.to_str().unwrap(), "--energy-adjustment", "per_kcal"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown energy adjustment"));
+}
+
+const FDC_FOOD: &str = r#"{"fdcId": 1, "description": "test food", "foodNutrients": [
+    {"nutrient": {"number": "208", "name": "Energy", "unitName": "kcal"}, "amount": 52.0},
+    {"nutrient": {"number": "307", "name": "Sodium, Na", "unitName": "mg"}, "amount": 1.0}
+]}"#;
+
+#[test]
+fn allow_partial_is_rejected_for_tables() {
+    let input = write_input("partial.csv", DASH_ROWS);
+    let output = run(&[input.to_str().unwrap(), "--allow-partial"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-partial applies to FDC JSON"));
+}
+
+#[test]
+fn fdc_tables_are_strict_without_allow_partial() {
+    let input = write_input("food.json", FDC_FOOD);
+    let input = input.to_str().unwrap();
+
+    let strict = run(&[input, "--format", "csv"]);
+    assert!(!strict.status.success());
+    assert!(String::from_utf8_lossy(&strict.stderr).contains("missing fields:"));
+
+    let partial = run(&[input, "--format", "csv", "--allow-partial"]);
+    assert!(partial.status.success());
+    assert!(String::from_utf8_lossy(&partial.stdout).starts_with("row,"));
+
+    // JSON output reports the missing fields instead.
+    let json: Value = serde_json::from_slice(&run(&[input]).stdout).unwrap();
+    let missing = json["missing_canonical_fields"].as_array().unwrap();
+    assert!(missing.contains(&Value::from("fiber")));
+    assert!(!missing.contains(&Value::from("sodium")));
+}
+
+#[test]
+fn coverage_report_flags_duplicate_ids() {
+    let input = write_input("duplicates.csv", "id,energy\nA,2000\nB,1800\nA,2100\n");
+    let report = std::env::temp_dir().join("dietarycodex_cli_duplicates_coverage.json");
+    let output = run(&[
+        input.to_str().unwrap(),
+        "--coverage",
+        report.to_str().unwrap(),
+    ]);
+    rows(&output);
+    let coverage: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(coverage["duplicate_ids"]["A"], serde_json::json!([0, 2]));
+    assert_eq!(coverage["missing_ids"], 0);
+}
@@ -0,0 +1,247 @@
+use dietarycodex::energy::{adjust, adjust_batch, intake_fields, EnergyAdjustment};
+use dietarycodex::eval::evaluate_batch_with_adjustment;
+use dietarycodex::nutrition_vector::NutritionVector;
+use dietarycodex::scores::hei::Hei2020Scorer;
+use dietarycodex::scores::DietScore;
+
+fn record(energy: f64, sodium: f64) -> NutritionVector {
+    NutritionVector {
+        energy: Some(energy),
+        sodium: Some(sodium),
+        fat: Some(80.0),
+        vegetables: Some(200.0),
+        sex: Some(2.0),
+        age: Some(40.0),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn intake_fields_exclude_energy_and_demographics() {
+    let fields = intake_fields();
+    assert!(fields.contains(&"sodium"));
+    assert!(fields.contains(&"veg_serv"));
+    for f in ["energy", "sex", "age", "olive_oil_primary", "veg_variety"] {
+        assert!(!fields.contains(&f), "{} adjusted", f);
+    }
+}
+
+#[test]
+fn per_thousand_kcal_divides_every_intake() {
+    let adjusted = adjust(&record(2000.0, 3000.0), EnergyAdjustment::PerThousandKcal);
+    assert_eq!(adjusted.sodium, Some(1500.0));
+    assert_eq!(adjusted.vegetables, Some(100.0));
+    assert_eq!(adjusted.energy, Some(2000.0));
+    assert_eq!(adjusted.sex, Some(2.0));
+    assert_eq!(adjusted.fiber, None);
+}
+
+#[test]
+fn nutrient_density_expresses_macronutrients_as_percent_energy() {
+    let adjusted = adjust(&record(2000.0, 3000.0), EnergyAdjustment::NutrientDensity);
+    assert_eq!(adjusted.fat, Some(36.0));
+    assert_eq!(adjusted.sodium, Some(3000.0));
+}
+
+#[test]
+fn records_without_energy_are_left_unchanged() {
+    let mut nv = record(2000.0, 3000.0);
+    nv.energy = None;
+    let adjusted = adjust(&nv, EnergyAdjustment::PerThousandKcal);
+    assert_eq!(adjusted.sodium, Some(3000.0));
+}
+
+#[test]
+fn residual_method_removes_dependence_on_energy() {
+    // Sodium rises 1.5 mg per kcal; the middle record eats 100 mg more than
+    // its energy predicts.
+    let batch = vec![
+        record(1500.0, 2250.0),
+        record(2000.0, 3100.0),
+        record(2500.0, 3750.0),
+    ];
+    let adjusted = adjust_batch(&batch, EnergyAdjustment::Residual);
+    let sodium: Vec<f64> = adjusted.iter().map(|nv| nv.sodium.unwrap()).collect();
+    let mean = sodium.iter().sum::<f64>() / 3.0;
+    assert!((mean - 9100.0 / 3.0).abs() < 1e-9);
+    assert!((sodium[0] - sodium[2]).abs() < 1e-9);
+    assert!((sodium[1] - sodium[0] - 100.0).abs() < 1e-9);
+    // Fat does not vary, so it is kept as reported.
+    assert!(adjusted.iter().all(|nv| nv.fat == Some(80.0)));
+}
+
+#[test]
+fn residual_method_needs_spread_in_energy() {
+    let batch = vec![record(2000.0, 2000.0), record(2000.0, 4000.0)];
+    let adjusted = adjust_batch(&batch, EnergyAdjustment::Residual);
+    assert_eq!(adjusted[0].sodium, Some(2000.0));
+    assert_eq!(adjusted[1].sodium, Some(4000.0));
+}
+
+#[test]
+fn method_names_round_trip() {
+    for method in [
+        EnergyAdjustment::None,
+        EnergyAdjustment::NutrientDensity,
+        EnergyAdjustment::PerThousandKcal,
+        EnergyAdjustment::Residual,
+    ] {
+        assert_eq!(EnergyAdjustment::from_name(method.name()), Some(method));
+    }
+    assert_eq!(
+        EnergyAdjustment::from_name("density"),
+        Some(EnergyAdjustment::NutrientDensity)
+    );
+    assert_eq!(EnergyAdjustment::from_name("per_reference_kcal"), None);
+    assert_eq!(EnergyAdjustment::from_name("per_kcal"), None);
+}
+
+#[test]
+fn per_reference_kcal_rescales_to_the_reference_diet() {
+    let adjusted = adjust(
+        &record(2000.0, 3000.0),
+        EnergyAdjustment::PerReferenceKcal(2500),
+    );
+    assert_eq!(adjusted.sodium, Some(3750.0));
+    assert_eq!(adjusted.energy, Some(2000.0));
+}
+
+fn dash_record(energy: f64, sodium: f64) -> NutritionVector {
+    NutritionVector {
+        fruit_serv: Some(2.0),
+        veg_serv: Some(3.0),
+        nuts_legumes_serv: Some(1.0),
+        whole_grains: Some(50.0),
+        low_fat_dairy_serv: Some(1.0),
+        red_processed_meat_serv: Some(0.5),
+        ssb_serv: Some(0.0),
+        ..record(energy, sodium)
+    }
+}
+
+#[test]
+fn hei_declares_density_standards() {
+    assert_eq!(
+        Hei2020Scorer.energy_adjustment(),
+        EnergyAdjustment::PerThousandKcal
+    );
+}
+
+#[test]
+fn run_adjustment_applies_to_batch_scorers() {
+    // Same sodium density: raw intakes rank the smaller eater better, the
+    // residual method ranks both alike.
+    let batch = vec![dash_record(1500.0, 2250.0), dash_record(2500.0, 3750.0)];
+    let raw = evaluate_batch_with_adjustment(&batch, None);
+    assert!(raw[0].scores["DASH"].value > raw[1].scores["DASH"].value);
+    assert_eq!(raw[0].scores["DASH"].explanation, None);
+
+    let adjusted = evaluate_batch_with_adjustment(&batch, Some(EnergyAdjustment::Residual));
+    assert_eq!(
+        adjusted[0].scores["DASH"].value,
+        adjusted[1].scores["DASH"].value
+    );
+    assert_eq!(
+        adjusted[0].scores["DASH"].explanation.as_deref(),
+        Some("energy adjustment: residual")
+    );
+    // Per-record scorers keep scoring reported intakes.
+    assert_eq!(
+        raw[0].scores["DASH_LINEAR"].value,
+        adjusted[0].scores["DASH_LINEAR"].value
+    );
+}
+
+#[test]
+fn adjusted_batches_leave_out_records_without_energy() {
+    let mut no_energy = dash_record(0.0, 3000.0);
+    no_energy.energy = None;
+    let batch = vec![
+        dash_record(1500.0, 2250.0),
+        dash_record(2500.0, 3750.0),
+        dash_record(0.0, 3000.0),
+        no_energy,
+    ];
+    let per_kcal = Some(EnergyAdjustment::PerThousandKcal);
+    let results = evaluate_batch_with_adjustment(&batch, per_kcal);
+    assert_eq!(results[2].scores["DASH"].value, None);
+    assert_eq!(
+        results[2].scores["DASH"].explanation.as_deref(),
+        Some("not applicable: energy must be positive")
+    );
+    assert_eq!(
+        results[3].scores["DASH"].explanation.as_deref(),
+        Some("missing fields: energy")
+    );
+    assert!(results[3]
+        .errors
+        .iter()
+        .any(|e| e.index == "DASH" && e.missing_fields == vec!["energy"]));
+    // The other records rank as if the batch held only them.
+    let alone = evaluate_batch_with_adjustment(&batch[..2], per_kcal);
+    for i in 0..2 {
+        assert_eq!(results[i].scores["DASH"], alone[i].scores["DASH"]);
+    }
+
+    // Without an adjustment every record is ranked as reported.
+    let raw = evaluate_batch_with_adjustment(&batch, None);
+    assert!(raw.iter().all(|r| r.scores["DASH"].value.is_some()));
+}
+
+#[test]
+fn metadata_reports_declared_adjustments() {
+    use dietarycodex::scores::registry::all_score_metadata;
+
+    let metas = all_score_metadata();
+    let adjustment = |name: &str| {
+        metas
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.energy_adjustment)
+    };
+    assert_eq!(adjustment("HEI_2020"), Some(EnergyAdjustment::PerThousandKcal));
+    assert_eq!(adjustment("DASH_LINEAR"), Some(EnergyAdjustment::None));
+    assert_eq!(adjustment("DASH"), Some(EnergyAdjustment::None));
+    assert_eq!(
+        adjustment("PHDI_ENERGY_ADJUSTED"),
+        Some(EnergyAdjustment::PerReferenceKcal(2500))
+    );
+}
+
+fn acs_record(energy: f64, hpf_rg_serv: f64) -> NutritionVector {
+    NutritionVector {
+        energy: Some(energy),
+        hpf_rg_serv: Some(hpf_rg_serv),
+        sex: Some(2.0),
+        veg_serv: Some(0.0),
+        veg_variety: Some(0.0),
+        fruit_serv: Some(0.0),
+        fruit_variety: Some(0.0),
+        whole_grains: Some(0.0),
+        red_processed_meat_serv: Some(0.0),
+        ssb_serv: Some(0.0),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn acs2020_v2_is_not_put_per_1000_kcal_twice() {
+    // Per 1,000 kcal the processed foods rank 4 < 2 < 1 < 3; divided by
+    // energy twice they would rank 4 < 3 < 2 < 1.
+    let batch = vec![
+        acs_record(1000.0, 2.0),
+        acs_record(2000.0, 3.0),
+        acs_record(4000.0, 10.0),
+        acs_record(3000.0, 3.0),
+    ];
+    let values = |adjustment| -> Vec<Option<f64>> {
+        evaluate_batch_with_adjustment(&batch, adjustment)
+            .iter()
+            .map(|r| r.scores["ACS2020_V2"].value)
+            .collect()
+    };
+    assert_eq!(
+        values(Some(EnergyAdjustment::PerThousandKcal)),
+        values(None)
+    );
+}
@@ -0,0 +1,185 @@
+use dietarycodex::fdc_ingest::{food_group, read_fdc_intake};
+use dietarycodex::nutrition_vector::NutritionVector;
+use serde_json::{json, Value};
+
+// Nutrients in the full format: (number, name, unit, amount per 100 g).
+fn full_food(category: Value, nutrients: &[(&str, &str, &str, f64)]) -> Value {
+    let items: Vec<Value> = nutrients
+        .iter()
+        .map(|(number, name, unit, amount)| {
+            json!({
+                "nutrient": {"number": number, "name": name, "unitName": unit},
+                "amount": amount
+            })
+        })
+        .collect();
+    let mut food = json!({"fdcId": 1, "description": "test food", "foodNutrients": items});
+    food.as_object_mut()
+        .unwrap()
+        .extend(category.as_object().unwrap().clone());
+    food
+}
+
+fn apple() -> Value {
+    let mut food = full_food(
+        json!({"dataType": "Foundation", "foodCategory": {"description": "Fruits and Fruit Juices"}}),
+        &[
+            ("958", "Energy (Atwater Specific Factors)", "kcal", 52.0),
+            ("957", "Energy (Atwater General Factors)", "kcal", 60.0),
+            ("291", "Fiber, total dietary", "g", 2.4),
+            ("307", "Sodium, Na", "mg", 1.0),
+            ("317", "Selenium, Se", "µg", 0.5),
+        ],
+    );
+    food["foodPortions"] = json!([
+        {"sequenceNumber": 2, "amount": 1.0, "measureUnit": {"name": "cup"}, "modifier": "sliced", "gramWeight": 110.0},
+        {"sequenceNumber": 1, "amount": 1.0, "measureUnit": {"name": "undetermined"}, "modifier": "medium", "gramWeight": 182.0}
+    ]);
+    food
+}
+
+#[test]
+fn nutrients_are_matched_by_number() {
+    let (nv, trace) = NutritionVector::from_fdc_json_traced(&apple().to_string()).unwrap();
+    // Atwater specific energy is preferred to general, units pass through.
+    assert_eq!(nv.energy, Some(52.0));
+    assert_eq!(nv.fiber, Some(2.4));
+    assert_eq!(nv.sodium, Some(1.0));
+    assert_eq!(nv.selenium, Some(0.5));
+    assert_eq!(nv.total_fruits, Some(100.0));
+    assert!(trace.unit_conversions.is_empty());
+}
+
+#[test]
+fn abridged_and_search_formats_are_read() {
+    let abridged = json!({
+        "dataType": "SR Legacy",
+        "foodNutrients": [
+            {"number": "208", "name": "Energy", "amount": 20.0, "unitName": "KCAL"},
+            {"number": "307", "name": "Sodium, Na", "amount": 0.005, "unitName": "G"}
+        ]
+    });
+    let search = json!({
+        "dataType": "Branded",
+        "foodNutrients": [
+            {"nutrientNumber": "208", "nutrientName": "Energy", "unitName": "KCAL", "value": 20.0},
+            {"nutrientNumber": "307", "nutrientName": "Sodium, Na", "unitName": "MG", "value": 5.0}
+        ]
+    });
+    for food in [abridged, search] {
+        let intake = read_fdc_intake(&food).unwrap();
+        assert_eq!(intake.fields["energy"], 20.0);
+        assert!((intake.fields["sodium"] - 5.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn consumed_foods_are_summed_into_one_day() {
+    let broccoli = full_food(
+        json!({"dataType": "Survey (FNDDS)", "wweiaFoodCategory": {"wweiaFoodCategoryCode": 6407}}),
+        &[
+            ("208", "Energy", "kcal", 35.0),
+            ("307", "Sodium, Na", "mg", 40.0),
+        ],
+    );
+    let mut crackers = full_food(
+        json!({"dataType": "Branded", "brandedFoodCategory": "Crackers & Biscotti"}),
+        &[
+            ("208", "Energy", "kcal", 400.0),
+            ("307", "Sodium, Na", "mg", 800.0),
+        ],
+    );
+    crackers["servingSize"] = json!(30.0);
+    crackers["servingSizeUnit"] = json!("g");
+    let input = json!({"foods": [
+        {"food": apple(), "portion": "medium"},
+        {"food": apple(), "portion": "CUP", "quantity": 0.5},
+        {"food": broccoli, "grams": 200},
+        {"food": crackers, "servings": 2}
+    ]});
+    let intake = read_fdc_intake(&input).unwrap();
+    assert_eq!(intake.foods, 4);
+    let apple_grams = 182.0 + 55.0;
+    assert!((intake.fields["total_fruits"] - apple_grams).abs() < 1e-9);
+    assert!((intake.fields["vegetables"] - 200.0).abs() < 1e-9);
+    let energy = 52.0 * apple_grams / 100.0 + 35.0 * 2.0 + 400.0 * 0.6;
+    assert!((intake.fields["energy"] - energy).abs() < 1e-9);
+    let sodium = apple_grams / 100.0 + 80.0 + 480.0;
+    assert!((intake.fields["sodium"] - sodium).abs() < 1e-9);
+
+    let nv = NutritionVector::from_fdc_json(&input["foods"].to_string()).unwrap();
+    assert!((nv.energy.unwrap() - energy).abs() < 1e-9);
+}
+
+#[test]
+fn consumed_foods_need_a_known_amount() {
+    let missing = json!([{"food": apple()}]);
+    let err = read_fdc_intake(&missing).unwrap_err().to_string();
+    assert!(err.contains("no consumed amount"), "{}", err);
+    let unknown = json!([{"food": apple(), "portion": "wedge"}]);
+    let err = read_fdc_intake(&unknown).unwrap_err().to_string();
+    assert!(err.contains("no portion matching 'wedge'"), "{}", err);
+    assert!(read_fdc_intake(&json!({"rows": []})).is_err());
+}
+
+#[test]
+fn servings_by_volume_are_not_taken_as_grams() {
+    let mut juice = full_food(
+        json!({"dataType": "Branded", "brandedFoodCategory": "Juice"}),
+        &[("208", "Energy", "kcal", 45.0)],
+    );
+    juice["servingSize"] = json!(240.0);
+    juice["servingSizeUnit"] = json!("ml");
+    juice["householdServingFullText"] = json!("1 cup");
+    let err = read_fdc_intake(&json!([{"food": juice.clone(), "servings": 1}]))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("serving size is 240 ml"), "{}", err);
+    let err = read_fdc_intake(&json!([{"food": juice.clone(), "portion": "cup"}]))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("no portion matching 'cup'"), "{}", err);
+
+    juice["servingSizeUnit"] = json!("GRM");
+    let intake = read_fdc_intake(&json!([{"food": juice, "servings": 1}])).unwrap();
+    assert!((intake.fields["energy"] - 45.0 * 2.4).abs() < 1e-9);
+}
+
+#[test]
+fn categories_map_to_food_groups() {
+    let group = |category: Value| food_group(&category);
+    assert_eq!(
+        group(json!({"foodCategory": {"description": "Vegetables and Vegetable Products"}})),
+        Some("vegetables")
+    );
+    assert_eq!(
+        group(json!({"foodCategory": "Beef Products"})),
+        Some("red_meat")
+    );
+    assert_eq!(
+        group(json!({"brandedFoodCategory": "Canned Fruit"})),
+        Some("total_fruits")
+    );
+    assert_eq!(
+        group(json!({"wweiaFoodCategory": {"wweiaFoodCategoryCode": 2402}})),
+        Some("fish")
+    );
+    // White potatoes are not counted as vegetables.
+    assert_eq!(
+        group(json!({"wweiaFoodCategory": {"wweiaFoodCategoryCode": 6802}})),
+        None
+    );
+    // Fruit juice counts as fruit, vegetable juice does not.
+    assert_eq!(
+        group(json!({"wweiaFoodCategory": {"wweiaFoodCategoryCode": 7004}})),
+        Some("total_fruits")
+    );
+    assert_eq!(
+        group(json!({"wweiaFoodCategory": {"wweiaFoodCategoryCode": 7008}})),
+        None
+    );
+    assert_eq!(
+        group(json!({"foodCategory": {"description": "Baked Products"}})),
+        None
+    );
+}
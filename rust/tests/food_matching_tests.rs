@@ -0,0 +1,114 @@
+use dietarycodex::food_item_resolver::FoodItemResolver;
+use dietarycodex::food_matching::{
+    similarity, split_food_key, tokenize, AmountUnit, DEFAULT_MATCH_THRESHOLD,
+};
+use dietarycodex::nutrition_vector::NutritionVector;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const ITEMS: &str = r#"{
+  "beef": {"components": {"red_meat": 100.0}, "synonyms": ["ground beef", "minced beef"]},
+  "broccoli, raw": {"components": {"vegetables": 100.0}}
+}"#;
+
+fn tokens(name: &str) -> Vec<String> {
+    tokenize(name)
+}
+
+#[test]
+fn food_columns_are_tokenised() {
+    assert_eq!(tokens("Beef (g)"), ["beef", "g"]);
+    assert_eq!(
+        split_food_key("beef_grams"),
+        Some((tokens("beef"), AmountUnit::Grams))
+    );
+    assert_eq!(
+        split_food_key("Ground Beef (g)"),
+        Some((tokens("ground beef"), AmountUnit::Grams))
+    );
+    assert_eq!(
+        split_food_key("beef servings"),
+        Some((tokens("beef"), AmountUnit::Servings))
+    );
+    assert_eq!(split_food_key("SEQN"), None);
+    assert_eq!(split_food_key("_g"), None);
+}
+
+#[test]
+fn similarity_ranks_overlap_and_typos() {
+    assert_eq!(similarity(&tokens("beef"), &tokens("beef")), 1.0);
+    let typo = similarity(&tokens("brocoli raw"), &tokens("broccoli raw"));
+    assert!(typo > 0.85 && typo < 1.0, "{}", typo);
+    let partial = similarity(&tokens("beef liver"), &tokens("beef"));
+    assert!((partial - 0.5).abs() < 1e-9, "{}", partial);
+    assert!(similarity(&tokens("orange juice"), &tokens("beef")) < 0.4);
+}
+
+#[test]
+fn names_synonyms_and_close_matches_resolve() {
+    let resolver = FoodItemResolver::from_json(ITEMS).unwrap();
+    for column in ["beef_g", "beef_grams", "Beef (g)", "BEEF gm"] {
+        let traced = resolver
+            .resolve_traced(column, 50.0, DEFAULT_MATCH_THRESHOLD, None)
+            .unwrap()
+            .contributions;
+        assert_eq!(traced[0].value, 50.0);
+        assert_eq!(traced[0].path, [column]);
+    }
+    // A synonym is applied, with the item it named in the path.
+    let traced = resolver
+        .resolve_traced("ground_beef_g", 80.0, DEFAULT_MATCH_THRESHOLD, None)
+        .unwrap()
+        .contributions;
+    assert_eq!(traced[0].path, ["ground_beef_g", "beef"]);
+    let traced = resolver
+        .resolve_traced("Brocoli raw (g)", 100.0, DEFAULT_MATCH_THRESHOLD, None)
+        .unwrap()
+        .contributions;
+    assert_eq!(traced[0].field, "vegetables");
+    assert_eq!(traced[0].path, ["Brocoli raw (g)", "broccoli, raw"]);
+}
+
+#[test]
+fn weak_matches_are_suggested_not_applied() {
+    let resolver = FoodItemResolver::from_json(ITEMS).unwrap();
+    let threshold = DEFAULT_MATCH_THRESHOLD;
+    assert!(resolver
+        .resolve_traced("beef_liver_g", 100.0, threshold, None)
+        .is_none());
+    let suggestion = resolver.suggest("beef_liver_g", threshold, None).unwrap();
+    assert_eq!(suggestion.item, "beef");
+    assert!((suggestion.score - 0.5).abs() < 1e-9);
+    assert!(resolver
+        .suggest("orange_juice_g", threshold, None)
+        .is_none());
+    assert!(resolver.suggest("beef_g", threshold, None).is_none());
+
+    // A lower threshold applies the match, for that call only.
+    assert!(resolver
+        .resolve_traced("beeef_g", 100.0, 0.75, None)
+        .is_some());
+    assert!(resolver
+        .resolve_traced("beeef_g", 100.0, threshold, None)
+        .is_none());
+    assert!(resolver.suggest("beeef_g", 0.75, None).is_none());
+}
+
+#[test]
+fn suggestions_are_reported_in_the_trace() {
+    let row: HashMap<String, Value> = [
+        ("Minced Beef (g)".to_string(), Value::from(100.0)),
+        ("beef_liver_g".to_string(), Value::from(60.0)),
+    ]
+    .into_iter()
+    .collect();
+    let (nv, trace) = NutritionVector::from_partial_map(&row);
+    assert_eq!(nv.red_meat, Some(100.0));
+    assert_eq!(
+        trace.translation_log["red_meat"].source,
+        ["Minced Beef (g) > beef"]
+    );
+    assert_eq!(trace.food_suggestions.len(), 1);
+    assert_eq!(trace.food_suggestions[0].column, "beef_liver_g");
+    assert_eq!(trace.food_suggestions[0].item, "beef");
+}
@@ -0,0 +1,105 @@
+use dietarycodex::food_matching::DEFAULT_MATCH_THRESHOLD;
+use dietarycodex::food_patterns::FoodPatternTable;
+use dietarycodex::nutrition_vector::NutritionVector;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const FPED: &str = "\
+Food code,Main food description,F_TOTAL (cup eq.),F_CITMLB (cup eq.),F_OTHER (cup eq.),F_JUICE (cup eq.),V_TOTAL (cup eq.),V_DRKGR (cup eq.),V_LEGUMES (cup eq.),G_WHOLE (oz. eq.),PF_MEAT (oz. eq.),PF_LEGUMES (oz. eq.),fiber
+63101000,\"Apple, raw\",0.8,0,0.8,0,0,0,0,0,0,0,2.4
+61210000,Orange juice,0.8,0,0,0.8,0,0,0,0,0,0,0.2
+41101000,\"Beans, dry, cooked\",0,0,0,0,0.5,0,0.5,0,0,2.0,6.0
+27311000,Beef and vegetable stew,0,0,0,0,0.3,0.1,0,0,1.2,0,1.0
+";
+
+fn table() -> FoodPatternTable {
+    FoodPatternTable::from_reader(FPED.as_bytes(), b',').unwrap()
+}
+
+#[test]
+fn fped_columns_fill_food_group_fields() {
+    let table = table();
+    assert_eq!(table.len(), 4);
+    let apple = table.get("63101000").unwrap();
+    assert_eq!(apple.description.as_deref(), Some("Apple, raw"));
+    assert_eq!(apple.per_100g["f_total"], 0.8);
+    // A cup equivalent of fruit is two 80 g portions.
+    assert!((apple.per_100g["total_fruits"] - 128.0).abs() < 1e-9);
+    assert_eq!(apple.per_100g["f_whole"], 0.8);
+    assert_eq!(apple.per_100g["fiber"], 2.4);
+    // Juice counts towards total but not whole fruit.
+    let juice = table.get("orange JUICE").unwrap();
+    assert_eq!(juice.per_100g["f_whole"], 0.0);
+    let beans = table.get("41101000").unwrap();
+    assert_eq!(beans.per_100g["pf_total"], 2.0);
+    assert_eq!(beans.per_100g["pf_seaplant"], 2.0);
+    assert_eq!(beans.per_100g["v_total"], 1.0);
+    assert_eq!(beans.per_100g["v_greens_beans"], 0.5);
+    // Legumes reach the gram field once, from V_LEGUMES, as cooked beans.
+    assert!((beans.per_100g["legumes"] - 86.0).abs() < 1e-9);
+    assert!((beans.per_100g["vegetables"] - 80.0).abs() < 1e-9);
+}
+
+#[test]
+fn a_days_foods_are_aggregated() {
+    let intake = table().aggregate([
+        ("63101000", 200.0),
+        ("Beef and vegetable stew", 250.0),
+        ("99999999", 50.0),
+    ]);
+    assert!((intake.fields["f_total"] - 1.6).abs() < 1e-9);
+    assert!((intake.fields["v_total"] - 0.75).abs() < 1e-9);
+    assert!((intake.fields["red_meat"] - 1.2 * 2.5 * 28.35).abs() < 1e-9);
+    assert_eq!(intake.unmatched, vec!["99999999".to_string()]);
+
+    let mut nv = NutritionVector {
+        fiber: Some(10.0),
+        ..Default::default()
+    };
+    intake.apply(&mut nv);
+    assert!((nv.fiber.unwrap() - 17.3).abs() < 1e-9);
+    assert!((nv.f_total.unwrap() - 1.6).abs() < 1e-9);
+    assert_eq!(nv.g_whole, Some(0.0));
+    assert_eq!(nv.whole_grains, Some(0.0));
+    assert!((nv.total_fruits.unwrap() - 256.0).abs() < 1e-9);
+}
+
+#[test]
+fn food_columns_resolve_through_the_table_given() {
+    let path = std::env::temp_dir().join(format!("fped_{}.tsv", std::process::id()));
+    let tsv = "FOODCODE\tDESCRIPTION\tF_TOTAL\tPF_LEGUMES\tfiber\n\
+               63101000\tApple, raw\t0.8\t0\t2.4\n\
+               41101000\tBeans, dry, cooked\t0\t2.0\t6.0\n";
+    std::fs::write(&path, tsv).unwrap();
+    let table = FoodPatternTable::from_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let row: HashMap<String, Value> = [
+        ("41101000_g".to_string(), Value::from(150.0)),
+        ("apple, raw_g".to_string(), Value::from(100.0)),
+        ("beef_g".to_string(), Value::from(100.0)),
+    ]
+    .into_iter()
+    .collect();
+    let (nv, trace) = NutritionVector::from_partial_map_with_units(
+        &row,
+        &HashMap::new(),
+        DEFAULT_MATCH_THRESHOLD,
+        Some(&table),
+    );
+    assert!((nv.pf_total.unwrap() - 3.0).abs() < 1e-9);
+    assert!((nv.f_total.unwrap() - 0.8).abs() < 1e-9);
+    // PF_LEGUMES only fills the protein foods; legume grams need V_LEGUMES.
+    assert_eq!(nv.legumes, None);
+    // food_components.json still takes precedence for its own items.
+    assert_eq!(nv.protein, Some(26.0));
+    assert!((nv.fiber.unwrap() - 11.4).abs() < 1e-9);
+    let sources = &trace.translation_log["fiber"].source;
+    assert!(sources.contains(&"41101000_g".to_string()));
+    // Descriptions are matched by name, so the food code joins the path.
+    assert!(sources.contains(&"apple, raw_g > 63101000".to_string()));
+
+    // Without a table the food codes are not found.
+    let (nv, _) = NutritionVector::from_partial_map(&row);
+    assert_eq!(nv.pf_total, None);
+}
@@ -1,5 +1,7 @@
 use dietarycodex::nutrition_vector::NutritionVector;
 use dietarycodex::eval::evaluate_allow_partial;
+use dietarycodex::food_item_resolver::FoodItemResolver;
+use dietarycodex::food_matching::DEFAULT_MATCH_THRESHOLD;
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -38,3 +40,85 @@ fn empty_input_gives_errors() {
     assert!(result.scores.values().all(|s| s.value.is_none()));
     assert!(!result.errors.is_empty());
 }
+
+const RECIPES: &str = r#"{
+  "beef": {"components": {"protein": 26.0, "red_meat": 100.0}},
+  "tomato": {"components": {"vitamin_c": 14.0, "vegetables": 100.0}},
+  "pasta": {"components": {"refined_grains": 100.0}},
+  "meat_sauce": {
+    "ingredients": [{"item": "beef", "grams": 300}, {"item": "tomato", "grams": 500}],
+    "yield_grams": 600,
+    "retention": {"vitamin_c": 0.5}
+  },
+  "lasagna": {
+    "ingredients": [{"item": "Meat_Sauce", "grams": 600}, {"item": "pasta", "grams": 300}],
+    "servings": 6,
+    "retention": {"vitamin_c": 0.8}
+  },
+  "loop_a": {"ingredients": [{"item": "loop_b", "grams": 100}]},
+  "loop_b": {"ingredients": [{"item": "loop_a", "grams": 50}, {"item": "beef", "grams": 50}]},
+  "stew": {"ingredients": [{"item": "beef", "grams": 200}, {"item": "mystery_root", "grams": 200}]}
+}"#;
+
+#[test]
+fn nested_recipes_expand_with_yield_and_retention() {
+    let resolver = FoodItemResolver::from_json(RECIPES).unwrap();
+    assert_eq!(resolver.recipe("lasagna").unwrap().batch_grams(), 900.0);
+    let totals = resolver.resolve("lasagna_g", 300.0).unwrap();
+    // A third of the lasagna: 200 g of sauce, which is a third of its batch.
+    assert!((totals["red_meat"] - 100.0).abs() < 1e-9);
+    assert!((totals["protein"] - 26.0).abs() < 1e-9);
+    assert!((totals["vegetables"] - 500.0 / 3.0).abs() < 1e-9);
+    assert!((totals["refined_grains"] - 100.0).abs() < 1e-9);
+    // Vitamin C loses half in the sauce and a fifth more in the oven.
+    let vitamin_c = 14.0 * 5.0 / 3.0 * 0.5 * 0.8;
+    assert!((totals["vitamin_c"] - vitamin_c).abs() < 1e-9);
+
+    let traced = resolver
+        .resolve_traced("lasagna_g", 300.0, DEFAULT_MATCH_THRESHOLD, None)
+        .unwrap();
+    assert!(traced.unresolved.is_empty());
+    let red_meat = traced
+        .contributions
+        .iter()
+        .find(|c| c.field == "red_meat")
+        .unwrap();
+    assert_eq!(red_meat.path, ["lasagna_g", "meat_sauce", "beef"]);
+
+    let serving = resolver.resolve("lasagna_servings", 2.0).unwrap();
+    assert!((serving["red_meat"] - 100.0).abs() < 1e-9);
+    assert!(resolver.resolve("meat_sauce_servings", 1.0).is_none());
+}
+
+#[test]
+fn recipe_cycles_are_cut() {
+    let resolver = FoodItemResolver::from_json(RECIPES).unwrap();
+    let traced = resolver
+        .resolve_traced("loop_a_g", 100.0, DEFAULT_MATCH_THRESHOLD, None)
+        .unwrap()
+        .contributions;
+    assert_eq!(traced.len(), 2);
+    assert!(traced
+        .iter()
+        .all(|c| c.path == ["loop_a_g", "loop_b", "beef"]));
+}
+
+#[test]
+fn translation_sources_keep_the_input_key() {
+    let mut map = HashMap::new();
+    map.insert("BEEF_G".to_string(), Value::from(50.0));
+    let (_, trace) = NutritionVector::from_partial_map(&map);
+    assert_eq!(trace.translation_log["red_meat"].source, ["BEEF_G"]);
+}
+
+#[test]
+fn unknown_ingredients_are_reported_in_the_trace() {
+    let resolver = FoodItemResolver::from_json(RECIPES).unwrap();
+    let traced = resolver
+        .resolve_traced("Stew (g)", 100.0, DEFAULT_MATCH_THRESHOLD, None)
+        .unwrap();
+    assert_eq!(traced.unresolved, [["Stew (g)", "mystery_root"]]);
+    // The known ingredients still count.
+    assert_eq!(traced.contributions.len(), 2);
+    assert!((resolver.resolve("stew_g", 100.0).unwrap()["red_meat"] - 50.0).abs() < 1e-9);
+}
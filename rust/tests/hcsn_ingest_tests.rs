@@ -1,4 +1,10 @@
-use dietarycodex::hcsn_ingest::{is_hcsn_sheet, resolve_hcsn_headers};
+use dietarycodex::eval::evaluate_allow_partial;
+use dietarycodex::hcsn_ingest::{aggregate_hcsn_row, is_hcsn_sheet, resolve_hcsn_headers};
+use dietarycodex::nutrition_vector::NutritionVector;
+use dietarycodex::scores::hcns::HcnsScorer;
+use dietarycodex::scores::DietScore;
+use serde_json::Value;
+use std::collections::HashMap;
 
 #[test]
 fn resolve_headers_maps_known_fields() {
@@ -8,8 +14,11 @@ fn resolve_headers_maps_known_fields() {
         "whole_grain_g".to_string(),
     ];
     let map = resolve_hcsn_headers(&headers);
-    assert_eq!(map.get("leafy_green_veg_servings"), Some(&"vegetables"));
-    assert_eq!(map.get("butter_servings"), Some(&"butter"));
+    assert_eq!(
+        map.get("leafy_green_veg_servings"),
+        Some(&"leafy_greens_wk")
+    );
+    assert_eq!(map.get("butter_servings"), Some(&"butter_wk"));
     assert_eq!(map.get("whole_grain_g"), Some(&"whole_grains"));
     assert!(is_hcsn_sheet(&headers));
 }
@@ -21,3 +30,69 @@ fn unknown_headers_not_mapped() {
     assert!(map.is_empty());
     assert!(!is_hcsn_sheet(&headers));
 }
+
+#[test]
+fn raw_frequency_items_are_summed_and_scored() {
+    let raw: HashMap<String, Value> = [
+        ("C3A_FF_13", 6.0),
+        ("C3B_FF_13", 4.0),
+        ("C4A_FF_13", 1.0),
+        ("C4B_FF_13", 1.0),
+        ("C5A_FF_13", 1.0),
+        ("C5B_FF_13", 1.0),
+        ("C5C_FF_13", 1.0),
+        ("C5D_FF_13", 0.0),
+        ("C9A_FF_13", 7.0),
+        ("respondent", 42.0),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), Value::from(*v)))
+    .collect();
+    let headers: Vec<String> = raw.keys().cloned().collect();
+    assert!(is_hcsn_sheet(&headers));
+    let row = aggregate_hcsn_row(&raw, &resolve_hcsn_headers(&headers));
+    assert_eq!(row["berries_wk"], Value::from(2.0));
+    assert_eq!(row["nuts_wk"], Value::from(3.0));
+    assert_eq!(row["olive_oil_primary"], Value::from(1.0));
+    assert_eq!(row["respondent"], Value::from(42.0));
+
+    let (nv, _) = NutritionVector::from_partial_map(&row);
+    // Leafy greens, berries and olive oil earn full points, other vegetables
+    // and nuts half, and the five limits are all met with no intake reported.
+    assert_eq!(HcnsScorer.evaluate(&nv), 3.0 + 1.0 + 5.0);
+}
+
+#[test]
+fn hcsn_rows_are_still_scored_with_mind() {
+    let raw: HashMap<String, Value> = [
+        ("leafy_green_veg_servings", 4.0),
+        ("other_veg_servings", 3.0),
+        ("berry_servings", 2.0),
+        ("nut_servings", 5.0),
+        ("bean_servings", 3.0),
+        ("whole_grains_servings", 3.0),
+        ("whole_grain_g", 20.0),
+        ("fish_servings", 1.0),
+        ("poultry_servings", 2.0),
+        ("olive_oil_daily_use", 7.0),
+        ("wine_servings", 1.0),
+        ("red_meat_servings", 1.0),
+        ("butter_servings", 0.5),
+        ("cheese_servings", 1.0),
+        ("pastry_sweets_servings", 2.0),
+        ("fried_food_servings", 1.0),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), Value::from(*v)))
+    .collect();
+    let headers: Vec<String> = raw.keys().cloned().collect();
+    let row = aggregate_hcsn_row(&raw, &resolve_hcsn_headers(&headers));
+    assert_eq!(row["vegetables"], Value::from(7.0));
+    assert_eq!(row["whole_grains"], Value::from(23.0));
+    assert_eq!(row["mono_fat"], Value::from(7.0));
+
+    let (nv, _) = NutritionVector::from_partial_map(&row);
+    let result = evaluate_allow_partial(&nv);
+    assert!(result.scores["MIND"].value.is_some());
+    assert!(result.scores["HCNS"].value.is_some());
+}
@@ -1,4 +1,9 @@
-use dietarycodex::nhanes_ingest::{build_nutrition_vector_from_nhanes, is_nhanes_sheet, resolve_nhanes_headers};
+use dietarycodex::nhanes_ingest::{
+    aggregate_nhanes_row, build_nutrition_vector_from_nhanes,
+    build_nutrition_vector_from_nhanes_days, is_nhanes_sheet, recall_day, resolve_nhanes_headers,
+    RecallDays,
+};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 
 #[test]
@@ -31,3 +36,155 @@ fn build_vector_averages_days() {
     assert_eq!(nv.energy, Some(1900.0));
     assert_eq!(nv.protein, Some(70.0));
 }
+
+fn two_day_row() -> HashMap<String, Value> {
+    let mut row = HashMap::new();
+    row.insert("SEQN".to_string(), json!(83732));
+    row.insert("DR1TKCAL".to_string(), json!(2000.0));
+    row.insert("DR2TKCAL".to_string(), json!(1800.0));
+    row.insert("Sodium - Day 1".to_string(), json!(3000.0));
+    row.insert("DR1T_PF_SEAFD_HI".to_string(), json!(1.0));
+    row.insert("DR1T_PF_SEAFD_LOW".to_string(), json!(0.5));
+    row.insert("DR2T_PF_SEAFD_HI".to_string(), json!(0.5));
+    row
+}
+
+#[test]
+fn recall_day_reads_variable_prefix_and_label_suffix() {
+    assert_eq!(recall_day("DR1TKCAL"), Some(1));
+    assert_eq!(recall_day("dr2tprot"), Some(2));
+    assert_eq!(recall_day("Total Fat - Day 2"), Some(2));
+    assert_eq!(recall_day("SEQN"), None);
+}
+
+#[test]
+fn mean_of_days_sums_within_a_day_before_averaging() {
+    let (row, recall) = aggregate_nhanes_row(&two_day_row(), &[], RecallDays::MeanOfDays);
+    assert_eq!(recall.days, vec![1, 2]);
+    assert!(recall.conflicting_aliases.is_empty());
+    assert_eq!(row["energy"], json!(1900.0));
+    // Day 1 seafood is 1.5 ounce equivalents across both groups, day 2 is 0.5.
+    assert_eq!(row["pf_seaplant"], json!(1.0));
+    assert!(!row.contains_key("fish"));
+    // Sodium was only recalled on day 1.
+    assert_eq!(row["sodium"], json!(3000.0));
+    assert_eq!(row["SEQN"], json!(83732));
+}
+
+#[test]
+fn day1_only_ignores_day_2() {
+    let (row, recall) = aggregate_nhanes_row(&two_day_row(), &[], RecallDays::Day1Only);
+    assert_eq!(recall.days, vec![1]);
+    assert_eq!(row["energy"], json!(2000.0));
+    assert_eq!(row["pf_seaplant"], json!(1.5));
+}
+
+#[test]
+fn require_both_drops_fields_recalled_on_one_day() {
+    let (row, recall) = aggregate_nhanes_row(&two_day_row(), &[], RecallDays::RequireBoth);
+    assert_eq!(recall.days, vec![1, 2]);
+    assert_eq!(row["energy"], json!(1900.0));
+    assert!(!row.contains_key("sodium"));
+
+    let day1: HashMap<String, Value> = two_day_row()
+        .into_iter()
+        .filter(|(k, _)| recall_day(k) != Some(2))
+        .collect();
+    let (row, recall) = aggregate_nhanes_row(&day1, &[], RecallDays::RequireBoth);
+    assert!(recall.days.is_empty());
+    assert!(!row.contains_key("energy"));
+}
+
+fn ordered_row(cells: &[(&str, Value)]) -> (HashMap<String, Value>, Vec<String>) {
+    let headers = cells.iter().map(|(k, _)| k.to_string()).collect();
+    let row = cells
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect();
+    (row, headers)
+}
+
+#[test]
+fn synonym_headers_of_one_day_are_not_summed() {
+    let (row, headers) = ordered_row(&[
+        ("DR1TKCAL", json!(2000.0)),
+        ("Total Calories - Day 1", json!(2000.0)),
+        ("Total Energy - Day 1", json!(2000.0)),
+        ("Protein (g) Day 1", json!(70.0)),
+        ("Protein - Day 1", json!(70.0)),
+        ("Added Sugars - Day 1", json!(40.0)),
+        ("DR1T_V_TOTAL", json!(1.0)),
+        ("DR1T_V_LEGUMES", json!(0.5)),
+        ("DR1T_PF_LEGUMES", json!(2.0)),
+    ]);
+    let (row, recall) = aggregate_nhanes_row(&row, &headers, RecallDays::MeanOfDays);
+    assert_eq!(row["energy"], json!(2000.0));
+    assert_eq!(row["protein"], json!(70.0));
+    assert_eq!(row["added_sugar_g"], json!(40.0));
+    assert!(!row.contains_key("sugar"));
+    // FPED groups still add up, legumes counting towards both food groups.
+    assert_eq!(row["v_total"], json!(1.5));
+    assert_eq!(row["pf_total"], json!(2.0));
+    assert_eq!(row["pf_seaplant"], json!(2.0));
+    assert_eq!(
+        recall.conflicting_aliases,
+        vec![
+            ("Total Calories - Day 1".to_string(), "energy"),
+            ("Total Energy - Day 1".to_string(), "energy"),
+            ("Protein - Day 1".to_string(), "protein"),
+        ]
+    );
+}
+
+#[test]
+fn the_first_synonym_in_file_order_is_kept() {
+    let (row, headers) = ordered_row(&[
+        ("Total Energy - Day 1", json!(2100.0)),
+        ("DR1TKCAL", json!(2000.0)),
+    ]);
+    let (row, recall) = aggregate_nhanes_row(&row, &headers, RecallDays::MeanOfDays);
+    assert_eq!(row["energy"], json!(2100.0));
+    assert_eq!(
+        recall.conflicting_aliases,
+        vec![("DR1TKCAL".to_string(), "energy")]
+    );
+}
+
+#[test]
+fn non_numeric_day_cells_are_missing() {
+    let (row, headers) = ordered_row(&[
+        ("DR1TKCAL", json!("refused")),
+        ("DR2TKCAL", json!(1800.0)),
+        ("DR1TPROT", json!("n/r")),
+    ]);
+    let (row, recall) = aggregate_nhanes_row(&row, &headers, RecallDays::MeanOfDays);
+    assert_eq!(row["energy"], json!(1800.0));
+    assert!(!row.contains_key("protein"));
+    assert_eq!(recall.days, vec![2]);
+}
+
+#[test]
+fn build_vector_reports_contributing_days() {
+    let mut row: HashMap<String, f32> = HashMap::new();
+    row.insert("DR1TKCAL".to_string(), 2000.0);
+    row.insert("DR2TKCAL".to_string(), 1800.0);
+    for field in dietarycodex::nutrition_vector::NutritionVector::all_field_names() {
+        if *field != "energy" {
+            row.insert(field.to_string(), 0.0);
+        }
+    }
+    let (nv, days) = build_nutrition_vector_from_nhanes_days(&row, RecallDays::Day1Only).unwrap();
+    assert_eq!(nv.energy, Some(2000.0));
+    assert_eq!(days, vec![1]);
+}
+
+#[test]
+fn recall_modes_round_trip_by_name() {
+    for mode in [
+        RecallDays::MeanOfDays,
+        RecallDays::Day1Only,
+        RecallDays::RequireBoth,
+    ] {
+        assert_eq!(RecallDays::from_name(mode.name()), Some(mode));
+    }
+}
@@ -55,14 +55,22 @@ fn duplicate_fields_prefers_canonical() {
     assert!(trace.conflicting_aliases.contains(&("carbohydrate".to_string(), "carbs")));
 }
 
+fn map_without(field: &str) -> HashMap<String, f64> {
+    NutritionVector::all_field_names()
+        .iter()
+        .filter(|f| **f != field)
+        .map(|f| (f.to_string(), 1.0))
+        .collect()
+}
+
 #[test]
 fn missing_canonical_field_errors() {
-    let mut map: HashMap<String, f64> = HashMap::new();
-    for f in NutritionVector::all_field_names() {
-        if *f != "fat" {
-            map.insert(f.to_string(), 1.0);
-        }
-    }
-    let err = NutritionVector::from_map(&map).unwrap_err();
-    assert!(err.missing_canonical_fields.contains(&"fat"));
+    let err = NutritionVector::from_map(&map_without("sodium")).unwrap_err();
+    assert_eq!(err.missing_canonical_fields, vec!["sodium"]);
+}
+
+#[test]
+fn fields_no_index_requires_may_be_missing() {
+    let nv = NutritionVector::from_map(&map_without("fat")).expect("no index needs fat");
+    assert_eq!(nv.fat, None);
 }
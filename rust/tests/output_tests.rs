@@ -0,0 +1,120 @@
+use dietarycodex::nhanes_ingest::RecallDays;
+use dietarycodex::output::{wide_table, write_table, Column, OutputFormat};
+use dietarycodex::tabular::{score_delimited, ScoredRow};
+use std::path::Path;
+
+fn template_rows() -> Vec<ScoredRow> {
+    let text = std::fs::read_to_string("../data/template.csv").unwrap();
+    let mut text_with_ids = String::new();
+    for (i, line) in text.lines().enumerate() {
+        let id = if i == 0 {
+            "participant_id".to_string()
+        } else {
+            format!("P{}", i)
+        };
+        text_with_ids.push_str(&format!("{},{}\n", id, line));
+    }
+    score_delimited(&text_with_ids, b',', RecallDays::default()).unwrap()
+}
+
+#[test]
+fn wide_table_has_one_column_per_index() {
+    let rows = template_rows();
+    let table = wide_table(&rows, false);
+    assert_eq!(table.row_count(), rows.len());
+    let names: Vec<&str> = table.columns.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(&names[..2], &["row", "participant_id"]);
+    assert_eq!(names.last(), Some(&"missing_fields"));
+    assert!(names.contains(&"HEI_2020") && names.contains(&"HEI_2020_valid"));
+    assert!(!names.iter().any(|n| n.starts_with("HEI_2020_total_fruits")));
+    assert_eq!(
+        table.column("participant_id"),
+        Some(&Column::Text(
+            (1..=rows.len()).map(|i| Some(format!("P{}", i))).collect()
+        ))
+    );
+    let expected = rows[0].result.scores["HEI_2020"].value;
+    match table.column("HEI_2020") {
+        Some(Column::Number(values)) => assert_eq!(values[0], expected),
+        other => panic!("unexpected column {:?}", other),
+    }
+}
+
+#[test]
+fn missing_fields_lists_only_fields_an_index_required() {
+    let text = "id,energy,sodium,fat\n1,2000,3000,70\n";
+    let rows = score_delimited(text, b',', RecallDays::default()).unwrap();
+    let table = wide_table(&rows, false);
+    let missing = match table.column("missing_fields") {
+        Some(Column::Text(values)) => values[0].clone().unwrap(),
+        other => panic!("unexpected column {:?}", other),
+    };
+    let fields: Vec<&str> = missing.split(';').collect();
+    assert!(fields.contains(&"fiber"));
+    assert!(fields.windows(2).all(|w| w[0] < w[1]));
+    // Fields no index requires are left out.
+    assert!(rows[0].result.trace.missing_fields.contains(&"vitamin_b12"));
+    assert!(!fields.contains(&"vitamin_b12"));
+}
+
+#[test]
+fn component_columns_hold_points() {
+    let rows = template_rows();
+    let table = wide_table(&rows, true);
+    let info = &rows[0].result.scores["HEI_2020"];
+    for c in &info.components {
+        match table.column(&format!("HEI_2020_{}", c.name)) {
+            Some(Column::Number(values)) => assert_eq!(values[0], Some(c.points)),
+            other => panic!("missing component {}: {:?}", c.name, other),
+        }
+    }
+}
+
+#[test]
+fn tsv_writer_emits_header_and_one_line_per_row() {
+    let rows = template_rows();
+    let table = wide_table(&rows, false);
+    let mut buf = Vec::new();
+    write_table(OutputFormat::Tsv, &table, &mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines.next().unwrap().split('\t').collect();
+    assert_eq!(header.len(), table.columns.len());
+    let first: Vec<&str> = lines.next().unwrap().split('\t').collect();
+    assert_eq!(first[0], "0");
+    assert_eq!(first[1], "P1");
+    assert_eq!(lines.count(), rows.len() - 1);
+}
+
+#[test]
+fn formats_follow_names_and_extensions() {
+    assert_eq!(OutputFormat::from_name("CSV"), Some(OutputFormat::Csv));
+    assert_eq!(
+        OutputFormat::from_path(Path::new("out/scores.parquet")),
+        Some(OutputFormat::Parquet)
+    );
+    assert_eq!(OutputFormat::from_path(Path::new("scores")), None);
+    assert!(OutputFormat::Json.table_sink(Vec::new()).is_none());
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn parquet_writer_round_trips_columns() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let rows = template_rows();
+    let table = wide_table(&rows, true);
+    let path = std::env::temp_dir().join("dietarycodex_output_test.parquet");
+    write_table(
+        OutputFormat::Parquet,
+        &table,
+        &mut std::fs::File::create(&path).unwrap(),
+    )
+    .unwrap();
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    let meta = reader.metadata().file_metadata();
+    assert_eq!(meta.num_rows() as usize, rows.len());
+    assert_eq!(meta.schema_descr().num_columns(), table.columns.len());
+    assert_eq!(meta.schema_descr().column(1).name(), "participant_id");
+    std::fs::remove_file(path).ok();
+}
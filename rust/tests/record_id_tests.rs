@@ -0,0 +1,58 @@
+use dietarycodex::record_ids::{check_ids, record_id, resolve_id_columns};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+fn headers(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+fn row(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+#[test]
+fn default_id_column_is_found_by_alias() {
+    let cols = resolve_id_columns(&headers(&["SEQN", "energy", "Participant_ID"]), &[]);
+    assert_eq!(cols, vec!["Participant_ID".to_string()]);
+    assert!(resolve_id_columns(&headers(&["energy"]), &[]).is_empty());
+}
+
+#[test]
+fn designated_columns_keep_their_order() {
+    let cols = resolve_id_columns(
+        &headers(&["visit", "Subject", "energy"]),
+        &headers(&["subject", "visit", "site"]),
+    );
+    assert_eq!(cols, headers(&["Subject", "visit", "site"]));
+}
+
+#[test]
+fn ids_are_copied_verbatim() {
+    let cols = headers(&["id"]);
+    let id = record_id(
+        &row(&[("id", json!("P-007")), ("energy", json!(2000))]),
+        &cols,
+    );
+    assert_eq!(id["id"], json!("P-007"));
+    assert_eq!(id.len(), 1);
+}
+
+#[test]
+fn missing_and_duplicate_ids_are_reported() {
+    let cols = headers(&["id"]);
+    let rows = [
+        row(&[("id", json!(1))]),
+        row(&[("id", json!(""))]),
+        row(&[("id", json!("1"))]),
+        row(&[("id", json!(2))]),
+        row(&[]),
+    ];
+    let ids: Vec<BTreeMap<String, Value>> = rows.iter().map(|r| record_id(r, &cols)).collect();
+    let report = check_ids(&cols, &ids);
+    assert_eq!(report.missing, vec![1, 4]);
+    assert_eq!(report.duplicates.len(), 1);
+    assert_eq!(report.duplicates["1"], vec![0, 2]);
+}
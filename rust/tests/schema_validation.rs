@@ -1,7 +1,5 @@
 use dietarycodex::nutrition_vector::NutritionVector;
-use serde_json;
 use std::collections::HashMap;
-use std::fs;
 
 fn full_map() -> HashMap<String, f64> {
     let mut m = HashMap::new();
@@ -1,18 +1,18 @@
 use dietarycodex::eval::{
     evaluate_all_scores, evaluate_allow_partial, format_skipped_scores,
-    print_scores_as_json_allow_partial, ScoreInfo,
+    print_scores_as_json_allow_partial,
 };
 use dietarycodex::nutrition_vector::NutritionVector;
 use dietarycodex::scores::acs2020::Acs2020Scorer;
+use dietarycodex::scores::all_scorers;
 use dietarycodex::scores::amed::AMedScorer;
-use dietarycodex::scores::dash::DashScorer;
+use dietarycodex::scores::dash::DashLinearScorer;
 use dietarycodex::scores::dashi::DashiScorer;
 use dietarycodex::scores::dii::DiiScorer;
-use dietarycodex::scores::hei::HeiScorer;
+use dietarycodex::scores::hei::{Hei2015Scorer, Hei2020Scorer};
 use dietarycodex::scores::mind::MindScorer;
 use dietarycodex::scores::phdi::PhdiScorer;
 use dietarycodex::scores::DietScore;
-use serde_json;
 
 fn expected_names() -> Vec<String> {
     dietarycodex::register_scores!()
@@ -24,16 +24,40 @@ fn expected_names() -> Vec<String> {
 #[test]
 fn hei_score_not_nan() {
     let nv = NutritionVector {
-        total_fruits: Some(150.0),
-        whole_grains: Some(80.0),
+        energy: Some(2000.0),
+        f_total: Some(1.5),
+        g_whole: Some(2.0),
         sodium: Some(1600.0),
         ..Default::default()
     };
-    let scorer = HeiScorer;
+    let scorer = Hei2015Scorer;
     let val = scorer.evaluate(&nv);
     assert!(!val.is_nan());
 }
 
+#[test]
+fn hei_2020_full_marks_at_standards() {
+    let nv = NutritionVector {
+        energy: Some(1000.0),
+        f_total: Some(0.8),
+        f_whole: Some(0.4),
+        v_total: Some(1.1),
+        v_greens_beans: Some(0.2),
+        g_whole: Some(1.5),
+        d_total: Some(1.3),
+        pf_total: Some(2.5),
+        pf_seaplant: Some(0.8),
+        mono_fat: Some(15.0),
+        pufa: Some(10.0),
+        saturated_fat: Some(8.0),
+        g_refined: Some(1.8),
+        sodium: Some(1100.0),
+        add_sugars: Some(4.0),
+        ..Default::default()
+    };
+    assert!((Hei2020Scorer.evaluate(&nv) - 100.0).abs() < 1e-9);
+}
+
 #[test]
 fn dash_score_not_nan() {
     let nv = NutritionVector {
@@ -45,7 +69,7 @@ fn dash_score_not_nan() {
         energy: Some(2000.0),
         ..Default::default()
     };
-    let scorer = DashScorer;
+    let scorer = DashLinearScorer;
     let val = scorer.evaluate(&nv);
     assert!(!val.is_nan());
 }
@@ -83,7 +107,15 @@ fn amed_score_not_nan() {
 }
 
 #[test]
-fn evaluate_returns_dash() {
+fn amed_keeps_the_per_record_scorer_next_to_the_cohort_one() {
+    use dietarycodex::scores::registry::batch_scorer;
+
+    assert!(expected_names().contains(&"aMED".to_string()));
+    assert!(batch_scorer("aMED_COHORT").is_some());
+}
+
+#[test]
+fn evaluate_returns_dash_linear() {
     let nv = NutritionVector {
         total_fruits: Some(200.0),
         vegetables: Some(200.0),
@@ -94,9 +126,9 @@ fn evaluate_returns_dash() {
         ..Default::default()
     };
     let scores = evaluate_allow_partial(&nv);
-    match scores.scores.get("DASH") {
+    match scores.scores.get("DASH_LINEAR") {
         Some(info) if info.value.is_some() => {}
-        _ => panic!("DASH score not computed"),
+        _ => panic!("DASH_LINEAR score not computed"),
     }
 }
 
@@ -222,16 +254,46 @@ fn returns_err_with_missing_fields() {
         ..Default::default()
     };
     let err = evaluate_all_scores(&nv).unwrap_err();
-    assert!(err.missing_canonical_fields.contains(&"fat"));
+    assert!(err.missing_canonical_fields.contains(&"sodium"));
     assert!(err.missing_canonical_fields.contains(&"saturated_fat"));
+    // No index requires total fat.
+    assert!(!err.missing_canonical_fields.contains(&"fat"));
+}
+
+#[test]
+fn strict_scoring_needs_only_the_fields_of_registered_indices() {
+    let mut nv = NutritionVector::default();
+    for scorer in all_scorers() {
+        for field in scorer.required_fields() {
+            *nv.field_mut(field).unwrap() = Some(if *field == "sex" { 2.0 } else { 40.0 });
+        }
+    }
+    assert!(!nv.missing_fields().is_empty());
+    assert!(nv.missing_required_fields().is_empty());
+    let result = evaluate_all_scores(&nv).expect("every index has its fields");
+    assert_eq!(result.ordered_names.len(), all_scorers().len());
+
+    nv.fiber = None;
+    let err = evaluate_all_scores(&nv).unwrap_err();
+    assert_eq!(err.missing_canonical_fields, vec!["fiber"]);
 }
 
 #[test]
 fn allow_partial_skips_missing() {
     let nv = NutritionVector {
-        fiber: Some(5.0),
-        fat: Some(40.0),
-        saturated_fat: Some(10.0),
+        energy: Some(2000.0),
+        sex: Some(1.0),
+        veg_serv: Some(3.0),
+        fruit_serv: Some(2.0),
+        whole_grains: Some(40.0),
+        ssb_serv: Some(0.5),
+        nuts_legumes_serv: Some(0.5),
+        red_processed_meat_serv: Some(1.0),
+        trans_fat: Some(2.0),
+        epa_dha: Some(100.0),
+        pufa: Some(12.0),
+        sodium: Some(3000.0),
+        alcohol: Some(10.0),
         ..Default::default()
     };
     let result = evaluate_allow_partial(&nv);
@@ -239,12 +301,23 @@ fn allow_partial_skips_missing() {
         Some(info) if info.value.is_some() => {}
         _ => panic!("AHEI should be computed"),
     }
-    match result.scores.get("DASH") {
+    match result.scores.get("DASH_LINEAR") {
         Some(info) if info.value.is_none() => {}
-        _ => panic!("DASH should be skipped"),
+        _ => panic!("DASH_LINEAR should be skipped"),
     }
 }
 
+#[test]
+fn ahei_requires_sex_code() {
+    let mut nv = all_fields_nv();
+    nv.sex = Some(3.0);
+    let result = evaluate_allow_partial(&nv);
+    assert_eq!(
+        result.scores["AHEI"].explanation.as_deref(),
+        Some("not applicable: sex 3 is not coded 1 (male) or 2 (female)")
+    );
+}
+
 #[test]
 fn skipped_reason_lists_missing_fields() {
     let nv = NutritionVector {
@@ -255,18 +328,18 @@ fn skipped_reason_lists_missing_fields() {
     match result.scores.get("AHEI") {
         Some(info) if info.value.is_none() => {
             let reason = info.explanation.as_ref().unwrap();
-            assert!(reason.contains("fat"));
-            assert!(reason.contains("saturated_fat"));
+            assert!(reason.contains("veg_serv"));
+            assert!(reason.contains("sex"));
         }
         _ => panic!("AHEI should be skipped"),
     }
-    match result.scores.get("DASH") {
+    match result.scores.get("DASH_LINEAR") {
         Some(info) if info.value.is_none() => {
             let reason = info.explanation.as_ref().unwrap();
             assert!(reason.contains("sodium"));
             assert!(reason.contains("energy"));
         }
-        _ => panic!("DASH should be skipped"),
+        _ => panic!("DASH_LINEAR should be skipped"),
     }
 }
 
@@ -293,13 +366,13 @@ fn metadata_sorted() {
     use dietarycodex::scores::registry::all_score_metadata;
 
     let metas = all_score_metadata();
-    let mut names: Vec<&str> = metas.iter().map(|m| m.name).collect();
+    let names: Vec<&str> = metas.iter().map(|m| m.name).collect();
     let mut sorted_names = names.clone();
     sorted_names.sort();
     assert_eq!(names, sorted_names, "score metadata not sorted by name");
 
     for meta in metas {
-        let mut fields: Vec<&str> = meta.required_fields.iter().copied().collect();
+        let fields: Vec<&str> = meta.required_fields.to_vec();
         let mut sorted_fields = fields.clone();
         sorted_fields.sort();
         assert_eq!(fields, sorted_fields, "fields for {} not sorted", meta.name);
@@ -364,6 +437,84 @@ fn all_fields_nv() -> NutritionVector {
         magnesium: Some(300.0),
         trans_fat: Some(0.2),
         alcohol: Some(5.0),
+        anthocyanidins: Some(18.0),
+        beta_carotene: Some(3700.0),
+        caffeine: Some(8.0),
+        cholesterol: Some(280.0),
+        eugenol: Some(0.01),
+        flavan3ols: Some(95.0),
+        flavanones: Some(11.0),
+        flavones: Some(1.5),
+        flavonols: Some(17.0),
+        folic_acid: Some(270.0),
+        garlic: Some(4.0),
+        ginger: Some(50.0),
+        isoflavones: Some(1.2),
+        niacin: Some(25.0),
+        omega6: Some(10.0),
+        onion: Some(35.0),
+        pepper: Some(10.0),
+        pufa: Some(14.0),
+        riboflavin: Some(1.7),
+        rosemary: Some(1.0),
+        saffron: Some(0.4),
+        tea: Some(1.7),
+        thiamin: Some(1.7),
+        thyme_oregano: Some(0.3),
+        turmeric: Some(500.0),
+        vitamin_b12: Some(5.0),
+        vitamin_b6: Some(1.5),
+        vitamin_d: Some(6.0),
+        f_total: Some(2.0),
+        f_whole: Some(1.2),
+        v_total: Some(2.5),
+        v_greens_beans: Some(0.5),
+        g_whole: Some(3.0),
+        g_refined: Some(4.0),
+        d_total: Some(2.5),
+        pf_total: Some(5.5),
+        pf_seaplant: Some(1.5),
+        add_sugars: Some(10.0),
+        age: Some(40.0),
+        sex: Some(2.0),
+        veg_serv: Some(5.0),
+        fruit_serv: Some(4.0),
+        nuts_legumes_serv: Some(1.0),
+        ssb_serv: Some(0.0),
+        red_processed_meat_serv: Some(0.5),
+        epa_dha: Some(250.0),
+        olive_oil_serv: Some(5.0),
+        legumes_serv: Some(0.5),
+        nuts_serv: Some(0.5),
+        fish_serv: Some(0.5),
+        sweets_serv: Some(0.1),
+        discret_fat_serv: Some(0.5),
+        veg_variety: Some(5.0),
+        fruit_variety: Some(3.0),
+        hpf_rg_serv: Some(2.0),
+        leafy_greens_wk: Some(6.0),
+        other_veg_wk: Some(6.0),
+        berries_wk: Some(2.0),
+        nuts_wk: Some(5.0),
+        beans_wk: Some(3.0),
+        whole_grains_wk: Some(3.0),
+        fish_wk: Some(1.0),
+        poultry_wk: Some(2.0),
+        olive_oil_primary: Some(1.0),
+        wine_wk: Some(1.0),
+        red_meat_wk: Some(0.0),
+        butter_wk: Some(1.0),
+        cheese_wk: Some(1.0),
+        pastries_sweets_wk: Some(1.0),
+        fried_food_wk: Some(1.0),
+        starchy_veg: Some(50.0),
+        dairy: Some(250.0),
+        eggs: Some(13.0),
+        soy: Some(50.0),
+        added_fat_unsat: Some(40.0),
+        added_fat_sat_trans: Some(5.0),
+        added_sugar_g: Some(25.0),
+        low_fat_dairy_serv: Some(2.0),
     }
 }
 
@@ -378,7 +529,41 @@ fn all_scores_skipped_when_no_input() {
 fn no_scores_skipped_when_all_fields_present() {
     let nv = all_fields_nv();
     let result = evaluate_allow_partial(&nv);
-    assert!(result.scores.values().all(|s| s.value.is_some()));
+    for (name, info) in &result.scores {
+        if name == "HEI_TODDLERS_2020" {
+            // The fixture describes an adult.
+            assert!(info.value.is_none());
+        } else {
+            assert!(info.value.is_some(), "{} skipped", name);
+        }
+    }
+}
+
+#[test]
+fn hei_routes_by_age() {
+    let mut nv = all_fields_nv();
+    let adult = evaluate_allow_partial(&nv);
+    assert_eq!(
+        adult.scores["HEI_TODDLERS_2020"].explanation.as_deref(),
+        Some("not applicable: age 40 is outside 12 to 23 months")
+    );
+    assert!(adult.errors.iter().all(|e| e.index != "HEI_TODDLERS_2020"));
+
+    nv.age = Some(1.5);
+    let toddler = evaluate_allow_partial(&nv);
+    assert!(toddler.scores["HEI_TODDLERS_2020"].value.is_some());
+    for name in ["HEI_2015", "HEI_2020"] {
+        assert_eq!(
+            toddler.scores[name].explanation.as_deref(),
+            Some("not applicable: age 1.5 is below 2")
+        );
+    }
+
+    // Adult editions still score records without an age.
+    nv.age = None;
+    let unknown = evaluate_allow_partial(&nv);
+    assert!(unknown.scores["HEI_2020"].value.is_some());
+    assert!(unknown.scores["HEI_TODDLERS_2020"].value.is_none());
 }
 
 #[test]
@@ -483,3 +668,388 @@ fn deserialize_alias_alcohol_intake() {
     let nv: NutritionVector = serde_json::from_str(json).unwrap();
     assert_eq!(nv.alcohol, Some(3.0));
 }
+
+#[test]
+fn dii_reports_unsupplied_parameters() {
+    let nv = NutritionVector {
+        saturated_fat: Some(28.6),
+        fiber: Some(18.8),
+        vitamin_c: Some(118.2),
+        vitamin_a: Some(983.9),
+        vitamin_e: Some(8.73),
+        omega3: Some(1.06),
+        zinc: Some(9.84),
+        selenium: Some(67.0),
+        magnesium: Some(310.1),
+        trans_fat: Some(3.15),
+        ..Default::default()
+    };
+    let result = evaluate_allow_partial(&nv);
+    let info = result.scores.get("DII").unwrap();
+    // every supplied intake sits at the global mean
+    assert!(info.value.unwrap().abs() < 1e-6);
+    assert!(info.valid);
+    let note = info.explanation.as_ref().unwrap();
+    assert!(note.starts_with("35 of 45 parameters not supplied"));
+    assert!(note.contains("turmeric"));
+    assert!(!note.contains("fiber"));
+}
+
+#[test]
+fn dii_contract_covers_anti_inflammatory_diets() {
+    use dietarycodex::contracts;
+    use dietarycodex::scores::dii::{parameters, score_range};
+
+    let [low, high] = score_range();
+    assert!((low + 13.152).abs() < 1e-9 && (high - 13.152).abs() < 1e-9);
+    let contract = contracts::range("DII");
+    assert!((contract[0] - low).abs() < 1e-9 && (contract[1] - high).abs() < 1e-9);
+
+    // Anti-inflammatory parameters far above the global mean, the others at
+    // zero, scores well below -9 and is still a valid score.
+    let mut nv = NutritionVector::default();
+    for p in parameters() {
+        let intake = if p.effect < 0.0 { p.mean + 5.0 * p.sd } else { 0.0 };
+        *nv.field_mut(&p.field).unwrap() = Some(intake);
+    }
+    let result = evaluate_allow_partial(&nv);
+    let info = result.scores.get("DII").unwrap();
+    assert!(info.value.unwrap() < -9.0, "{:?}", info.value);
+    assert!(info.valid, "{:?}", info.explanation);
+}
+
+#[test]
+fn amed_cohort_medians_are_sex_specific() {
+    use dietarycodex::scores::amed::AMedCohortScorer;
+    use dietarycodex::scores::BatchScore;
+
+    // Men eat more fish than women, so against the pooled median no woman
+    // earns the fish point while the heavier eater of each sex does within
+    // their own stratum.
+    let batch: Vec<NutritionVector> = [(1.0, 200.0), (1.0, 100.0), (2.0, 50.0), (2.0, 25.0)]
+        .iter()
+        .map(|&(sex, fish)| NutritionVector {
+            sex: Some(sex),
+            fish: Some(fish),
+            red_meat: Some(50.0),
+            ..Default::default()
+        })
+        .collect();
+    let stratified = AMedCohortScorer::default().evaluate_batch(&batch);
+    assert_eq!(stratified, vec![1.0, 0.0, 1.0, 0.0]);
+    let pooled = AMedCohortScorer {
+        stratify_by_sex: false,
+        ..Default::default()
+    }
+    .evaluate_batch(&batch);
+    assert_eq!(pooled, vec![1.0, 1.0, 0.0, 0.0]);
+}
+
+fn medi_vector(servings: [f64; 11]) -> NutritionVector {
+    let [olive_oil, fruit, veg, legumes, nuts, fish, alcohol_g, ssb, sweets, discret_fat, red_meat] =
+        servings;
+    NutritionVector {
+        olive_oil_serv: Some(olive_oil),
+        fruit_serv: Some(fruit),
+        veg_serv: Some(veg),
+        legumes_serv: Some(legumes),
+        nuts_serv: Some(nuts),
+        fish_serv: Some(fish),
+        alcohol: Some(alcohol_g),
+        ssb_serv: Some(ssb),
+        sweets_serv: Some(sweets),
+        discret_fat_serv: Some(discret_fat),
+        red_processed_meat_serv: Some(red_meat),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn medi_scores_serving_targets() {
+    use dietarycodex::scores::medi::{MediScorer, MediV2Scorer};
+
+    let ideal = medi_vector([5.0, 3.0, 2.0, 0.5, 0.5, 0.5, 14.0, 0.0, 0.0, 0.0, 0.0]);
+    assert_eq!(MediScorer.evaluate(&ideal), 11.0);
+    assert!((MediV2Scorer.evaluate(&ideal) - 55.0).abs() < 1e-9);
+
+    // Targets are met exactly for fruit, legumes and fish; limits are reached
+    // exactly for sweets and red meat, which no longer earns their points.
+    let mixed = medi_vector([
+        2.5,
+        3.0,
+        1.0,
+        3.0 / 7.0,
+        0.0,
+        1.0,
+        7.0,
+        0.5,
+        2.0 / 7.0,
+        0.0,
+        1.0,
+    ]);
+    assert_eq!(MediScorer.evaluate(&mixed), 5.0);
+    assert!((MediV2Scorer.evaluate(&mixed) - 30.0).abs() < 1e-9);
+}
+
+#[test]
+fn medi_aliases_resolve_to_serving_fields() {
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    let map: HashMap<String, Value> = [
+        ("frt_serv_medi", 3.0),
+        ("fish_seafood_serv_medi", 0.5),
+        ("legumes_serv_medi", 0.5),
+        ("redproc_meat_serv_medi", 1.5),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), Value::from(*v)))
+    .collect();
+    let (nv, _) = NutritionVector::from_partial_map(&map);
+    assert_eq!(nv.fruit_serv, Some(3.0));
+    assert_eq!(nv.fish_serv, Some(0.5));
+    assert_eq!(nv.legumes_serv, Some(0.5));
+    assert_eq!(nv.red_processed_meat_serv, Some(1.5));
+}
+
+#[test]
+fn acs2020_versions_registered_by_name() {
+    use dietarycodex::scores::registry::batch_scorer;
+
+    for name in ["ACS2020_V1", "ACS2020_V2", "ACS2020_V3"] {
+        let scorer = batch_scorer(name).expect(name);
+        assert_eq!(scorer.name(), name);
+        assert!(scorer.required_fields().contains(&"sex"));
+    }
+    assert!(batch_scorer("ACS2020_V4").is_none());
+}
+
+#[test]
+fn acs2020_v2_ranks_processed_foods_per_energy() {
+    use dietarycodex::scores::acs2020::{Acs2020CohortScorer, Acs2020Version};
+    use dietarycodex::scores::BatchScore;
+
+    // Same servings, but the first record eats twice the energy, so only V2
+    // sees it as the lower processed food intake.
+    let batch: Vec<NutritionVector> = [4000.0, 2000.0]
+        .iter()
+        .map(|&energy| NutritionVector {
+            sex: Some(2.0),
+            energy: Some(energy),
+            hpf_rg_serv: Some(3.0),
+            ssb_serv: Some(1.0),
+            ..Default::default()
+        })
+        .collect();
+    let v1 = Acs2020CohortScorer(Acs2020Version::V1).evaluate_batch(&batch);
+    let v2 = Acs2020CohortScorer(Acs2020Version::V2).evaluate_batch(&batch);
+    assert_eq!(v1[0], v1[1]);
+    assert_eq!(v2[0] - v2[1], 1.5);
+}
+
+#[test]
+fn ahei_cohort_scores_sodium_on_energy_adjusted_deciles() {
+    use dietarycodex::scores::ahei::{component_scores, AheiCohortScorer};
+    use dietarycodex::scores::BatchScore;
+
+    // Sodium in proportion to energy: equal once adjusted, so every record
+    // shares the lowest decile although raw intakes differ.
+    let batch: Vec<NutritionVector> = (0..10)
+        .map(|i| {
+            let energy = 1500.0 + 100.0 * i as f64;
+            NutritionVector {
+                energy: Some(energy),
+                sodium: Some(1.5 * energy),
+                sex: Some(1.0),
+                ..all_fields_nv()
+            }
+        })
+        .collect();
+    let scores = AheiCohortScorer.evaluate_batch(&batch);
+    for (nv, score) in batch.iter().zip(&scores) {
+        let others: f64 = component_scores(nv)
+            .iter()
+            .filter(|c| c.name != "sodium")
+            .map(|c| c.points)
+            .sum();
+        assert!((score - others - 10.0).abs() < 1e-9);
+    }
+
+    // At equal energy the lowest sodium earns 10 points, the highest 0.
+    let batch: Vec<NutritionVector> = (0..10)
+        .map(|i| NutritionVector {
+            sodium: Some(1000.0 + 200.0 * i as f64),
+            sex: Some(1.0),
+            ..all_fields_nv()
+        })
+        .collect();
+    let scores = AheiCohortScorer.evaluate_batch(&batch);
+    assert!((scores[0] - scores[9] - 10.0).abs() < 1e-9);
+    assert!((scores[0] - scores[1] - 10.0 / 9.0).abs() < 1e-9);
+}
+
+#[test]
+fn ahei_cohort_leaves_out_records_it_has_no_standards_for() {
+    use dietarycodex::eval::evaluate_batch_allow_partial;
+
+    let complete = NutritionVector {
+        sex: Some(1.0),
+        ..all_fields_nv()
+    };
+    let uncoded = NutritionVector {
+        sex: Some(3.0),
+        ..all_fields_nv()
+    };
+    let no_energy = NutritionVector {
+        energy: Some(0.0),
+        ..complete.clone()
+    };
+    let results = evaluate_batch_allow_partial(&[complete, uncoded, no_energy]);
+    assert!(results[0].scores["AHEI_COHORT"].valid);
+    assert_eq!(
+        results[1].scores["AHEI_COHORT"].explanation.as_deref(),
+        Some("not applicable: sex 3 is not coded 1 (male) or 2 (female)")
+    );
+    assert_eq!(
+        results[2].scores["AHEI_COHORT"].explanation.as_deref(),
+        Some("not applicable: energy must be positive")
+    );
+}
+
+#[test]
+fn dash_quintiles_replace_linear_scoring_under_dash_name() {
+    use dietarycodex::scores::registry::batch_scorer;
+
+    let dash = batch_scorer("DASH").expect("DASH");
+    assert_eq!(dash.required_fields().len(), 8);
+    let names = expected_names();
+    assert!(names.contains(&"DASH_LINEAR".to_string()));
+    assert!(!names.contains(&"DASH".to_string()));
+
+    // Values equal to a cut point fall in the lower quintile, so identical
+    // intakes rank everyone lowest: 1 point for each healthy component and 5
+    // for each of the three to limit.
+    let batch = vec![all_fields_nv(); 3];
+    assert_eq!(dash.evaluate_batch(&batch), vec![20.0; 3]);
+}
+
+#[test]
+fn batch_evaluation_adds_cohort_scores_to_each_record() {
+    use dietarycodex::eval::evaluate_batch_allow_partial;
+
+    let mut incomplete = all_fields_nv();
+    incomplete.low_fat_dairy_serv = None;
+    let batch = vec![all_fields_nv(), incomplete, all_fields_nv()];
+    let results = evaluate_batch_allow_partial(&batch);
+    assert_eq!(results.len(), 3);
+
+    for result in &results {
+        assert!(result.scores["AHEI"].value.is_some());
+        assert!(result.ordered_names.contains(&"DASH".to_string()));
+        assert!(result.ordered_names.contains(&"aMED_COHORT".to_string()));
+    }
+    assert_eq!(results[0].scores["DASH"].value, Some(20.0));
+    assert!(results[0].scores["DASH"].valid);
+    assert_eq!(results[1].scores["DASH"].value, None);
+    assert_eq!(
+        results[1].scores["DASH"].explanation.as_deref(),
+        Some("missing fields: low_fat_dairy_serv")
+    );
+    assert!(results[1]
+        .errors
+        .iter()
+        .any(|e| e.index == "DASH" && e.missing_fields == vec!["low_fat_dairy_serv"]));
+}
+
+#[test]
+fn metadata_lists_batch_scorers() {
+    use dietarycodex::scores::registry::all_score_metadata;
+
+    let metas = all_score_metadata();
+    let dash = metas.iter().find(|m| m.name == "DASH").unwrap();
+    assert!(dash.batch);
+    let linear = metas.iter().find(|m| m.name == "DASH_LINEAR").unwrap();
+    assert!(!linear.batch);
+}
+
+#[test]
+fn score_info_lists_components_that_add_up_to_the_total() {
+    let result = evaluate_allow_partial(&all_fields_nv());
+    for name in ["AHEI", "AHEIP", "DII", "HCNS", "HEI_2020", "MEDI_V2", "PHDI"] {
+        let info = &result.scores[name];
+        let total: f64 = info.components.iter().map(|c| c.points).sum();
+        assert!(!info.components.is_empty(), "{} has no components", name);
+        assert!((total - info.value.unwrap()).abs() < 1e-9, "{}", name);
+        for c in &info.components {
+            assert!(c.points <= c.max_points + 1e-9, "{} {}", name, c.name);
+        }
+    }
+
+    let hei = &result.scores["HEI_2020"].components[0];
+    assert_eq!(hei.name, "total_fruits");
+    assert_eq!(hei.standard, 0.8);
+    assert!(result.scores["DASH_LINEAR"].components.is_empty());
+}
+
+// Every field `name` requires set to `intake`, with no energy.
+fn zero_energy_nv(name: &str, intake: f64) -> NutritionVector {
+    let mut nv = NutritionVector::default();
+    for field in dietarycodex::contracts::required_fields(name) {
+        *nv.field_mut(field).unwrap() = Some(intake);
+    }
+    nv.energy = Some(0.0);
+    nv.sex = Some(1.0);
+    nv
+}
+
+fn assert_needs_positive_energy(name: &str, nv: &NutritionVector) {
+    let info = &evaluate_allow_partial(nv).scores[name];
+    assert_eq!(info.value, None, "{}", name);
+    assert_eq!(
+        info.explanation.as_deref(),
+        Some("not applicable: energy must be positive"),
+        "{}",
+        name
+    );
+}
+
+#[test]
+fn hei_needs_positive_energy() {
+    for intake in [0.0, 1.0] {
+        for name in ["HEI_2015", "HEI_2020"] {
+            assert_needs_positive_energy(name, &zero_energy_nv(name, intake));
+        }
+        let mut toddler = zero_energy_nv("HEI_TODDLERS_2020", intake);
+        toddler.age = Some(1.5);
+        assert_needs_positive_energy("HEI_TODDLERS_2020", &toddler);
+    }
+}
+
+#[test]
+fn ahei_needs_positive_energy() {
+    for intake in [0.0, 1.0] {
+        assert_needs_positive_energy("AHEI", &zero_energy_nv("AHEI", intake));
+    }
+}
+
+#[test]
+fn aheip_needs_positive_energy() {
+    for intake in [0.0, 1.0] {
+        assert_needs_positive_energy("AHEIP", &zero_energy_nv("AHEIP", intake));
+    }
+}
+
+#[test]
+fn phdi_needs_positive_energy() {
+    for intake in [0.0, 1.0] {
+        assert_needs_positive_energy("PHDI", &zero_energy_nv("PHDI", intake));
+    }
+}
+
+#[test]
+fn phdi_energy_adjusted_needs_positive_energy() {
+    for intake in [0.0, 1.0] {
+        let nv = zero_energy_nv("PHDI_ENERGY_ADJUSTED", intake);
+        assert_needs_positive_energy("PHDI_ENERGY_ADJUSTED", &nv);
+    }
+}
@@ -0,0 +1,333 @@
+use dietarycodex::energy::EnergyAdjustment;
+use dietarycodex::eval::evaluate_batch_allow_partial;
+use dietarycodex::food_patterns::FoodPatternTable;
+use dietarycodex::nhanes_ingest::RecallDays;
+use dietarycodex::nutrition_vector::NutritionVector;
+use dietarycodex::output::{OutputFormat, TableLayout};
+use dietarycodex::scores::dii;
+use dietarycodex::scores::registry::cohort_fields;
+use dietarycodex::stream::{
+    write_json_stream, write_table_stream, ScoreStream, StreamOptions, CHUNK_ROWS,
+};
+use dietarycodex::tabular::{record_map, score_delimited};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn template_text(rows: usize) -> String {
+    let text = std::fs::read_to_string("../data/template.csv").unwrap();
+    let mut lines = text.lines();
+    let header = lines.next().unwrap();
+    let first = lines.next().unwrap();
+    let mut out = format!("id,{}\n", header);
+    for i in 0..rows {
+        out.push_str(&format!("{},{}\n", i + 1, first));
+    }
+    out
+}
+
+#[test]
+fn stream_matches_in_memory_scoring() {
+    let text = template_text(3);
+    let streamed: Vec<_> = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b','))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let whole = score_delimited(&text, b',', RecallDays::default()).unwrap();
+    assert_eq!(streamed.len(), 3);
+    for (a, b) in streamed.iter().zip(&whole) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(
+            a.result.scores["HEI_2020"].value,
+            b.result.scores["HEI_2020"].value
+        );
+    }
+    assert_eq!(streamed[2].id["id"], json!(3));
+}
+
+#[test]
+fn coverage_is_accumulated_while_reading() {
+    let text = "id,energy,sodium,WTDRD1\n1,2000,3000,2\n,1800,,1\n";
+    let mut stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    assert!(stream.next().is_some());
+    assert_eq!(stream.coverage().rows, 1);
+    assert!(stream.next().is_some());
+    assert!(stream.next().is_none());
+    let coverage = stream.coverage();
+    assert_eq!(coverage.rows, 2);
+    assert_eq!(coverage.missing_ids, 1);
+    let sodium = coverage
+        .most_missing
+        .iter()
+        .find(|(f, _)| f == "sodium")
+        .unwrap();
+    assert_eq!(sodium.1, 1);
+}
+
+#[test]
+fn coverage_flags_identifiers_shared_by_several_rows() {
+    let text = "id,energy\n1,2000\n2,1800\n1,2100\n,1900\n1,2200\n";
+    let mut stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    for row in stream.by_ref() {
+        row.unwrap();
+    }
+    let coverage = stream.coverage();
+    assert_eq!(coverage.missing_ids, 1);
+    assert_eq!(coverage.duplicate_ids.len(), 1);
+    assert_eq!(coverage.duplicate_ids["1"], vec![0, 2, 4]);
+}
+
+#[test]
+fn weighted_means_use_running_sums() {
+    let mut text = template_text(0).lines().next().unwrap().to_string();
+    text = text.replacen("id,", "id,WTDRD1,", 1) + "\n";
+    let row = template_text(1).lines().nth(1).unwrap().to_string();
+    text.push_str(&row.replacen("1,", "1,1,", 1));
+    text.push('\n');
+    text.push_str(&row.replacen("1,", "2,3,", 1));
+    text.push('\n');
+    let mut stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    let rows: Vec<_> = stream.by_ref().map(Result::unwrap).collect();
+    let coverage = stream.coverage();
+    let hei = &coverage.weighted["HEI_2020"];
+    assert_eq!(hei.n, 2);
+    assert_eq!(hei.weight_total, 4.0);
+    let value = rows[0].result.scores["HEI_2020"].value.unwrap();
+    assert!((hei.mean - value).abs() < 1e-9);
+    assert!((hei.percentiles["p50"] - value).abs() < 1e-9);
+}
+
+#[test]
+fn design_columns_are_written_after_the_identifiers() {
+    let text = "SEQN,wtdrd1,SDMVPSU,SDMVSTRA,energy\n83732,1000.5,1,125,2000\n83733,,2,126,1800\n";
+    let stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    assert_eq!(stream.design_columns(), ["WTDRD1", "SDMVPSU", "SDMVSTRA"]);
+    let layout = TableLayout::from_registry(stream.id_columns(), stream.design_columns(), false);
+    let mut buf = Vec::new();
+    let mut sink = OutputFormat::Csv.table_sink(&mut buf).unwrap();
+    write_table_stream(stream, &layout, sink.as_mut()).unwrap();
+    drop(sink);
+    let out = String::from_utf8(buf).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert!(lines[0].starts_with("row,SEQN,WTDRD1,SDMVPSU,SDMVSTRA,"));
+    assert!(lines[1].starts_with("0,83732,1000.5,1,125,"));
+    assert!(lines[2].starts_with("1,83733,,2,126,"));
+}
+
+#[test]
+fn table_stream_writes_every_chunk_with_one_header() {
+    let text = template_text(CHUNK_ROWS + 5);
+    let stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    let layout = TableLayout::from_registry(stream.id_columns(), &[], false);
+    let mut buf = Vec::new();
+    let mut sink = OutputFormat::Csv.table_sink(&mut buf).unwrap();
+    let written = write_table_stream(stream, &layout, sink.as_mut()).unwrap();
+    drop(sink);
+    assert_eq!(written, CHUNK_ROWS + 5);
+    let out = String::from_utf8(buf).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), CHUNK_ROWS + 6);
+    assert!(lines[0].starts_with("row,id,"));
+    assert_eq!(lines.iter().filter(|l| l.starts_with("row,")).count(), 1);
+    assert!(lines[CHUNK_ROWS + 5].starts_with(&format!("{},{},", CHUNK_ROWS + 4, CHUNK_ROWS + 5)));
+}
+
+#[test]
+fn empty_tables_still_get_a_header() {
+    let text = "id,energy\n";
+    let stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    let layout = TableLayout::from_registry(stream.id_columns(), &[], false);
+    let mut buf = Vec::new();
+    let mut sink = OutputFormat::Csv.table_sink(&mut buf).unwrap();
+    assert_eq!(
+        write_table_stream(stream, &layout, sink.as_mut()).unwrap(),
+        0
+    );
+    drop(sink);
+    let out = String::from_utf8(buf).unwrap();
+    assert!(out.starts_with("row,id,AHEI,AHEI_valid,"));
+    assert!(out.ends_with(",ACS2020_V3,ACS2020_V3_valid,missing_fields\n"));
+    assert_eq!(TableLayout::from_rows(&[], false), TableLayout::default());
+}
+
+#[test]
+fn registry_layout_lists_components_no_row_has() {
+    // The first row supplies a single DII parameter.
+    let text = "id,fiber\n1,20\n";
+    let stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    let layout = TableLayout::from_registry(stream.id_columns(), &[], true);
+    let rows: Vec<_> = stream.map(Result::unwrap).collect();
+    let seen = TableLayout::from_rows(&rows, true);
+    let parts = |layout: &TableLayout| {
+        layout
+            .indices
+            .iter()
+            .find(|(name, _)| name == "DII")
+            .map(|(_, parts)| parts.len())
+    };
+    assert!(parts(&seen) < parts(&layout));
+    assert_eq!(parts(&layout), Some(dii::parameters().len()));
+    let hei = layout
+        .indices
+        .iter()
+        .find(|(n, _)| n == "HEI_2020")
+        .unwrap();
+    assert_eq!(hei.1.len(), 13);
+    assert!(layout.indices.iter().any(|(n, _)| n == "aMED_COHORT"));
+}
+
+#[test]
+fn cohort_scores_join_the_streamed_rows() {
+    let text = template_text(3);
+    let options = StreamOptions::new(b',');
+    let cohort = ScoreStream::new(text.as_bytes(), &options)
+        .unwrap()
+        .cohort_scores(None)
+        .unwrap();
+    let streamed: Vec<_> = ScoreStream::new(text.as_bytes(), &options)
+        .unwrap()
+        .with_cohort_scores(cohort)
+        .map(Result::unwrap)
+        .collect();
+
+    let mut lines = text.lines();
+    let headers: Vec<String> = lines
+        .next()
+        .unwrap()
+        .split(',')
+        .map(str::to_string)
+        .collect();
+    let batch: Vec<NutritionVector> = lines
+        .map(|line| {
+            let record = csv::StringRecord::from(line.split(',').collect::<Vec<_>>());
+            NutritionVector::from_partial_map(&record_map(&headers, &record)).0
+        })
+        .collect();
+    let whole = evaluate_batch_allow_partial(&batch);
+    assert!(streamed[0].result.scores["DASH"].value.is_some());
+    for (row, expected) in streamed.iter().zip(&whole) {
+        assert_eq!(row.result.ordered_names, expected.ordered_names);
+        for name in ["DASH", "aMED_COHORT", "ACS2020_V1"] {
+            assert_eq!(row.result.scores[name], expected.scores[name]);
+        }
+    }
+}
+
+#[test]
+fn cohort_pass_keeps_only_the_fields_cohort_scorers_read() {
+    let fields = cohort_fields();
+    assert!(fields.contains(&"sodium") && fields.contains(&"sex"));
+    assert!(!fields.contains(&"fiber"));
+    assert!(fields.len() < NutritionVector::all_field_names().len() / 2);
+}
+
+#[test]
+fn borrowed_records_score_like_owned_ones() {
+    let mut full = HashMap::new();
+    for (field, value) in [
+        ("energy", 2000.0),
+        ("sodium", 2300.0),
+        ("fruit_serv", 2.0),
+        ("veg_serv", 3.0),
+        ("nuts_legumes_serv", 1.0),
+        ("whole_grains", 40.0),
+        ("low_fat_dairy_serv", 1.0),
+        ("red_processed_meat_serv", 0.5),
+        ("ssb_serv", 0.0),
+    ] {
+        full.insert(field.to_string(), json!(value));
+    }
+    let mut no_energy = full.clone();
+    no_energy.remove("energy");
+    let records = vec![full.clone(), full, no_energy];
+    let headers: Vec<String> = records[0].keys().cloned().collect();
+    let options = StreamOptions::default();
+    let adjustment = Some(EnergyAdjustment::Residual);
+
+    let cohort =
+        ScoreStream::from_records(records.iter().map(Ok), &headers, HashMap::new(), &options)
+            .cohort_scores(adjustment)
+            .unwrap();
+    assert_eq!(cohort.len(), 3);
+    let borrowed: Vec<_> =
+        ScoreStream::from_records(records.iter().map(Ok), &headers, HashMap::new(), &options)
+            .with_cohort_scores(cohort)
+            .map(Result::unwrap)
+            .collect();
+    let cohort =
+        ScoreStream::from_records(records.iter().map(Ok), &headers, HashMap::new(), &options)
+            .cohort_scores(adjustment)
+            .unwrap();
+    let owned: Vec<_> = ScoreStream::from_records(
+        records.into_iter().map(Ok),
+        &headers,
+        HashMap::new(),
+        &options,
+    )
+    .with_cohort_scores(cohort)
+    .map(Result::unwrap)
+    .collect();
+
+    for (a, b) in borrowed.iter().zip(&owned) {
+        assert_eq!(a.result.scores["DASH"], b.result.scores["DASH"]);
+    }
+    assert!(owned[0].result.scores["DASH"].value.is_some());
+    assert_eq!(
+        owned[2].result.scores["DASH"].explanation.as_deref(),
+        Some("missing fields: energy")
+    );
+    assert!(owned[2]
+        .result
+        .errors
+        .iter()
+        .any(|e| e.index == "DASH" && e.missing_fields == vec!["energy"]));
+}
+
+#[test]
+fn json_stream_is_one_array() {
+    let text = template_text(2);
+    let stream = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    let mut buf = Vec::new();
+    assert_eq!(write_json_stream(stream, &mut buf).unwrap(), 2);
+    let parsed: Value = serde_json::from_slice(&buf).unwrap();
+    assert_eq!(parsed.as_array().unwrap().len(), 2);
+    assert_eq!(parsed[1]["id"]["id"], json!(2));
+    assert_eq!(parsed[1]["row"], json!(1));
+}
+
+#[test]
+fn match_thresholds_apply_per_stream() {
+    let text = "id,beeef_g\n1,100\n2,100\n";
+    let loose = StreamOptions {
+        match_threshold: 0.75,
+        ..StreamOptions::new(b',')
+    };
+    let mut strict = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    let mut loose = ScoreStream::new(text.as_bytes(), &loose).unwrap();
+    // Rows of the two streams are scored alternately in one process.
+    for _ in 0..2 {
+        let trace = strict.next().unwrap().unwrap().result.trace;
+        assert!(!trace.translation_log.contains_key("red_meat"));
+        assert_eq!(trace.food_suggestions[0].column, "beeef_g");
+        let trace = loose.next().unwrap().unwrap().result.trace;
+        assert_eq!(trace.translation_log["red_meat"].source, ["beeef_g > beef"]);
+        assert!(trace.food_suggestions.is_empty());
+    }
+}
+
+#[test]
+fn food_pattern_tables_apply_per_stream() {
+    let fped = "Food code,Main food description,PF_LEGUMES\n41101000,\"Beans, dry, cooked\",2.0\n";
+    let table = FoodPatternTable::from_reader(fped.as_bytes(), b',').unwrap();
+    let text = "id,41101000_g\n1,150\n";
+    let with_table = StreamOptions {
+        food_patterns: Some(Arc::new(table)),
+        ..StreamOptions::new(b',')
+    };
+    let mut plain = ScoreStream::new(text.as_bytes(), &StreamOptions::new(b',')).unwrap();
+    let mut fped = ScoreStream::new(text.as_bytes(), &with_table).unwrap();
+    let trace = plain.next().unwrap().unwrap().result.trace;
+    assert!(!trace.translation_log.contains_key("pf_total"));
+    let trace = fped.next().unwrap().unwrap().result.trace;
+    assert_eq!(trace.translation_log["pf_total"].source, ["41101000_g"]);
+}
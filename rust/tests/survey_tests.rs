@@ -0,0 +1,84 @@
+use dietarycodex::nhanes_ingest::RecallDays;
+use dietarycodex::survey::{
+    design_variables, dietary_weight, weighted_summary, QuantileSketch, SKETCH_CAPACITY,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn nhanes_row() -> HashMap<String, Value> {
+    let mut row = HashMap::new();
+    row.insert("seqn".to_string(), json!(83732));
+    row.insert("WTDRD1".to_string(), json!(1000.0));
+    row.insert("WTDR2D".to_string(), json!(1500.0));
+    row.insert("SDMVPSU".to_string(), json!(1));
+    row.insert("SDMVSTRA".to_string(), json!(125));
+    row.insert("DR1TKCAL".to_string(), json!(2000.0));
+    row
+}
+
+#[test]
+fn design_columns_are_passed_through() {
+    let design = design_variables(&nhanes_row());
+    assert_eq!(design.len(), 4);
+    // SEQN is carried as the identifier.
+    assert!(!design.contains_key("SEQN"));
+    assert_eq!(design["SDMVSTRA"], json!(125));
+    assert!(!design.contains_key("DR1TKCAL"));
+}
+
+#[test]
+fn weight_follows_recall_days() {
+    let mut design = design_variables(&nhanes_row());
+    assert_eq!(dietary_weight(&design, RecallDays::Day1Only), Some(1000.0));
+    assert_eq!(dietary_weight(&design, RecallDays::MeanOfDays), Some(1500.0));
+    design.remove("WTDR2D");
+    assert_eq!(dietary_weight(&design, RecallDays::RequireBoth), Some(1000.0));
+}
+
+#[test]
+fn weighted_summary_uses_weights() {
+    let pairs = [(40.0, 1.0), (60.0, 3.0), (80.0, 0.0), (f64::NAN, 2.0)];
+    let summary = weighted_summary(&pairs).unwrap();
+    assert_eq!(summary.n, 2);
+    assert_eq!(summary.weight_total, 4.0);
+    assert_eq!(summary.mean, 55.0);
+    assert_eq!(summary.percentiles["p5"], 40.0);
+    assert_eq!(summary.percentiles["p25"], 40.0);
+    assert_eq!(summary.percentiles["p50"], 60.0);
+    assert_eq!(summary.percentiles["p95"], 60.0);
+    assert!(weighted_summary(&[(50.0, 0.0)]).is_none());
+}
+
+#[test]
+fn sketch_matches_exact_summary_until_full() {
+    let pairs: Vec<(f64, f64)> = (0..100).map(|i| (i as f64, 1.0 + (i % 3) as f64)).collect();
+    let mut sketch = QuantileSketch::default();
+    for &(v, w) in &pairs {
+        sketch.add(v, w);
+    }
+    sketch.add(f64::NAN, 1.0);
+    sketch.add(10.0, 0.0);
+    assert_eq!(sketch.summary(), weighted_summary(&pairs));
+    assert!(QuantileSketch::default().summary().is_none());
+}
+
+#[test]
+fn sketch_percentiles_stay_close_on_long_streams() {
+    let n = SKETCH_CAPACITY * 50;
+    let mut sketch = QuantileSketch::default();
+    for i in 0..n {
+        // Spread the values over 0..100 out of order.
+        sketch.add(((i * 7919) % n) as f64 * 100.0 / n as f64, 1.0);
+    }
+    let summary = sketch.summary().unwrap();
+    assert_eq!(summary.n, n);
+    assert!((summary.mean - 50.0).abs() < 0.01);
+    for (key, expected) in [("p5", 5.0), ("p50", 50.0), ("p95", 95.0)] {
+        assert!(
+            (summary.percentiles[key] - expected).abs() < 1.0,
+            "{} = {}",
+            key,
+            summary.percentiles[key]
+        );
+    }
+}
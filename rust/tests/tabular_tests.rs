@@ -0,0 +1,57 @@
+use dietarycodex::nhanes_ingest::RecallDays;
+use dietarycodex::tabular::{delimiter_for, read_delimited, score_delimited};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn delimiter_follows_extension() {
+    assert_eq!(delimiter_for(Path::new("intake.tsv")), b'\t');
+    assert_eq!(delimiter_for(Path::new("intake.TAB")), b'\t');
+    assert_eq!(delimiter_for(Path::new("intake.csv")), b',');
+}
+
+#[test]
+fn cells_are_typed_and_missing_markers_dropped() {
+    let (rows, units) = read_delimited(
+        "id,energy,sodium\r\nP-1, 2000 ,NA\r\n,,\r\n7,1800.5,\r\n",
+        b',',
+    )
+    .unwrap();
+    assert!(units.is_empty());
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["id"], json!("P-1"));
+    assert_eq!(rows[0]["energy"], json!(2000));
+    assert!(!rows[0].contains_key("sodium"));
+    assert_eq!(rows[1]["id"], json!(7));
+    assert_eq!(rows[1]["energy"], json!(1800.5));
+}
+
+#[test]
+fn template_rows_score_like_the_fixture() {
+    let text = std::fs::read_to_string("../data/template.csv").unwrap();
+    let rows = score_delimited(&text, b',', RecallDays::default()).unwrap();
+    assert!(!rows.is_empty());
+    for row in &rows {
+        assert!(row.result.trace.missing_fields.is_empty());
+        assert!(row.result.scores["HEI_2020"].value.is_some());
+    }
+}
+
+#[test]
+fn in_memory_tables_get_the_cohort_scores() {
+    let text = std::fs::read_to_string("../data/template.csv").unwrap();
+    let rows = score_delimited(&text, b',', RecallDays::default()).unwrap();
+    for row in &rows {
+        assert!(row.result.ordered_names.contains(&"DASH".to_string()));
+        assert!(row.result.scores["DASH"].value.is_some());
+    }
+}
+
+#[test]
+fn tsv_tables_resolve_nhanes_headers() {
+    let text = "SEQN\tDR1TKCAL\tDR2TKCAL\n83732\t2000\t2400\n";
+    let rows = score_delimited(text, b'\t', RecallDays::MeanOfDays).unwrap();
+    assert_eq!(rows[0].id["SEQN"], json!(83732));
+    assert_eq!(rows[0].result.trace.recall_days, vec![1, 2]);
+    assert!(rows[0].result.trace.used_fields.contains(&"energy"));
+}
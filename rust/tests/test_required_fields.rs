@@ -8,7 +8,7 @@ fn template_contains_required_fields() {
     let headers: Vec<&str> = header_line.split(',').map(|s| s.trim()).collect();
     for field in NutritionVector::all_field_names() {
         assert!(
-            headers.contains(&field),
+            headers.contains(field),
             "template.csv missing required column {}",
             field
         );
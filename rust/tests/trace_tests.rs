@@ -26,7 +26,7 @@ fn trace_consistency() {
 
     let dash_reason = result
         .scores
-        .get("DASH")
+        .get("DASH_LINEAR")
         .unwrap()
         .explanation
         .as_ref()
@@ -0,0 +1,160 @@
+use dietarycodex::food_matching::DEFAULT_MATCH_THRESHOLD;
+use dietarycodex::nhanes_ingest::RecallDays;
+use dietarycodex::nutrition_vector::NutritionVector;
+use dietarycodex::tabular::{read_delimited, score_delimited};
+use dietarycodex::units::{
+    canonical_unit, conversion_factor, normalize_unit, split_unit_suffix, to_canonical, Converted,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn map(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+#[test]
+fn units_are_normalised_and_scaled() {
+    assert_eq!(canonical_unit("sodium"), Some("mg"));
+    assert_eq!(normalize_unit(" UG "), Some("mcg"));
+    assert_eq!(normalize_unit("kJ"), Some("kj"));
+    assert_eq!(normalize_unit("cup_eq"), None);
+    assert_eq!(conversion_factor("g", "mg"), Some(1000.0));
+    assert_eq!(conversion_factor("mcg", "mg"), Some(0.001));
+    assert!((conversion_factor("kj", "kcal").unwrap() - 1.0 / 4.184).abs() < 1e-12);
+    assert_eq!(conversion_factor("g", "kcal"), None);
+    assert_eq!(split_unit_suffix("Sodium_G"), Some(("Sodium", "g")));
+    assert_eq!(split_unit_suffix("selenium_mcg"), Some(("selenium", "mcg")));
+    assert_eq!(split_unit_suffix("_g"), None);
+}
+
+#[test]
+fn unverifiable_labels_are_kept_but_wrong_kinds_rejected() {
+    assert_eq!(
+        to_canonical("f_total", "f_total", 1.5, Some("cups")),
+        Converted::Unchanged(1.5)
+    );
+    assert!(matches!(
+        to_canonical("sodium", "sodium", 2.0, Some("cups")),
+        Converted::Mismatch(_)
+    ));
+    assert!(matches!(
+        to_canonical("energy", "energy", 2.0, Some("g")),
+        Converted::Mismatch(_)
+    ));
+}
+
+#[test]
+fn suffixed_columns_are_converted_and_traced() {
+    let (nv, trace) = NutritionVector::from_partial_map(&map(&[
+        ("sodium_g", json!(2.3)),
+        ("energy_kj", json!(8368.0)),
+        ("vit_c_mg", json!(90.0)),
+    ]));
+    assert!((nv.sodium.unwrap() - 2300.0).abs() < 1e-9);
+    assert!((nv.energy.unwrap() - 2000.0).abs() < 1e-9);
+    assert_eq!(nv.vitamin_c, Some(90.0));
+    assert_eq!(trace.unit_conversions.len(), 2);
+    let sodium = trace
+        .unit_conversions
+        .iter()
+        .find(|c| c.field == "sodium")
+        .unwrap();
+    assert_eq!((sodium.from, sodium.to, sodium.factor), ("g", "mg", 1000.0));
+    assert_eq!(sodium.column, "sodium_g");
+    assert!(trace
+        .aliases_applied
+        .contains(&("sodium_g".to_string(), "sodium")));
+}
+
+#[test]
+fn strict_maps_convert_suffixed_columns_too() {
+    let mut data: HashMap<String, f64> = NutritionVector::all_field_names()
+        .iter()
+        .filter(|f| !["sodium", "energy"].contains(f))
+        .map(|f| (f.to_string(), 1.0))
+        .collect();
+    data.insert("sodium_g".to_string(), 2.3);
+    data.insert("energy_kj".to_string(), 8368.0);
+    let nv = NutritionVector::from_map(&data).expect("suffixed columns resolve");
+    assert!((nv.sodium.unwrap() - 2300.0).abs() < 1e-9);
+    assert!((nv.energy.unwrap() - 2000.0).abs() < 1e-9);
+}
+
+#[test]
+fn declared_units_override_and_mismatches_drop_values() {
+    let units: HashMap<String, String> = [("sodium", "g"), ("calcium", "cups")]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let (nv, trace) = NutritionVector::from_partial_map_with_units(
+        &map(&[("sodium", json!(2.0)), ("calcium", json!(3.0))]),
+        &units,
+        DEFAULT_MATCH_THRESHOLD,
+        None,
+    );
+    assert_eq!(nv.sodium, Some(2000.0));
+    assert_eq!(nv.calcium, None);
+    assert_eq!(trace.unit_mismatches.len(), 1);
+    assert_eq!(trace.unit_mismatches[0].unit, "cups");
+    assert_eq!(trace.unit_mismatches[0].expected, "mg");
+    assert!(trace.missing_fields.contains(&"calcium"));
+}
+
+#[test]
+fn units_row_under_the_header_is_applied() {
+    let text = "id,sodium,energy\n,g,kJ\n1,2.3,8368\n";
+    let (rows, units) = read_delimited(text, b',').unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(units["energy"], "kJ");
+    assert!(!units.contains_key("id"));
+
+    let scored = score_delimited(text, b',', RecallDays::default()).unwrap();
+    assert_eq!(scored.len(), 1);
+    assert_eq!(scored[0].id["id"], json!(1));
+    assert_eq!(scored[0].result.trace.unit_conversions.len(), 2);
+    // A sodium intake of 2.3 g must not pass as 2.3 mg.
+    let nv = NutritionVector::from_partial_map_with_units(
+        &rows[0],
+        &units,
+        DEFAULT_MATCH_THRESHOLD,
+        None,
+    )
+    .0;
+    assert!((nv.sodium.unwrap() - 2300.0).abs() < 1e-9);
+}
+
+fn fdc_food(nutrients: &[(&str, &str, f64)]) -> String {
+    let items: Vec<Value> = nutrients
+        .iter()
+        .map(|(name, unit, amount)| {
+            json!({"nutrient": {"name": name, "unitName": unit}, "amount": amount})
+        })
+        .collect();
+    json!({ "foodNutrients": items }).to_string()
+}
+
+#[test]
+fn fdc_amounts_follow_unit_name() {
+    let food = fdc_food(&[
+        ("Energy", "kJ", 418.4),
+        ("Energy", "KCAL", 100.0),
+        ("Sodium, Na", "G", 0.5),
+        ("Selenium, Se", "UG", 20.0),
+        ("Calcium, Ca", "IU", 3.0),
+    ]);
+    let (nv, trace) = NutritionVector::from_fdc_json_traced(&food).unwrap();
+    assert_eq!(nv.energy, Some(100.0));
+    assert_eq!(nv.sodium, Some(500.0));
+    assert_eq!(nv.selenium, Some(20.0));
+    assert_eq!(nv.calcium, None);
+    assert_eq!(trace.unit_conversions.len(), 1);
+    assert_eq!(trace.unit_conversions[0].field, "sodium");
+    assert_eq!(trace.unit_mismatches[0].field, "calcium");
+
+    let kj_only = fdc_food(&[("Energy", "kJ", 418.4)]);
+    let nv = NutritionVector::from_fdc_json(&kj_only).unwrap();
+    assert!((nv.energy.unwrap() - 100.0).abs() < 1e-9);
+}
@@ -0,0 +1,456 @@
+use dietarycodex::nutrition_vector::NutritionVector;
+use dietarycodex::scores::acs2020::{Acs2020CohortScorer, Acs2020Version};
+use dietarycodex::scores::ahei::{Ahei, GRAMS_PER_DRINK};
+use dietarycodex::scores::aheip::AheipScorer;
+use dietarycodex::scores::amed::AMedCohortScorer;
+use dietarycodex::scores::dash::DashCohortScorer;
+use dietarycodex::scores::dii::DiiScorer;
+use dietarycodex::scores::hei::{Hei2015Scorer, Hei2020Scorer, HeiToddlers2020Scorer};
+use dietarycodex::scores::medi::{MediScorer, MediV2Scorer};
+use dietarycodex::scores::phdi::{self, PhdiEnergyAdjustedScorer, PhdiScorer};
+use dietarycodex::scores::{BatchScore, DietScore};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+
+fn read_csv(path: &str) -> (Vec<String>, Vec<HashMap<String, f64>>) {
+    let text = fs::read_to_string(path).expect("read validation csv");
+    let mut lines = text.lines();
+    let headers: Vec<String> = lines
+        .next()
+        .expect("empty csv")
+        .split(',')
+        .map(|h| h.trim().to_string())
+        .collect();
+    let rows = lines
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            headers
+                .iter()
+                .cloned()
+                .zip(
+                    l.split(',')
+                        .map(|v| v.trim().parse::<f64>().unwrap_or(f64::NAN)),
+                )
+                .collect()
+        })
+        .collect();
+    (headers, rows)
+}
+
+fn assert_close(actual: f64, expected: f64, id: f64) {
+    assert!(
+        (actual - expected).abs() <= 1e-3 + 1e-3 * expected.abs(),
+        "row {}: expected {}, got {}",
+        id,
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn dii_matches_reference() {
+    let (headers, rows) = read_csv("../data/DII_validation_result.csv");
+    let end = headers.iter().position(|h| h == "EXP_DII_ALL").unwrap();
+    for row in rows {
+        let map: HashMap<String, Value> = headers[1..end]
+            .iter()
+            .map(|h| (h.clone(), Value::from(row[h])))
+            .collect();
+        let (nv, trace) = NutritionVector::from_partial_map(&map);
+        assert!(trace.translation_log.is_empty());
+        assert!(DiiScorer.explain(&nv).is_none());
+        assert_close(DiiScorer.evaluate(&nv), row["DII_ALL"], row["id"]);
+    }
+}
+
+/// Reference rows describe HEI inputs the way the R `dietaryindex` package
+/// does: food groups in cup/oz equivalents and the fatty acid ratio directly.
+fn hei_vector(row: &HashMap<String, f64>) -> NutritionVector {
+    let saturated = row["saturated_fat"];
+    NutritionVector {
+        energy: Some(row["kcal"]),
+        f_total: Some(row["total_fruit"]),
+        f_whole: Some(row["whole_fruit"]),
+        v_total: Some(row["total_vegetable"]),
+        v_greens_beans: Some(row["green_and_bean"]),
+        pf_total: Some(row["total_protein"]),
+        pf_seaplant: Some(row["seafood_plant_protein"]),
+        g_whole: Some(row["whole_grain"]),
+        d_total: Some(row["dairy"]),
+        mono_fat: Some(row["fatty_acid"] * saturated / 2.0),
+        pufa: Some(row["fatty_acid"] * saturated / 2.0),
+        saturated_fat: Some(saturated),
+        g_refined: Some(row["refined_grain"]),
+        sodium: Some(row["sodium"]),
+        add_sugars: Some(row["added_sugar"]),
+        age: row.get("age").copied(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn hei_2015_matches_reference() {
+    let (_, rows) = read_csv("../data/HEI2015_VALIDATION.csv");
+    for row in rows {
+        let nv = hei_vector(&row);
+        assert_close(
+            Hei2015Scorer.evaluate(&nv),
+            row["EXP_HEI2015_ALL"],
+            row["id"],
+        );
+        let columns = [
+            "TOTALFRT",
+            "FRT",
+            "VEG",
+            "GREENNBEAN",
+            "WHOLEGRAIN",
+            "DAIRY",
+            "TOTALPRO",
+            "SEAPLANTPRO",
+            "FATTYACID",
+            "REFINEDGRAIN",
+            "SODIUM",
+            "ADDEDSUGAR",
+            "SATFAT",
+        ];
+        let components = Hei2015Scorer.components(&nv);
+        assert_eq!(components.len(), columns.len());
+        for (component, column) in components.iter().zip(columns) {
+            assert_close(
+                component.points,
+                row[&format!("EXP_HEI2015_{column}")],
+                row["id"],
+            );
+        }
+    }
+}
+
+#[test]
+fn hei_2020_matches_reference() {
+    let (_, rows) = read_csv("../data/HEI2020_V2_VALIDATION.csv");
+    for row in rows.iter().filter(|r| r["age"] >= 2.0) {
+        let nv = hei_vector(row);
+        assert_close(
+            Hei2020Scorer.evaluate(&nv),
+            row["EXP_HEI2020_ALL"],
+            row["id"],
+        );
+    }
+}
+
+#[test]
+fn hei_2020_matches_reference_with_energy_shares() {
+    // This file gives sodium in g and added sugars / saturated fat as percent
+    // of energy.
+    let (_, rows) = read_csv("../data/HEI2020_VALIDATION.csv");
+    for row in rows.iter().filter(|r| r["age"] >= 2.0) {
+        let mut row = row.clone();
+        let kcal = row["kcal"];
+        row.insert("sodium".into(), row["sodium"] * 1000.0);
+        row.insert(
+            "added_sugar".into(),
+            row["added_sugar"] / 100.0 * kcal / 16.0,
+        );
+        row.insert(
+            "saturated_fat".into(),
+            row["saturated_fat"] / 100.0 * kcal / 9.0,
+        );
+        let nv = hei_vector(&row);
+        assert_close(
+            Hei2020Scorer.evaluate(&nv),
+            row["EXP_HEI2020_ALL"],
+            row["id"],
+        );
+    }
+}
+
+#[test]
+fn hei_toddlers_2020_matches_reference() {
+    let (_, rows) = read_csv("../data/HEI2020_V2_VALIDATION.csv");
+    let toddlers: Vec<_> = rows.iter().filter(|r| r["age"] < 2.0).collect();
+    assert!(!toddlers.is_empty());
+    for row in toddlers {
+        let nv = hei_vector(row);
+        assert!(HeiToddlers2020Scorer.not_applicable(&nv).is_none());
+        assert_close(
+            HeiToddlers2020Scorer.evaluate(&nv),
+            row["EXP_HEI2020_ALL"],
+            row["id"],
+        );
+    }
+}
+
+#[test]
+fn ahei_matches_reference() {
+    // PUFA and trans fat are given as percent of energy, alcohol in drinks.
+    let (_, rows) = read_csv("../data/AHEI_VALIDATION.csv");
+    for row in rows {
+        let kcal = row["kcal"];
+        let nv = NutritionVector {
+            sex: Some(row["gender"]),
+            energy: Some(kcal),
+            veg_serv: Some(row["vegetable"]),
+            fruit_serv: Some(row["fruit"]),
+            whole_grains: Some(row["whole_grain"]),
+            nuts_legumes_serv: Some(row["nut_legume"]),
+            epa_dha: Some(row["n3_fat"]),
+            pufa: Some(row["pufa"] / 100.0 * kcal / 9.0),
+            ssb_serv: Some(row["ssb_fruit_juice"]),
+            red_processed_meat_serv: Some(row["red_processed_meat"]),
+            trans_fat: Some(row["trans_fat"] / 100.0 * kcal / 9.0),
+            sodium: Some(row["sodium"]),
+            alcohol: Some(row["alcohol"] * GRAMS_PER_DRINK),
+            ..Default::default()
+        };
+        assert!(Ahei.not_applicable(&nv).is_none());
+        assert_close(Ahei.evaluate(&nv), row["EXP_AHEI_ALL"], row["id"]);
+    }
+}
+
+#[test]
+fn aheip_matches_reference() {
+    // Trans fat is given as percent of energy and both fat and meat
+    // components as ratios.
+    let (_, rows) = read_csv("../data/AHEIP_VALIDATION.csv");
+    for row in rows {
+        let kcal = row["kcal"];
+        let nv = NutritionVector {
+            energy: Some(kcal),
+            veg_serv: Some(row["vegetable"]),
+            fruit_serv: Some(row["whole_fruit"]),
+            poultry: Some(row["white_meat_red_meat"]),
+            fish: Some(0.0),
+            red_meat: Some(1.0),
+            fiber: Some(row["fiber"]),
+            trans_fat: Some(row["trans_fat"] / 100.0 * kcal / 9.0),
+            pufa: Some(row["poly_fat_sat_fat"]),
+            saturated_fat: Some(1.0),
+            calcium: Some(row["calcium"]),
+            folic_acid: Some(row["folate"]),
+            iron: Some(row["iron"]),
+            ..Default::default()
+        };
+        assert_close(AheipScorer.evaluate(&nv), row["EXP_AHEIP_ALL"], row["id"]);
+    }
+}
+
+#[test]
+fn amed_cohort_matches_reference() {
+    let (_, rows) = read_csv("../data/MED_VALIDATION.csv");
+    let batch: Vec<NutritionVector> = rows
+        .iter()
+        .map(|row| NutritionVector {
+            sex: Some(row["gender"]),
+            energy: Some(row["kcal"]),
+            total_fruits: Some(row["fruit"]),
+            vegetables: Some(row["vegetable"]),
+            whole_grains: Some(row["whole_grain"]),
+            legumes: Some(row["legume"]),
+            nuts: Some(row["nut"]),
+            fish: Some(row["fish"]),
+            red_meat: Some(row["red_processed_meat"]),
+            mono_fat: Some(row["monofat_satfat"]),
+            saturated_fat: Some(1.0),
+            alcohol: Some(row["alcohol"]),
+            ..Default::default()
+        })
+        .collect();
+    let scores = AMedCohortScorer::default().evaluate_batch(&batch);
+    for (row, score) in rows.iter().zip(scores) {
+        assert_close(score, row["EXP_MED_ALL"], row["id"]);
+    }
+}
+
+#[test]
+fn medi_matches_reference() {
+    // Servings per day, except alcohol in grams: one drink is 14 g.
+    let (_, rows) = read_csv("../data/MEDI_VALIDATION.csv");
+    for row in rows {
+        let nv = NutritionVector {
+            olive_oil_serv: Some(row["olive_oil"]),
+            fruit_serv: Some(row["fruit"]),
+            veg_serv: Some(row["vegetable"]),
+            legumes_serv: Some(row["legume"]),
+            nuts_serv: Some(row["nut"]),
+            fish_serv: Some(row["fish"]),
+            alcohol: Some(row["alcohol_g"]),
+            ssb_serv: Some(row["ssb"]),
+            sweets_serv: Some(row["sweets"]),
+            discret_fat_serv: Some(row["discret_fat"]),
+            red_processed_meat_serv: Some(row["red_processed_meat"]),
+            ..Default::default()
+        };
+        assert_close(MediScorer.evaluate(&nv), row["EXP_MEDI_ALL"], row["id"]);
+        assert_close(
+            MediV2Scorer.evaluate(&nv),
+            row["EXP_MEDI_V2_ALL"],
+            row["id"],
+        );
+    }
+}
+
+#[test]
+fn dash_matches_reference() {
+    let (_, rows) = read_csv("../data/DASH_VALIDATION.csv");
+    let batch: Vec<NutritionVector> = rows
+        .iter()
+        .map(|row| NutritionVector {
+            sex: Some(row["gender"]),
+            energy: Some(row["kcal"]),
+            fruit_serv: Some(row["whole_fruit"]),
+            veg_serv: Some(row["vegetable"]),
+            nuts_legumes_serv: Some(row["nut_legume"]),
+            whole_grains: Some(row["whole_grain"]),
+            low_fat_dairy_serv: Some(row["low_fat_dairy"]),
+            sodium: Some(row["sodium"]),
+            red_processed_meat_serv: Some(row["red_processed_meat"]),
+            ssb_serv: Some(row["ssb"]),
+            ..Default::default()
+        })
+        .collect();
+    let scores = DashCohortScorer::default().evaluate_batch(&batch);
+    for (row, score) in rows.iter().zip(scores) {
+        assert_close(score, row["EXP_DASH_ALL"], row["id"]);
+    }
+}
+
+fn acs2020_matches_reference(path: &str, version: Acs2020Version) {
+    let (_, rows) = read_csv(path);
+    let batch: Vec<NutritionVector> = rows
+        .iter()
+        .map(|row| NutritionVector {
+            sex: Some(row["gender"]),
+            energy: Some(row["kcal"]),
+            veg_serv: Some(row["vegetable"]),
+            veg_variety: Some(row["vegetable_unique"]),
+            fruit_serv: Some(row["fruit"]),
+            fruit_variety: Some(row["fruit_unique"]),
+            whole_grains: Some(row["whole_grain"]),
+            red_processed_meat_serv: Some(row["red_meat"]),
+            hpf_rg_serv: Some(row["process_food"]),
+            ssb_serv: Some(row["ssb"]),
+            ..Default::default()
+        })
+        .collect();
+    let scores = Acs2020CohortScorer(version).evaluate_batch(&batch);
+    for (row, score) in rows.iter().zip(scores) {
+        assert_close(score, row["EXP_ACS_ALL"], row["id"]);
+    }
+}
+
+#[test]
+fn acs2020_v1_matches_reference() {
+    acs2020_matches_reference("../data/ACS2020_V1_validation.csv", Acs2020Version::V1);
+}
+
+#[test]
+fn acs2020_v2_matches_reference() {
+    acs2020_matches_reference("../data/ACS2020_V2_validation.csv", Acs2020Version::V2);
+}
+
+/// Food groups as grams per day; added fats and sugar from `fats_and_sugar`,
+/// which converts the reference columns to grams. Energy and every intake are
+/// multiplied by `scale`.
+fn phdi_vector<F>(row: &HashMap<String, f64>, scale: f64, fats_and_sugar: F) -> NutritionVector
+where
+    F: Fn(&str, f64) -> f64,
+{
+    let grams = |column: &str| Some(row[column] * scale);
+    let fat_sugar = |kind: &str, column: &str| Some(fats_and_sugar(kind, row[column]) * scale);
+    NutritionVector {
+        sex: Some(row["gender"]),
+        energy: grams("totalkcal_phdi"),
+        whole_grains: grams("wgrain_serv_phdi"),
+        starchy_veg: grams("starchy_veg_serv_phdi"),
+        vegetables: grams("veg_serv_phdi"),
+        total_fruits: grams("frt_serv_phdi"),
+        dairy: grams("dairy_serv_phdi"),
+        red_meat: grams("redproc_meat_serv_phdi"),
+        poultry: grams("poultry_serv_phdi"),
+        eggs: grams("egg_serv_phdi"),
+        fish: grams("fish_serv_phdi"),
+        nuts: grams("nuts_serv_phdi"),
+        legumes: grams("legumes_serv_phdi"),
+        soy: grams("soy_serv_phdi"),
+        added_fat_unsat: fat_sugar("fat", "added_fat_unsat_serv_phdi"),
+        added_fat_sat_trans: fat_sugar("fat", "added_fat_sat_trans_serv_phdi"),
+        added_sugar_g: fat_sugar("sugar", "added_sugar_serv_phdi"),
+        ..Default::default()
+    }
+}
+
+fn assert_phdi_components(nv: &NutritionVector, row: &HashMap<String, f64>) {
+    let columns = [
+        "wgrain",
+        "starchy_veg",
+        "veg",
+        "frt",
+        "dairy",
+        "redproc_meat",
+        "poultry",
+        "egg",
+        "fish",
+        "nuts",
+        "legumes",
+        "soy",
+        "added_fat_unsat",
+        "added_fat_sat",
+        "added_sugar",
+    ];
+    for (component, column) in phdi::component_scores(nv).iter().zip(columns) {
+        assert_close(
+            component.points,
+            row[&format!("exp_phdi_{column}")],
+            row["id"],
+        );
+    }
+}
+
+#[test]
+fn phdi_matches_reference() {
+    // Added fats and sugar are given as percent of energy.
+    let (_, rows) = read_csv("../data/PHDI_VALIDATION.csv");
+    for row in rows {
+        let kcal = row["totalkcal_phdi"];
+        let nv = phdi_vector(&row, 1.0, |kind, percent| {
+            let kcal_per_g = if kind == "fat" { 9.0 } else { 4.0 };
+            percent / 100.0 * kcal / kcal_per_g
+        });
+        assert!(PhdiScorer.not_applicable(&nv).is_none());
+        assert_close(PhdiScorer.evaluate(&nv), row["exp_phdi_all"], row["id"]);
+        assert_phdi_components(&nv, &row);
+    }
+}
+
+#[test]
+fn phdi_matches_reference_with_grams() {
+    let (_, rows) = read_csv("../data/PHDI_V2_VALIDATION.csv");
+    for row in rows {
+        let nv = phdi_vector(&row, 1.0, |_, grams| grams);
+        assert_close(PhdiScorer.evaluate(&nv), row["exp_phdi_all"], row["id"]);
+        assert_phdi_components(&nv, &row);
+    }
+}
+
+#[test]
+fn phdi_energy_adjusted_rescales_to_reference_energy() {
+    // The reference rows scored as reported at 2,500 kcal must score the
+    // same after energy adjustment when the whole diet is halved.
+    let (_, rows) = read_csv("../data/PHDI_V2_VALIDATION.csv");
+    for row in rows {
+        let to_reference = phdi::REFERENCE_KCAL / row["totalkcal_phdi"];
+        let reference = phdi_vector(&row, to_reference, |_, grams| grams);
+        let halved = phdi_vector(&row, to_reference / 2.0, |_, grams| grams);
+        assert_close(
+            PhdiEnergyAdjustedScorer.evaluate(&halved),
+            PhdiScorer.evaluate(&reference),
+            row["id"],
+        );
+        assert_close(
+            PhdiEnergyAdjustedScorer.evaluate(&reference),
+            PhdiScorer.evaluate(&reference),
+            row["id"],
+        );
+    }
+}
@@ -0,0 +1,31 @@
+import pandas as pd
+import pytest
+
+from compute.medi import calculate_medi, calculate_medi_v2
+
+
+def test_medi_matches_reference():
+    df = pd.read_csv("data/MEDI_VALIDATION.csv")
+    mapping = {
+        "olive_oil": "olive_oil_serv_medi",
+        "fruit": "frt_serv_medi",
+        "vegetable": "veg_serv_medi",
+        "legume": "legumes_serv_medi",
+        "nut": "nuts_serv_medi",
+        "fish": "fish_seafood_serv_medi",
+        "ssb": "ssb_serv_medi",
+        "sweets": "sweets_serv_medi",
+        "discret_fat": "discret_fat_serv_medi",
+        "red_processed_meat": "redproc_meat_serv_medi",
+    }
+    df = df.rename(columns=mapping)
+    # One standard drink is 14 g of alcohol.
+    df["alcohol_serv_medi"] = df["alcohol_g"] / 14
+    assert (
+        pytest.approx(calculate_medi(df).tolist(), rel=1e-3, abs=1e-3)
+        == df["EXP_MEDI_ALL"].tolist()
+    )
+    assert (
+        pytest.approx(calculate_medi_v2(df).tolist(), rel=1e-3, abs=1e-3)
+        == df["EXP_MEDI_V2_ALL"].tolist()
+    )